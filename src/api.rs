@@ -1,26 +1,50 @@
 use dioxus::prelude::*;
 
-use crate::shared::types::{DailyUsagePointDto, DataStatusDto, SchedulerStatusDto};
+use crate::shared::types::{
+    CoverageDto, CycleComparisonDto, CycleUsageDto, DailyUsagePointDto, DataStatusDto,
+    ForceRefreshDto, ForecastDto, GaugeMode, GaugeStyle, HealthDto, HistoryPageDto, LocaleConfig,
+    OptimizeDbDto, RawSmsDto, RefreshMode, RemainingSeriesDto, SchedulerStatusDto, SmsRequestDto,
+    TotalUsageDto, TrendPointDto, UsageStatsDto, WeekdayUsageDto,
+};
+
+/// Error message of server fns called while the DB connection isn't up, so the UI
+/// can tell a boot failure apart from an empty database.
+pub const DB_UNAVAILABLE: &str = "backend database not ready";
+
+#[cfg(feature = "server")]
+use crate::backend::usage::{self, clamp_usage_days, daily_usage_series, reduce_daily_usage};
+#[cfg(feature = "server")]
+use crate::shared::types::Granularity;
+#[cfg(feature = "server")]
+use dioxus::logger::tracing::{info, warn};
+
+#[cfg(feature = "server")]
+fn require_db(caller: &str) -> Result<&'static std::sync::Arc<crate::backend::Db>, ServerFnError> {
+    crate::backend::GLOBAL_DB.get().ok_or_else(|| {
+        warn!(caller, "DB not initialized");
+        ServerFnError::new(DB_UNAVAILABLE)
+    })
+}
 
 #[server(LatestDataStatus)]
 pub async fn latest_data_status() -> Result<Option<DataStatusDto>, ServerFnError> {
     #[cfg(feature = "server")]
     {
-        use crate::backend::GLOBAL_DB;
-
-        let Some(db) = GLOBAL_DB.get() else {
-            eprintln!("latest_data_status: DB not initialized");
-            return Ok(None);
-        };
-        match db.get_latest_data_status().await {
+        let db = require_db("latest_data_status")?;
+        match db.get_latest_data_status_cached().await {
             Ok(Some(r)) => Ok(Some(DataStatusDto {
                 remaining_percentage: r.remaining_percentage,
                 remaining_data_mb: r.remaining_data_mb,
                 date_time: r.date_time.to_rfc3339(),
+                id: r.id,
+                display_time: Some(crate::utils::format::format_local(
+                    &r.date_time.to_rfc3339(),
+                    &crate::backend::LOCALE,
+                )),
             })),
             Ok(None) => Ok(None),
             Err(e) => {
-                eprintln!("latest_data_status query error: {e}");
+                warn!(error = %e, "latest_data_status query failed");
                 Ok(None)
             }
         }
@@ -31,40 +55,320 @@ pub async fn latest_data_status() -> Result<Option<DataStatusDto>, ServerFnError
     }
 }
 
-#[server(GetSchedulerStatus)]
-pub async fn get_scheduler_status() -> Result<SchedulerStatusDto, ServerFnError> {
+/// Send the status request SMS, at most once per `WINDTRE_MIN_SMS_INTERVAL_SECS`.
+/// The reply is stored by the next scheduler run (or the SMS webhook). Admin only.
+#[server(RequestStatusSms)]
+pub async fn request_status_sms(
+    #[server(default)] token: String,
+) -> Result<SmsRequestDto, ServerFnError> {
     #[cfg(feature = "server")]
     {
-        use crate::backend::scheduler::{SCHED_HANDLE, STATUS};
+        use crate::backend::windtre::{request_data_status_sms, SmsRequestOutcome};
 
-        if let Some(st) = STATUS.get() {
-            let s = st.read().await.clone();
-            // Derive true running status from the join handle, if present
-            let running = if let Some(hcell) = SCHED_HANDLE.get() {
-                let h = hcell.read().await;
-                h.as_ref().map(|j| !j.is_finished()).unwrap_or(false)
+        require_admin(token).await?;
+        match request_data_status_sms().await {
+            Ok(SmsRequestOutcome::Sent) => Ok(SmsRequestDto {
+                sent: true,
+                message: "SMS sent".into(),
+            }),
+            Ok(SmsRequestOutcome::Throttled { retry_in_secs }) => Ok(SmsRequestDto {
+                sent: false,
+                message: format!("An SMS was sent recently, try again in {retry_in_secs}s"),
+            }),
+            Err(e) => {
+                warn!(error = %e, "request_status_sms failed");
+                Ok(SmsRequestDto {
+                    sent: false,
+                    message: format!("Failed to send SMS: {e}"),
+                })
+            }
+        }
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = token;
+        Ok(SmsRequestDto {
+            sent: false,
+            message: String::new(),
+        })
+    }
+}
+
+/// Ask the carrier for a new reading and store the reply. See `RefreshMode` for
+/// which mode to use behind proxies with short request timeouts. Admin only.
+#[server(ForceRefresh)]
+pub async fn force_refresh(
+    mode: RefreshMode,
+    #[server(default)] token: String,
+) -> Result<ForceRefreshDto, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        use crate::backend::scheduler;
+        require_admin(token).await?;
+        let db = require_db("force_refresh")?;
+        Ok(scheduler::force_refresh(db.clone(), mode).await)
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (mode, token);
+        Err(ServerFnError::new(
+            "force_refresh is only available on the server",
+        ))
+    }
+}
+
+/// Header carrying the admin token when it isn't passed as the `token` argument.
+#[cfg(feature = "server")]
+const ADMIN_TOKEN_HEADER: &str = "x-admin-token";
+
+/// Gate for mutating server fns: the `token` argument, or else the `X-Admin-Token`
+/// header, must match `ADMIN_TOKEN`.
+#[cfg(feature = "server")]
+async fn require_admin(token: String) -> Result<(), ServerFnError> {
+    if !token.is_empty() {
+        return check_admin_token(&token);
+    }
+    let headers: axum::http::HeaderMap = extract().await.unwrap_or_default();
+    let token = headers
+        .get(ADMIN_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    check_admin_token(token)
+}
+
+/// Compare `token` against `ADMIN_TOKEN`; without `ADMIN_TOKEN` admin calls are disabled.
+#[cfg(feature = "server")]
+fn check_admin_token(token: &str) -> Result<(), ServerFnError> {
+    match std::env::var("ADMIN_TOKEN") {
+        Ok(expected) if !expected.is_empty() => {
+            if crate::backend::secret_matches(token, &expected) {
+                Ok(())
             } else {
-                false
-            };
-            return Ok(SchedulerStatusDto {
-                started: s.started,
-                running,
-                db_url: s.db_url,
-                last_loop_at: s.last_loop_at,
-                last_event: s.last_event,
-                last_error: s.last_error,
-                next_iteration_at: s.next_iteration_at,
-            });
-        }
-        return Ok(SchedulerStatusDto {
-            started: false,
-            running: false,
-            db_url: String::new(),
-            last_loop_at: None,
-            last_event: Some("not started".into()),
-            last_error: None,
-            next_iteration_at: None,
-        });
+                Err(ServerFnError::new("unauthorized: invalid admin token"))
+            }
+        }
+        _ => Err(ServerFnError::new(
+            "admin endpoints are disabled (ADMIN_TOKEN not set)",
+        )),
+    }
+}
+
+/// Delete a stored reading. Admin only.
+#[server(DeleteReading)]
+pub async fn delete_reading(
+    id: i64,
+    #[server(default)] token: String,
+) -> Result<(), ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        require_admin(token).await?;
+        let db = require_db("delete_reading")?;
+        match db.delete_by_id(id).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(ServerFnError::new(format!("no reading with id {id}"))),
+            Err(e) => Err(ServerFnError::new(format!("delete failed: {e}"))),
+        }
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (id, token);
+        Ok(())
+    }
+}
+
+/// Correct the values of a stored reading. Admin only.
+#[server(UpdateReading)]
+pub async fn update_reading(
+    id: i64,
+    remaining_percentage: i32,
+    remaining_data_mb: i32,
+    #[server(default)] token: String,
+) -> Result<(), ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        require_admin(token).await?;
+        if !(0..=100).contains(&remaining_percentage) || remaining_data_mb < 0 {
+            return Err(ServerFnError::new(
+                "percentage must be 0-100 and MB must not be negative",
+            ));
+        }
+        let db = require_db("update_reading")?;
+        match db
+            .update_reading(id, remaining_percentage, remaining_data_mb)
+            .await
+        {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(ServerFnError::new(format!("no reading with id {id}"))),
+            Err(e) => Err(ServerFnError::new(format!("update failed: {e}"))),
+        }
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (id, remaining_percentage, remaining_data_mb, token);
+        Ok(())
+    }
+}
+
+/// Store a reading entered by hand (e.g. read off the carrier app when the SMS was
+/// missed). `date_time` is RFC 3339 and must not be in the future. Admin only.
+#[server(AddReading)]
+pub async fn add_reading(
+    remaining_percentage: i32,
+    remaining_data_mb: i32,
+    date_time: String,
+    #[server(default)] token: String,
+) -> Result<(), ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        use chrono::{DateTime, Utc};
+        require_admin(token).await?;
+        if !(0..=100).contains(&remaining_percentage) || remaining_data_mb < 0 {
+            return Err(ServerFnError::new(
+                "percentage must be 0-100 and MB must not be negative",
+            ));
+        }
+        let date_time = DateTime::parse_from_rfc3339(date_time.trim())
+            .map_err(|e| ServerFnError::new(format!("invalid timestamp {date_time:?}: {e}")))?
+            .with_timezone(&Utc);
+        if date_time > Utc::now() {
+            return Err(ServerFnError::new("timestamp is in the future"));
+        }
+        let db = require_db("add_reading")?;
+        match db
+            .insert_data_status(remaining_percentage, remaining_data_mb, date_time)
+            .await
+        {
+            Ok(0) => Err(ServerFnError::new(
+                "a reading with that timestamp is already stored",
+            )),
+            Ok(_) => {
+                // A backfilled reading can sit before a reset that's already recorded
+                if let Err(e) = db.rebuild_cycles().await {
+                    warn!(error = %e, "add_reading cycle rebuild failed");
+                }
+                Ok(())
+            }
+            Err(e) => Err(ServerFnError::new(format!("insert failed: {e}"))),
+        }
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (remaining_percentage, remaining_data_mb, date_time, token);
+        Ok(())
+    }
+}
+
+/// Most SMS `GetRawSmsSample` returns.
+#[cfg(feature = "server")]
+const MAX_RAW_SMS: usize = 50;
+
+/// The newest `limit` inbox SMS as received, without parsing (admin). With `redact`
+/// sender numbers keep only their last three digits.
+#[server(GetRawSmsSample)]
+pub async fn get_raw_sms_sample(
+    limit: usize,
+    #[server(default)] redact: bool,
+    #[server(default)] token: String,
+) -> Result<Vec<RawSmsDto>, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        require_admin(token).await?;
+        let smss = crate::backend::windtre::newest_smses(limit.clamp(1, MAX_RAW_SMS))
+            .await
+            .map_err(|e| ServerFnError::new(format!("reading the inbox failed: {e:#}")))?;
+        Ok(smss
+            .into_iter()
+            .map(|(sms, date)| RawSmsDto {
+                message: sms.message,
+                from: sms.from.map(|f| if redact { redact_number(&f) } else { f }),
+                time: date.map(|d| d.to_rfc3339()).or(sms.timestamp).or(sms.time),
+            })
+            .collect())
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (limit, redact, token);
+        Ok(vec![])
+    }
+}
+
+/// `+393331234567` → `•••567`; alphanumeric senders like `WindTre` are kept.
+#[cfg(feature = "server")]
+fn redact_number(number: &str) -> String {
+    if number.chars().any(|c| c.is_ascii_alphabetic()) {
+        return number.to_string();
+    }
+    let chars: Vec<char> = number.chars().collect();
+    let tail: String = chars[chars.len().saturating_sub(3)..].iter().collect();
+    format!("•••{tail}")
+}
+
+#[server(StopScheduler, "/api", "Url", "scheduler/stop")]
+pub async fn stop_scheduler(#[server(default)] token: String) -> Result<(), ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        require_admin(token).await?;
+        crate::backend::scheduler::stop().await;
+        Ok(())
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = token;
+        Ok(())
+    }
+}
+
+#[server(RestartScheduler, "/api", "Url", "scheduler/restart")]
+pub async fn restart_scheduler(#[server(default)] token: String) -> Result<(), ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        require_admin(token).await?;
+        crate::backend::scheduler::restart()
+            .await
+            .map_err(|e| ServerFnError::new(format!("restart failed: {e}")))
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = token;
+        Ok(())
+    }
+}
+
+/// Reclaim the space of deleted rows (`VACUUM`) and refresh the query planner's
+/// statistics. Blocks writes while it runs. Admin only.
+#[server(OptimizeDb, "/api", "Url", "db/optimize")]
+pub async fn optimize_db(#[server(default)] token: String) -> Result<OptimizeDbDto, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        require_admin(token).await?;
+        let db = require_db("optimize_db")?;
+        match db.vacuum().await {
+            Ok((before, after)) => {
+                info!(
+                    size_before_bytes = before,
+                    size_after_bytes = after,
+                    "vacuum done"
+                );
+                Ok(OptimizeDbDto {
+                    size_before_bytes: before,
+                    size_after_bytes: after,
+                })
+            }
+            Err(e) => Err(ServerFnError::new(format!("optimize failed: {e}"))),
+        }
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = token;
+        Ok(OptimizeDbDto::default())
+    }
+}
+
+#[server(GetSchedulerStatus)]
+pub async fn get_scheduler_status() -> Result<SchedulerStatusDto, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        Ok(crate::backend::scheduler::status_dto().await)
     }
     #[cfg(not(feature = "server"))]
     {
@@ -76,89 +380,806 @@ pub async fn get_scheduler_status() -> Result<SchedulerStatusDto, ServerFnError>
             last_event: None,
             last_error: None,
             next_iteration_at: None,
+            interval_minutes: 0,
+            stale_alert: None,
+            stale_threshold_hours: 0,
+            unconfigured: None,
+            passive: false,
         })
     }
 }
 
+/// Range used by the usage chart when the caller doesn't pick one.
+pub const DEFAULT_USAGE_DAYS: i64 = 90;
+/// Longest range the usage chart can ask for (about ten years, in weekly bars).
+pub const MAX_USAGE_DAYS: i64 = 3650;
+
+/// Per-day (weekly past 90 days) usage; readings flagged as suspect are left out
+/// unless `include_suspect` is set.
 #[server(GetDailyUsage)]
-pub async fn get_daily_usage() -> Result<Vec<DailyUsagePointDto>, ServerFnError> {
+pub async fn get_daily_usage(
+    days: i64,
+    #[server(default)] include_suspect: bool,
+) -> Result<Vec<DailyUsagePointDto>, ServerFnError> {
     #[cfg(feature = "server")]
     {
-        use crate::backend::GLOBAL_DB;
-        use chrono::{Duration, Utc};
-        let Some(db) = GLOBAL_DB.get() else {
-            eprintln!("get_daily_usage: DB not initialized");
+        usage_points("get_daily_usage", days, include_suspect, Granularity::Day).await
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (days, include_suspect);
+        Ok(vec![])
+    }
+}
+
+/// Usage summed per ISO week (Monday to Sunday), labeled like `2024-W20`.
+#[server(GetWeeklyUsage)]
+pub async fn get_weekly_usage(
+    days: i64,
+    #[server(default)] include_suspect: bool,
+) -> Result<Vec<DailyUsagePointDto>, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        usage_points("get_weekly_usage", days, include_suspect, Granularity::Week).await
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (days, include_suspect);
+        Ok(vec![])
+    }
+}
+
+/// Usage summed per calendar month, labeled like `2024-05`.
+#[server(GetMonthlyUsage)]
+pub async fn get_monthly_usage(
+    days: i64,
+    #[server(default)] include_suspect: bool,
+) -> Result<Vec<DailyUsagePointDto>, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        usage_points(
+            "get_monthly_usage",
+            days,
+            include_suspect,
+            Granularity::Month,
+        )
+        .await
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (days, include_suspect);
+        Ok(vec![])
+    }
+}
+
+#[cfg(feature = "server")]
+async fn usage_points(
+    caller: &str,
+    days: i64,
+    include_suspect: bool,
+    granularity: Granularity,
+) -> Result<Vec<DailyUsagePointDto>, ServerFnError> {
+    use chrono::{Duration, Utc};
+    let db = require_db(caller)?;
+
+    let days = clamp_usage_days(days);
+    let since = Utc::now() - Duration::days(days);
+    let rows = match db.get_rows_since(since, include_suspect).await {
+        Ok(r) => r,
+        Err(e) => {
+            warn!(error = %e, "{caller} query failed");
             return Ok(vec![]);
+        }
+    };
+
+    Ok(usage::usage_series(&rows, days, Utc::now(), granularity))
+}
+
+/// Days with and without readings in the last `days` days and the longest gap, so
+/// inferred usage can be read with its missing data in mind.
+#[server(GetCoverage)]
+pub async fn get_coverage(days: i64) -> Result<CoverageDto, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        use chrono::{Duration, Utc};
+        let db = require_db("get_coverage")?;
+
+        let days = clamp_usage_days(days);
+        let now = Utc::now();
+        match db.get_rows_since(now - Duration::days(days), false).await {
+            Ok(rows) => Ok(usage::coverage(&rows, days, now)),
+            Err(e) => {
+                warn!(error = %e, "get_coverage query failed");
+                Ok(CoverageDto::default())
+            }
+        }
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = days;
+        Ok(CoverageDto::default())
+    }
+}
+
+/// Remaining data of every reading in the last `days` days (1..=90), oldest first.
+#[server(GetRemainingTrend)]
+pub async fn get_remaining_trend(days: i64) -> Result<Vec<TrendPointDto>, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        use chrono::{Duration, Utc};
+        let db = require_db("get_remaining_trend")?;
+        let since = Utc::now() - Duration::days(days.clamp(1, 90));
+        match db.get_rows_since(since, false).await {
+            Ok(rows) => Ok(rows
+                .into_iter()
+                .map(|r| TrendPointDto {
+                    timestamp: r.date_time.timestamp(),
+                    remaining_data_mb: r.remaining_data_mb,
+                })
+                .collect()),
+            Err(e) => {
+                warn!(error = %e, "get_remaining_trend query failed");
+                Ok(vec![])
+            }
+        }
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = days;
+        Ok(vec![])
+    }
+}
+
+/// Remaining percentage at the last reading of each day over the last `days` days,
+/// for a burn-down chart, with the bounds of the current cycle.
+#[server(GetRemainingSeries)]
+pub async fn get_remaining_series(days: i64) -> Result<RemainingSeriesDto, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        use chrono::{Duration, Utc};
+        let db = require_db("get_remaining_series")?;
+
+        let now = Utc::now();
+        let cycle_start = match db.current_cycle_start().await {
+            Ok(Some(start)) => start,
+            Ok(None) => usage::scheduled_cycle_start(now, *usage::BILLING_RESET_DAY),
+            Err(e) => {
+                warn!(error = %e, "get_remaining_series cycle query failed");
+                return Ok(RemainingSeriesDto::default());
+            }
         };
+        let cycle_end = usage::scheduled_cycle_end(cycle_start, *usage::BILLING_RESET_DAY);
 
-        let since = Utc::now() - Duration::days(90);
-        let mut rows = match db.get_rows_since(since).await {
+        let since = now - Duration::days(clamp_usage_days(days));
+        let rows = match db.get_rows_since(since, false).await {
             Ok(r) => r,
             Err(e) => {
-                eprintln!("get_daily_usage query error: {e}");
-                return Ok(vec![]);
+                warn!(error = %e, "get_remaining_series query failed");
+                return Ok(RemainingSeriesDto::default());
+            }
+        };
+        let plan_total_mb = plan_total_mb(db).await;
+        let low_threshold = match (*crate::backend::LOW_DATA_THRESHOLD_MB, plan_total_mb) {
+            (Some(mb), Some(total)) if total > 0 => Some((mb, mb as f32 / total as f32 * 100.0)),
+            _ => None,
+        };
+        let projection = match cycle_pace(db, cycle_start, now).await {
+            Ok(pace) => pace.map(|(remaining_mb, avg_daily_mb)| {
+                usage::project_cycle_end(remaining_mb, avg_daily_mb, now, cycle_end)
+            }),
+            Err(e) => {
+                warn!(error = %e, "get_remaining_series cycle rows query failed");
+                None
             }
         };
+        Ok(RemainingSeriesDto {
+            points: usage::remaining_series(&rows),
+            cycle_start: cycle_start.timestamp(),
+            cycle_end: cycle_end.timestamp(),
+            low_threshold,
+            projection,
+        })
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = days;
+        Ok(RemainingSeriesDto::default())
+    }
+}
 
-        // Sort rows by timestamp ascending to make choosing the last sample per day easy
-        rows.sort_by_key(|r| r.date_time);
+/// This cycle's cumulative usage next to the previous cycle's, aligned by day of cycle.
+/// The previous cycle is cut to the length of the current one (e.g. 31 vs 28 days).
+#[server(GetCycleComparison)]
+pub async fn get_cycle_comparison() -> Result<CycleComparisonDto, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        use chrono::{Duration, Utc};
+        // Two full cycles plus the partial one before them
+        const HISTORY_DAYS: i64 = 100;
+        let db = require_db("get_cycle_comparison")?;
 
-        // For each day, keep the LAST reading (latest timestamp) of remaining_data_mb
-        use std::collections::BTreeMap;
-        let mut last_by_day: BTreeMap<String, (chrono::DateTime<Utc>, i32)> = BTreeMap::new();
-        for r in rows.into_iter() {
-            let day = r.date_time.date_naive().to_string();
-            match last_by_day.get(&day) {
-                Some((ts, _)) if r.date_time <= *ts => {
-                    // keep existing (we want the last of the day)
-                }
-                _ => {
-                    last_by_day.insert(day, (r.date_time, r.remaining_data_mb));
-                }
+        let rows = match db
+            .get_rows_since(Utc::now() - Duration::days(HISTORY_DAYS), false)
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                warn!(error = %e, "get_cycle_comparison query failed");
+                return Ok(CycleComparisonDto::default());
+            }
+        };
+        let starts = match db
+            .cycle_starts_since(Utc::now() - Duration::days(HISTORY_DAYS))
+            .await
+        {
+            Ok(s) => s,
+            Err(e) => {
+                warn!(error = %e, "get_cycle_comparison cycle query failed");
+                return Ok(CycleComparisonDto::default());
+            }
+        };
+        let cycles = usage::split_cycles(&rows, &starts);
+        let Some(current) = cycles.last() else {
+            return Ok(CycleComparisonDto::default());
+        };
+        let start = current[0].date_time;
+        let cycle_days = (usage::scheduled_cycle_end(start, *usage::BILLING_RESET_DAY).date_naive()
+            - start.date_naive())
+        .num_days()
+        .max(1) as usize;
+        // The first slice usually starts mid-cycle, so it's only a previous cycle
+        // when another one precedes it
+        let previous = match cycles.len() {
+            n if n >= 3 => usage::cumulative_by_cycle_day(cycles[n - 2], cycle_days),
+            _ => vec![],
+        };
+        Ok(CycleComparisonDto {
+            current: usage::cumulative_by_cycle_day(current, cycle_days),
+            previous,
+        })
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        Ok(CycleComparisonDto::default())
+    }
+}
+
+/// The newest raw readings (not reduced per day), newest first.
+#[server(GetRecentReadings)]
+pub async fn get_recent_readings(limit: i64) -> Result<Vec<DataStatusDto>, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        const MAX_RECENT_READINGS: i64 = 100;
+        let db = require_db("get_recent_readings")?;
+        match db.get_recent(limit.clamp(1, MAX_RECENT_READINGS)).await {
+            Ok(rows) => Ok(rows
+                .into_iter()
+                .map(|r| DataStatusDto {
+                    remaining_percentage: r.remaining_percentage,
+                    remaining_data_mb: r.remaining_data_mb,
+                    date_time: r.date_time.to_rfc3339(),
+                    id: r.id,
+                    display_time: None,
+                })
+                .collect()),
+            Err(e) => {
+                warn!(error = %e, "get_recent_readings query failed");
+                Ok(vec![])
+            }
+        }
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = limit;
+        Ok(vec![])
+    }
+}
+
+/// Parse a range bound: RFC 3339, or a plain `YYYY-MM-DD` covering the whole (UTC) day.
+#[cfg(feature = "server")]
+pub(crate) fn parse_range_bound(
+    s: &str,
+    end_of_day: bool,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    let time = if end_of_day {
+        NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999)?
+    } else {
+        NaiveTime::MIN
+    };
+    Some(date.and_time(time).and_utc())
+}
+
+/// Raw readings between two ISO dates/timestamps, both inclusive, oldest first.
+#[server(GetRowsBetween)]
+pub async fn get_rows_between(
+    start: String,
+    end: String,
+) -> Result<Vec<DataStatusDto>, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        let (Some(start_dt), Some(end_dt)) = (
+            parse_range_bound(&start, false),
+            parse_range_bound(&end, true),
+        ) else {
+            return Err(ServerFnError::new(format!(
+                "invalid range {start:?}..{end:?}: expected RFC 3339 or YYYY-MM-DD"
+            )));
+        };
+        if end_dt < start_dt {
+            return Err(ServerFnError::new("invalid range: end is before start"));
+        }
+        let db = require_db("get_rows_between")?;
+        match db.get_rows_between(start_dt, end_dt, true).await {
+            Ok(rows) => Ok(rows
+                .into_iter()
+                .map(|r| DataStatusDto {
+                    remaining_percentage: r.remaining_percentage,
+                    remaining_data_mb: r.remaining_data_mb,
+                    date_time: r.date_time.to_rfc3339(),
+                    id: r.id,
+                    display_time: None,
+                })
+                .collect()),
+            Err(e) => {
+                warn!(error = %e, "get_rows_between query failed");
+                Ok(vec![])
+            }
+        }
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (start, end);
+        Ok(vec![])
+    }
+}
+
+/// Data used between two dates (`YYYY-MM-DD` or RFC 3339, both days inclusive), e.g.
+/// "this weekend". The window is clamped to today and to the first stored reading.
+#[server(GetTotalUsage)]
+pub async fn get_total_usage(start: String, end: String) -> Result<TotalUsageDto, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        use chrono::{Duration, Utc};
+        let (Some(start_dt), Some(end_dt)) = (
+            parse_range_bound(&start, false),
+            parse_range_bound(&end, true),
+        ) else {
+            return Err(ServerFnError::new(format!(
+                "invalid range {start:?}..{end:?}: expected RFC 3339 or YYYY-MM-DD"
+            )));
+        };
+        if end_dt < start_dt {
+            return Err(ServerFnError::new("invalid range: end is before start"));
+        }
+        if (end_dt - start_dt).num_days() >= MAX_USAGE_DAYS {
+            return Err(ServerFnError::new(format!(
+                "invalid range: longer than {MAX_USAGE_DAYS} days"
+            )));
+        }
+        let db = require_db("get_total_usage")?;
+        // One day earlier, so the first day can be measured against it; suspect rows
+        // are left out like in the chart and stats
+        let rows = match db
+            .get_rows_between(start_dt - Duration::days(1), end_dt, false)
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                warn!(error = %e, "get_total_usage query failed");
+                return Ok(TotalUsageDto::default());
+            }
+        };
+        let end_day = end_dt.date_naive().min(Utc::now().date_naive());
+        let start_day = match rows.first() {
+            Some(first) => start_dt.date_naive().max(first.date_time.date_naive()),
+            None => start_dt.date_naive(),
+        };
+        Ok(TotalUsageDto {
+            start: start_day.to_string(),
+            end: end_day.to_string(),
+            used_mb: usage::total_usage(&rows, start_day, end_day),
+        })
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (start, end);
+        Ok(TotalUsageDto::default())
+    }
+}
+
+/// Start of the current billing cycle (RFC 3339), once a reset has been detected.
+#[server(GetCycleStart)]
+pub async fn get_cycle_start() -> Result<Option<String>, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        let db = require_db("get_cycle_start")?;
+        match db.current_cycle_start().await {
+            Ok(start) => Ok(start.map(|dt| dt.to_rfc3339())),
+            Err(e) => {
+                warn!(error = %e, "get_cycle_start query failed");
+                Ok(None)
             }
         }
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        Ok(None)
+    }
+}
+
+/// A page of all readings, newest first unless `ascending`. `limit` is capped at
+/// 500; a negative `offset` is an error.
+#[server(GetHistoryPage)]
+pub async fn get_history_page(
+    limit: i64,
+    offset: i64,
+    ascending: bool,
+) -> Result<HistoryPageDto, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        if offset < 0 {
+            return Err(ServerFnError::new("offset must not be negative"));
+        }
+        const MAX_HISTORY_PAGE: i64 = 500;
+        let limit = limit.clamp(1, MAX_HISTORY_PAGE);
+        let db = require_db("get_history_page")?;
+        let page = async {
+            let rows = db.get_rows_paginated(limit, offset, ascending).await?;
+            let total = db.count_rows().await?;
+            anyhow::Ok(HistoryPageDto {
+                rows: rows
+                    .into_iter()
+                    .map(|r| DataStatusDto {
+                        remaining_percentage: r.remaining_percentage,
+                        remaining_data_mb: r.remaining_data_mb,
+                        date_time: r.date_time.to_rfc3339(),
+                        id: r.id,
+                        display_time: None,
+                    })
+                    .collect(),
+                total,
+            })
+        };
+        match page.await {
+            Ok(p) => Ok(p),
+            Err(e) => {
+                warn!(error = %e, "get_history_page query failed");
+                Ok(HistoryPageDto::default())
+            }
+        }
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (limit, offset, ascending);
+        Ok(HistoryPageDto::default())
+    }
+}
+
+#[server(GetUsageStats)]
+pub async fn get_usage_stats() -> Result<UsageStatsDto, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        use chrono::Utc;
+        let db = require_db("get_usage_stats")?;
+
+        let now = Utc::now();
+        let cycle_start = match current_cycle_start(db, now).await {
+            Ok(start) => start,
+            Err(e) => {
+                warn!(error = %e, "get_usage_stats cycle query failed");
+                return Ok(UsageStatsDto::default());
+            }
+        };
+        let days_into_cycle = (now.date_naive() - cycle_start.date_naive()).num_days() + 1;
+
+        let rows = match db.get_rows_since(cycle_start, false).await {
+            Ok(r) => r,
+            Err(e) => {
+                warn!(error = %e, "get_usage_stats query failed");
+                return Ok(UsageStatsDto::default());
+            }
+        };
+        let points = reduce_daily_usage(&rows, days_into_cycle, now);
+        let total: i32 = points.iter().map(|p| p.used_mb).sum();
+        let peak = points.iter().map(|p| p.used_mb).max().unwrap_or(0);
+        Ok(UsageStatsDto {
+            total_used_this_cycle_mb: total,
+            // days_into_cycle is always >= 1
+            avg_daily_mb: total / days_into_cycle as i32,
+            days_into_cycle: days_into_cycle as i32,
+            peak_day_mb: peak,
+        })
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        Ok(UsageStatsDto::default())
+    }
+}
+
+/// Where the current cycle ends up at its average daily usage so far, using the
+/// configured `BILLING_RESET_DAY` for the end of the cycle.
+#[server(GetForecast)]
+pub async fn get_forecast() -> Result<ForecastDto, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        use chrono::Utc;
+        let db = require_db("get_forecast")?;
+
+        let now = Utc::now();
+        let reset_day = *usage::BILLING_RESET_DAY;
+        let cycle_start = match current_cycle_start(db, now).await {
+            Ok(start) => start,
+            Err(e) => {
+                warn!(error = %e, "get_forecast cycle query failed");
+                return Ok(ForecastDto::default());
+            }
+        };
+        let cycle_end = usage::scheduled_cycle_end(cycle_start, reset_day);
+        let pace = match cycle_pace(db, cycle_start, now).await {
+            Ok(pace) => pace,
+            Err(e) => {
+                warn!(error = %e, "get_forecast query failed");
+                None
+            }
+        };
+        let (remaining_mb, avg_daily_mb) = pace.unwrap_or_default();
+        Ok(ForecastDto {
+            cycle_start: cycle_start.timestamp(),
+            cycle_end: cycle_end.timestamp(),
+            reset_day,
+            remaining_mb,
+            avg_daily_mb,
+            projection: pace
+                .map(|_| usage::project_cycle_end(remaining_mb, avg_daily_mb, now, cycle_end)),
+        })
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        Ok(ForecastDto::default())
+    }
+}
+
+/// Start of the current billing cycle: the newest reset in the `cycles` table, or the
+/// last `BILLING_RESET_DAY` before a reset has been recorded.
+#[cfg(feature = "server")]
+async fn current_cycle_start(
+    db: &crate::backend::Db,
+    now: chrono::DateTime<chrono::Utc>,
+) -> anyhow::Result<chrono::DateTime<chrono::Utc>> {
+    Ok(match db.current_cycle_start().await? {
+        Some(start) => start,
+        None => usage::scheduled_cycle_start(now, *usage::BILLING_RESET_DAY),
+    })
+}
+
+/// Remaining MB at the newest reading since `cycle_start` and the average daily usage
+/// (same average as `GetUsageStats`), or `None` before the cycle's first reading.
+#[cfg(feature = "server")]
+async fn cycle_pace(
+    db: &crate::backend::Db,
+    cycle_start: chrono::DateTime<chrono::Utc>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> anyhow::Result<Option<(i32, i32)>> {
+    let rows = db.get_rows_since(cycle_start, false).await?;
+    Ok(rows.last().map(|latest| {
+        let days_into_cycle = (now.date_naive() - cycle_start.date_naive()).num_days() + 1;
+        let used: i32 = reduce_daily_usage(&rows, days_into_cycle, now)
+            .iter()
+            .map(|p| p.used_mb)
+            .sum();
+        (latest.remaining_data_mb, used / days_into_cycle as i32)
+    }))
+}
 
-        // Build output for last 90 days in order, computing usage as prev_day_remaining - curr_day_remaining when both exist
-        let mut filled = Vec::new();
-        let mut prev_remaining: Option<i32> = None;
-        for i in (0..90).rev() {
-            let d = (Utc::now() - Duration::days(i)).date_naive().to_string();
-            let curr_remaining = last_by_day.get(&d).map(|(_, v)| *v);
-            let used = match (prev_remaining, curr_remaining) {
-                (Some(prev), Some(curr)) => {
-                    let diff = prev - curr;
-                    if diff > 0 {
-                        diff
-                    } else {
-                        0
+/// Data used so far in the current cycle (the sum of the daily drops since its start)
+/// and the plan size, for "42 GB of 100 GB used". Right after a reset it's about 0.
+#[server(GetCycleUsage)]
+pub async fn get_cycle_usage() -> Result<CycleUsageDto, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        use chrono::Utc;
+        let db = require_db("get_cycle_usage")?;
+
+        let now = Utc::now();
+        let cycle_start = match current_cycle_start(db, now).await {
+            Ok(start) => start,
+            Err(e) => {
+                warn!(error = %e, "get_cycle_usage cycle query failed");
+                return Ok(CycleUsageDto::default());
+            }
+        };
+        let days_into_cycle = (now.date_naive() - cycle_start.date_naive()).num_days() + 1;
+        let rows = match db.get_rows_since(cycle_start, false).await {
+            Ok(r) => r,
+            Err(e) => {
+                warn!(error = %e, "get_cycle_usage query failed");
+                return Ok(CycleUsageDto::default());
+            }
+        };
+        let used_mb = reduce_daily_usage(&rows, days_into_cycle, now)
+            .iter()
+            .map(|p| p.used_mb)
+            .sum();
+        Ok(CycleUsageDto {
+            used_mb,
+            plan_total_mb: plan_total_mb(db).await,
+            cycle_start: cycle_start.to_rfc3339(),
+        })
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        Ok(CycleUsageDto::default())
+    }
+}
+
+/// `WINDTRE_PLAN_TOTAL_MB`, else the size implied by the stored readings.
+#[cfg(feature = "server")]
+async fn plan_total_mb(db: &crate::backend::Db) -> Option<i32> {
+    match crate::backend::windtre::plan_total_mb() {
+        Some(mb) => Some(mb),
+        None => db.implied_plan_total_mb().await.unwrap_or(None),
+    }
+}
+
+#[server(GetWeekdayUsage)]
+pub async fn get_weekday_usage(days: i64) -> Result<Vec<WeekdayUsageDto>, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        use chrono::{Datelike, Duration, Utc, Weekday};
+        const WEEKDAYS: [Weekday; 7] = [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ];
+
+        let mut sums = [0i64; 7];
+        let mut counts = [0i32; 7];
+        let db = require_db("get_weekday_usage")?;
+        let days = clamp_usage_days(days);
+        let now = Utc::now();
+        match db.get_rows_since(now - Duration::days(days), false).await {
+            Ok(rows) => {
+                // Average over measured days only, so missing readings and reset
+                // days don't drag a weekday down and uneven edges are normalized
+                for d in daily_usage_series(&rows, days, now) {
+                    if let Some(used) = d.used_mb {
+                        let idx = d.date.weekday().num_days_from_monday() as usize;
+                        sums[idx] += used as i64;
+                        counts[idx] += 1;
                     }
                 }
-                _ => 0,
-            };
-            if let Some(curr) = curr_remaining {
-                // eprintln!(
-                //     "get_daily_usage: {d}: prev={} curr={} used={}",
-                //     prev_remaining.unwrap_or(-1),
-                //     curr,
-                //     used
-                // );
-                prev_remaining = Some(curr);
-            } else {
-                // eprintln!(
-                //     "get_daily_usage: {d}: prev={} curr=NA used=0",
-                //     prev_remaining.unwrap_or(-1)
-                // );
-                // do not update prev_remaining when there's no reading for this day
             }
-            filled.push(DailyUsagePointDto {
-                date: d,
-                used_mb: used,
-            });
+            Err(e) => warn!(error = %e, "get_weekday_usage query failed"),
         }
-        return Ok(filled);
+
+        Ok(WEEKDAYS
+            .iter()
+            .enumerate()
+            .map(|(i, wd)| WeekdayUsageDto {
+                weekday: wd.to_string(),
+                avg_mb: if counts[i] > 0 {
+                    (sums[i] / counts[i] as i64) as i32
+                } else {
+                    0
+                },
+                samples: counts[i],
+            })
+            .collect())
     }
     #[cfg(not(feature = "server"))]
     {
+        let _ = days;
         Ok(vec![])
     }
 }
+
+/// Always succeeds; the booleans carry the state so monitors can alert on fields.
+/// `GET /api/health` (add `?probe_mikrotik=true` to also contact the router).
+#[server(Healthcheck, "/api", "GetJson", "health")]
+pub async fn healthcheck(
+    #[server(default)] probe_mikrotik: bool,
+) -> Result<HealthDto, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        use crate::backend::{mikrotik, scheduler, GLOBAL_DB};
+        use chrono::Utc;
+
+        let (db_ok, latest_reading_age_seconds) = match GLOBAL_DB.get() {
+            Some(db) => {
+                let db_ok = db.ping().await.is_ok();
+                let age = match db.get_latest_data_status_cached().await {
+                    Ok(Some(r)) => Some((Utc::now() - r.date_time).num_seconds()),
+                    _ => None,
+                };
+                (db_ok, age)
+            }
+            None => (false, None),
+        };
+        let mikrotik_ok = if probe_mikrotik {
+            let res = mikrotik::probe(std::time::Duration::from_secs(3)).await;
+            if let Err(e) = &res {
+                warn!(error = %e, "healthcheck: mikrotik probe failed");
+            }
+            Some(res.is_ok())
+        } else {
+            None
+        };
+        Ok(HealthDto {
+            db_ok,
+            mikrotik_ok,
+            scheduler_running: scheduler::status_dto().await.running,
+            latest_reading_age_seconds,
+        })
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = probe_mikrotik;
+        Ok(HealthDto {
+            db_ok: false,
+            mikrotik_ok: None,
+            scheduler_running: false,
+            latest_reading_age_seconds: None,
+        })
+    }
+}
+
+#[server(GetGaugeMode)]
+pub async fn get_gauge_mode() -> Result<GaugeMode, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        Ok(*crate::backend::DISPLAY_MODE)
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        Ok(GaugeMode::default())
+    }
+}
+
+#[server(GetGaugeStyle)]
+pub async fn get_gauge_style() -> Result<GaugeStyle, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        Ok(*crate::backend::GAUGE_STYLE)
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        Ok(GaugeStyle::default())
+    }
+}
+
+/// Page title until the server's `APP_TITLE` arrives.
+pub const DEFAULT_APP_TITLE: &str = "WindTre Data Status";
+
+#[server(GetAppTitle)]
+pub async fn get_app_title() -> Result<String, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        Ok(crate::backend::APP_TITLE.clone())
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        Ok(DEFAULT_APP_TITLE.to_string())
+    }
+}
+
+#[server(GetLocale)]
+pub async fn get_locale() -> Result<LocaleConfig, ServerFnError> {
+    #[cfg(feature = "server")]
+    {
+        Ok(*crate::backend::LOCALE)
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        Ok(LocaleConfig::default())
+    }
+}
@@ -1,26 +1,72 @@
 use dioxus::prelude::*;
 
-use crate::components::{DataStatusCard, UsageChartView};
+use crate::api::{get_app_title, DEFAULT_APP_TITLE};
+use crate::components::{
+    use_admin_token, use_admin_token_provider, use_locale_provider, use_theme_provider,
+    use_toast_provider, AddReadingForm, AdminLogin, BurnDownChart, CycleComparison, DataStatusCard,
+    HistoryTable, RawSmsSample, RecentReadings, ThemeToggle, Toasts, UsageChartView,
+    WeekdayBreakdown,
+};
 use crate::{FAVICON, TAILWIND_CSS};
 
 #[allow(non_snake_case)]
 #[component]
 pub fn App() -> Element {
+    use_toast_provider();
+    use_locale_provider();
+    use_admin_token_provider();
+    let admin_token = use_admin_token();
+    let theme = use_theme_provider();
+    // Rendered on the server so the first paint has the configured title
+    let title = use_server_future(get_app_title)?;
+    let title = match &*title.read_unchecked() {
+        Some(Ok(t)) => t.clone(),
+        _ => DEFAULT_APP_TITLE.to_string(),
+    };
     rsx! {
+        document::Title { "{title}" }
         document::Link { rel: "icon", href: FAVICON }
         document::Stylesheet { href: TAILWIND_CSS }
-        document::Meta { name: "theme-color", content: "#020618" } // slate-950
-        document::Meta { name: "color-scheme", content: "dark" }
-        // Page container
-        div { class: "min-h-screen bg-slate-950 text-slate-100 p-6 space-y-6",
+        document::Meta { name: "theme-color", content: theme().page_color() }
+        document::Meta { name: "color-scheme", content: theme().name() }
+        // Page container; `dark` switches the `dark:` variants on
+        div { class: "{theme().name()} min-h-screen bg-slate-50 dark:bg-slate-950 text-slate-900 dark:text-slate-100 p-6 space-y-6",
+            div { class: "w-full max-w-5xl mx-auto flex justify-end gap-2",
+                AdminLogin {}
+                ThemeToggle {}
+            }
             // Centered card (max-w-xl)
             div { class: "w-full max-w-xl mx-auto",
-                DataStatusCard {}
+                DataStatusCard { title: title.clone() }
             }
             // Full-width chart section
             div { class: "w-full max-w-5xl mx-auto",
                 UsageChartView {}
             }
+            div { class: "w-full max-w-5xl mx-auto",
+                BurnDownChart {}
+            }
+            div { class: "w-full max-w-5xl mx-auto",
+                CycleComparison {}
+            }
+            div { class: "w-full max-w-5xl mx-auto",
+                WeekdayBreakdown {}
+            }
+            div { class: "w-full max-w-5xl mx-auto",
+                RecentReadings {}
+            }
+            if !admin_token().is_empty() {
+                div { class: "w-full max-w-5xl mx-auto",
+                    AddReadingForm {}
+                }
+                div { class: "w-full max-w-5xl mx-auto",
+                    RawSmsSample {}
+                }
+            }
+            div { class: "w-full max-w-5xl mx-auto",
+                HistoryTable {}
+            }
+            Toasts {}
         }
     }
 }
@@ -1,25 +1,146 @@
 #![cfg(feature = "server")]
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use dioxus::logger::tracing::{error, info, warn};
 use once_cell::sync::OnceCell;
-use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteRow, SqliteSynchronous};
 use sqlx::{sqlite::SqlitePoolOptions, Pool, Row, Sqlite};
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Minimum rise of the remaining percentage between consecutive readings that
+/// counts as a plan reset (small rises are rounding noise or bonus data).
+const CYCLE_RESET_MIN_JUMP_PERCENT: i32 = 10;
+
+/// Tracing target of the line logged for every reading insert (new, duplicate or
+/// unchanged); `RUST_LOG=info,audit=off` silences it.
+pub const AUDIT_TARGET: &str = "audit";
+
+pub fn is_cycle_reset(prev_percentage: i32, percentage: i32) -> bool {
+    percentage - prev_percentage >= CYCLE_RESET_MIN_JUMP_PERCENT
+}
+
+/// [`is_cycle_reset`] that, with a configured `reset_day`, also requires the jump to
+/// happen around that day of the month (a mid-cycle top-up is not a new cycle).
+pub fn is_cycle_reset_on(
+    prev: (i32, DateTime<Utc>),
+    cur: (i32, DateTime<Utc>),
+    reset_day: Option<u32>,
+) -> bool {
+    is_cycle_reset(prev.0, cur.0)
+        && reset_day.is_none_or(|day| crate::backend::usage::spans_reset_day(prev.1, cur.1, day))
+}
+
+/// SQLite pool/connection settings, from `DB_MAX_CONNECTIONS` (1-32, default 3),
+/// `DB_BUSY_TIMEOUT_SECS` (1-300, default 5), `DB_JOURNAL_MODE` (default `wal`) and
+/// `DB_SYNCHRONOUS` (default `normal`). Invalid values fall back to the default.
+#[derive(Debug, Clone, Copy)]
+pub struct DbOptions {
+    pub max_connections: u32,
+    pub busy_timeout: Duration,
+    pub journal_mode: SqliteJournalMode,
+    pub synchronous: SqliteSynchronous,
+}
+
+impl Default for DbOptions {
+    fn default() -> Self {
+        Self {
+            max_connections: 3,
+            busy_timeout: Duration::from_secs(5),
+            journal_mode: SqliteJournalMode::Wal,
+            synchronous: SqliteSynchronous::Normal,
+        }
+    }
+}
+
+impl DbOptions {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_connections: env_parse("DB_MAX_CONNECTIONS", default.max_connections, |v: &u32| {
+                (1..=32).contains(v)
+            }),
+            busy_timeout: Duration::from_secs(env_parse(
+                "DB_BUSY_TIMEOUT_SECS",
+                default.busy_timeout.as_secs(),
+                |v: &u64| (1..=300).contains(v),
+            )),
+            journal_mode: env_parse("DB_JOURNAL_MODE", default.journal_mode, |_| true),
+            synchronous: env_parse("DB_SYNCHRONOUS", default.synchronous, |_| true),
+        }
+    }
+}
+
+fn env_parse<T: FromStr + std::fmt::Debug>(
+    name: &str,
+    default: T,
+    valid: impl Fn(&T) -> bool,
+) -> T {
+    match std::env::var(name) {
+        Ok(raw) => match raw.trim().parse::<T>() {
+            Ok(v) if valid(&v) => v,
+            _ => {
+                warn!(value = ?raw, default = ?default, "invalid {name}; using the default");
+                default
+            }
+        },
+        Err(_) => default,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertOutcome {
+    Inserted(i64),
+    /// A row with the same `date_time` already exists
+    Duplicate,
+    /// Same values as the newest row within the dedup window
+    Unchanged,
+    /// Older than the newest row, e.g. from a router with a skewed clock; not stored
+    /// (`SCHED_NEWER_ONLY`)
+    Stale,
+}
+
+fn data_status_row_from(r: &SqliteRow) -> Result<DataStatusRow> {
+    let date_time_str: String = r.try_get("date_time")?;
+    let created_at_str: String = r.try_get("created_at")?;
+    Ok(DataStatusRow {
+        id: r.try_get("id")?,
+        remaining_percentage: r.try_get("remaining_percentage")?,
+        remaining_data_mb: r.try_get("remaining_data_mb")?,
+        date_time: DateTime::parse_from_rfc3339(&date_time_str)?.with_timezone(&Utc),
+        created_at: DateTime::parse_from_rfc3339(&created_at_str)?.with_timezone(&Utc),
+    })
+}
 
 pub static GLOBAL_DB: OnceCell<Arc<Db>> = OnceCell::new();
 
+/// `DATABASE_URL`, or a SQLite file `DB_FILENAME` (default `data.db`) in `DATA_DIR`
+/// (default `./data` under the working directory), created if missing.
 pub fn resolve_db_url() -> String {
     use std::{env, fs, path::PathBuf};
     if let Ok(url) = env::var("DATABASE_URL") {
         return url;
     }
-    // Place DB under project_root/data/data.db
-    let root = env!("CARGO_MANIFEST_DIR");
-    let mut path = PathBuf::from(root);
-    path.push("data");
-    let _ = fs::create_dir_all(&path);
-    path.push("data.db");
+    let dir = match env::var("DATA_DIR") {
+        Ok(d) if !d.trim().is_empty() => PathBuf::from(d.trim()),
+        _ => PathBuf::from("data"),
+    };
+    // Relative paths are resolved against the working directory of the process
+    let mut path = if dir.is_absolute() {
+        dir
+    } else {
+        env::current_dir().unwrap_or_default().join(dir)
+    };
+    if let Err(e) = fs::create_dir_all(&path) {
+        warn!(path = %path.display(), error = %e, "could not create the data directory");
+    }
+    let file = env::var("DB_FILENAME")
+        .ok()
+        .filter(|f| !f.trim().is_empty())
+        .unwrap_or_else(|| "data.db".into());
+    path.push(file.trim());
     // SQLx expects absolute paths in the form sqlite:///abs/path
     let path_str = path.to_string_lossy();
     let trimmed = path_str
@@ -29,14 +150,20 @@ pub fn resolve_db_url() -> String {
     format!("sqlite:///{}?mode=rwc", trimmed)
 }
 
+/// How long `get_latest_data_status_cached` may serve a cached row.
+const LATEST_CACHE_TTL: Duration = Duration::from_secs(5);
+
 #[derive(Debug, Clone)]
 pub struct Db {
     pool: Pool<Sqlite>,
+    // Latest row and when it was read; cleared on every write to data_status
+    latest_cache: Arc<RwLock<Option<(Instant, DataStatusRow)>>>,
+    // Bumped on every invalidation so a read that raced a write isn't cached
+    latest_cache_gen: Arc<AtomicU64>,
 }
 
 #[derive(Debug, Clone)]
 pub struct DataStatusRow {
-    #[allow(dead_code)]
     pub id: i64,
     pub remaining_percentage: i32,
     pub remaining_data_mb: i32,
@@ -45,24 +172,172 @@ pub struct DataStatusRow {
     pub created_at: DateTime<Utc>,
 }
 
+/// Last known scheduler state, kept in a single-row table so it survives restarts.
+#[derive(Debug, Clone, Default)]
+pub struct PersistedSchedulerState {
+    pub last_loop_at: Option<String>,
+    pub last_event: Option<String>,
+    pub last_error: Option<String>,
+    pub next_iteration_at: Option<String>,
+}
+
+/// `PRAGMA integrity_check` reported problems.
+#[derive(Debug, thiserror::Error)]
+#[error("database integrity check failed: {0}")]
+pub struct CorruptDatabase(String);
+
+/// Whether `e` means the database file itself is damaged (failed integrity check,
+/// `SQLITE_CORRUPT` or `SQLITE_NOTADB`), so retrying won't help.
+pub fn is_corruption(e: &anyhow::Error) -> bool {
+    e.chain().any(|c| {
+        c.is::<CorruptDatabase>()
+            || matches!(
+                c.downcast_ref::<sqlx::Error>(),
+                Some(sqlx::Error::Database(d)) if matches!(d.code().as_deref(), Some("11" | "26"))
+            )
+    })
+}
+
+/// Rename `path` (plus its `-wal`/`-shm` files) to `<name>.corrupt-<timestamp>`.
+fn move_aside(path: &std::path::Path) -> Result<std::path::PathBuf> {
+    let suffix = format!("corrupt-{}", Utc::now().format("%Y%m%dT%H%M%SZ"));
+    let aside = path.with_file_name(format!(
+        "{}.{suffix}",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    std::fs::rename(path, &aside)?;
+    for ext in ["-wal", "-shm"] {
+        let mut side = path.as_os_str().to_owned();
+        side.push(ext);
+        let side = std::path::PathBuf::from(side);
+        if side.exists() {
+            let mut to = aside.as_os_str().to_owned();
+            to.push(ext);
+            std::fs::rename(&side, to)?;
+        }
+    }
+    Ok(aside)
+}
+
 impl Db {
+    /// Open (creating if needed) and migrate the database. A corrupt file is an error,
+    /// unless `DB_RECOVER_ON_CORRUPT` is set: then it's moved aside and a new, empty
+    /// database is created in its place.
     pub async fn connect(database_url: &str) -> Result<Self> {
+        Self::connect_recovering(
+            database_url,
+            crate::backend::env_flag("DB_RECOVER_ON_CORRUPT"),
+        )
+        .await
+    }
+
+    async fn connect_recovering(database_url: &str, recover: bool) -> Result<Self> {
+        match Self::connect_checked(database_url).await {
+            Err(e) if is_corruption(&e) => {
+                if !recover {
+                    return Err(e.context(
+                        "database file is corrupt; restore a backup, or set DB_RECOVER_ON_CORRUPT=1 to move it aside and start empty",
+                    ));
+                }
+                let path = SqliteConnectOptions::from_str(database_url)?.get_filename();
+                let aside = move_aside(&path)?;
+                error!(
+                    path = %path.display(),
+                    moved_to = %aside.display(),
+                    error = %e,
+                    "database is corrupt; moved it aside and starting with an EMPTY database"
+                );
+                Self::connect_checked(database_url).await
+            }
+            r => r,
+        }
+    }
+
+    async fn connect_checked(database_url: &str) -> Result<Self> {
+        Self::connect_with(database_url, DbOptions::from_env()).await
+    }
+
+    async fn connect_with(database_url: &str, cfg: DbOptions) -> Result<Self> {
         let opts = SqliteConnectOptions::from_str(database_url)?
             .create_if_missing(true)
-            .journal_mode(SqliteJournalMode::Wal)
-            .synchronous(SqliteSynchronous::Normal)
-            .busy_timeout(std::time::Duration::from_secs(5))
+            .journal_mode(cfg.journal_mode)
+            .synchronous(cfg.synchronous)
+            .busy_timeout(cfg.busy_timeout)
             .foreign_keys(true);
 
         let pool = SqlitePoolOptions::new()
-            .max_connections(3)
+            .max_connections(cfg.max_connections)
             .connect_with(opts)
             .await?;
-        let db = Self { pool };
+        let db = Self {
+            pool,
+            latest_cache: Arc::new(RwLock::new(None)),
+            latest_cache_gen: Arc::new(AtomicU64::new(0)),
+        };
+        if let Err(e) = db.integrity_check().await {
+            // Release the file so it can be moved aside
+            db.pool.close().await;
+            return Err(e);
+        }
         db.migrate().await?;
         Ok(db)
     }
 
+    /// A private in-memory database. Each connection would open its own, so the pool
+    /// holds just one.
+    #[cfg(test)]
+    pub async fn in_memory() -> Result<Self> {
+        let cfg = DbOptions {
+            max_connections: 1,
+            ..DbOptions::default()
+        };
+        Self::connect_with("sqlite::memory:", cfg).await
+    }
+
+    /// Size of the database in bytes (pages in use plus free pages, without the WAL).
+    async fn size_bytes(&self) -> Result<i64> {
+        let (size,): (i64,) = sqlx::query_as(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(size)
+    }
+
+    /// `VACUUM` and `PRAGMA optimize`, then a WAL checkpoint so the file actually
+    /// shrinks. Returns the size before and after in bytes. Writers wait on the busy
+    /// timeout meanwhile; a second call while one runs is refused.
+    pub async fn vacuum(&self) -> Result<(i64, i64)> {
+        static RUNNING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+        let Ok(_guard) = RUNNING.try_lock() else {
+            anyhow::bail!("a vacuum is already running");
+        };
+        let before = self.size_bytes().await?;
+        sqlx::query("VACUUM").execute(&self.pool).await?;
+        sqlx::query("PRAGMA optimize").execute(&self.pool).await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&self.pool)
+            .await?;
+        let after = self.size_bytes().await?;
+        Ok((before, after))
+    }
+
+    async fn integrity_check(&self) -> Result<()> {
+        let rows: Vec<(String,)> = sqlx::query_as("PRAGMA integrity_check")
+            .fetch_all(&self.pool)
+            .await?;
+        match rows.first() {
+            Some((first,)) if first == "ok" => Ok(()),
+            _ => Err(CorruptDatabase(
+                rows.into_iter()
+                    .map(|(r,)| r)
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            )
+            .into()),
+        }
+    }
+
     async fn migrate(&self) -> Result<()> {
         sqlx::query(
             r#"
@@ -71,12 +346,77 @@ impl Db {
                 remaining_percentage INTEGER NOT NULL,
                 remaining_data_mb INTEGER NOT NULL,
                 date_time TEXT NOT NULL UNIQUE,
-                created_at TEXT NOT NULL
+                created_at TEXT NOT NULL,
+                suspect INTEGER
             );
             "#,
         )
         .execute(&self.pool)
         .await?;
+        // Databases created before readings could be flagged
+        let (has_suspect,): (bool,) = sqlx::query_as(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('data_status') WHERE name = 'suspect'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if !has_suspect {
+            sqlx::query("ALTER TABLE data_status ADD COLUMN suspect INTEGER")
+                .execute(&self.pool)
+                .await?;
+        }
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS scheduler_state (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                last_loop_at TEXT,
+                last_event TEXT,
+                last_error TEXT,
+                next_iteration_at TEXT,
+                updated_at TEXT NOT NULL
+            );
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        let (had_cycles,): (bool,) = sqlx::query_as(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'cycles'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS cycles (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                started_at TEXT NOT NULL UNIQUE,
+                detected_at TEXT NOT NULL
+            );
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        // Single-row lease so only one process runs the scheduler per DB file
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS scheduler_lock (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                owner TEXT NOT NULL,
+                lease_until INTEGER NOT NULL
+            );
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        // Backfill markers for readings stored before cycles were tracked; afterwards
+        // inserts and edits keep the table current
+        if !had_cycles {
+            self.rebuild_cycles().await?;
+        }
+        Ok(())
+    }
+
+    /// Trivial query to check the pool can reach the database.
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
         Ok(())
     }
 
@@ -98,7 +438,273 @@ impl Db {
         .bind(created_at.to_rfc3339())
         .execute(&self.pool)
         .await?;
-        Ok(rec.last_insert_rowid())
+        self.invalidate_latest_cache();
+        // last_insert_rowid keeps the previous value when the insert was ignored
+        if rec.rows_affected() == 0 {
+            info!(
+                target: AUDIT_TARGET,
+                remaining_percentage,
+                remaining_data_mb,
+                %date_time,
+                "reading ignored: duplicate timestamp"
+            );
+            return Ok(0);
+        }
+        self.record_cycle_start_if_reset(remaining_percentage, date_time)
+            .await?;
+        let id = rec.last_insert_rowid();
+        info!(
+            target: AUDIT_TARGET,
+            id,
+            remaining_percentage,
+            remaining_data_mb,
+            %date_time,
+            "reading inserted"
+        );
+        Ok(id)
+    }
+
+    /// Delete one reading; false when there is no row with `id`.
+    pub async fn delete_by_id(&self, id: i64) -> Result<bool> {
+        let rec = sqlx::query("DELETE FROM data_status WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        self.after_manual_edit(rec.rows_affected()).await
+    }
+
+    /// Correct the values of one reading (clearing its suspect flag); false when there
+    /// is no row with `id`.
+    pub async fn update_reading(
+        &self,
+        id: i64,
+        remaining_percentage: i32,
+        remaining_data_mb: i32,
+    ) -> Result<bool> {
+        let rec = sqlx::query(
+            r#"UPDATE data_status
+            SET remaining_percentage = ?2, remaining_data_mb = ?3, suspect = NULL
+            WHERE id = ?1"#,
+        )
+        .bind(id)
+        .bind(remaining_percentage)
+        .bind(remaining_data_mb)
+        .execute(&self.pool)
+        .await?;
+        self.after_manual_edit(rec.rows_affected()).await
+    }
+
+    /// The latest reading and cycle starts may have changed
+    async fn after_manual_edit(&self, rows_affected: u64) -> Result<bool> {
+        if rows_affected == 0 {
+            return Ok(false);
+        }
+        self.invalidate_latest_cache();
+        self.rebuild_cycles().await?;
+        Ok(true)
+    }
+
+    /// Insert `(remaining_percentage, remaining_data_mb, date_time)` rows in one
+    /// transaction, skipping timestamps that already exist; returns how many were new.
+    /// Cycle starts are recomputed once afterwards instead of per row.
+    pub async fn insert_many(&self, rows: &[(i32, i32, DateTime<Utc>)]) -> Result<usize> {
+        let created_at = Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+        let mut inserted = 0;
+        for (remaining_percentage, remaining_data_mb, date_time) in rows {
+            let rec = sqlx::query(
+                r#"INSERT OR IGNORE INTO data_status
+                (remaining_percentage, remaining_data_mb, date_time, created_at)
+                VALUES (?1, ?2, ?3, ?4)"#,
+            )
+            .bind(remaining_percentage)
+            .bind(remaining_data_mb)
+            .bind(date_time.to_rfc3339())
+            .bind(&created_at)
+            .execute(&mut *tx)
+            .await?;
+            inserted += rec.rows_affected() as usize;
+        }
+        tx.commit().await?;
+        self.invalidate_latest_cache();
+        if inserted > 0 {
+            self.rebuild_cycles().await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Store a cycle start at `date_time` if the reading jumped up from the one before it.
+    async fn record_cycle_start_if_reset(
+        &self,
+        remaining_percentage: i32,
+        date_time: DateTime<Utc>,
+    ) -> Result<()> {
+        let prev: Option<(i32, String)> = sqlx::query_as(
+            r#"SELECT remaining_percentage, date_time FROM data_status
+            WHERE date_time < ?1 ORDER BY date_time DESC LIMIT 1"#,
+        )
+        .bind(date_time.to_rfc3339())
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some((prev_pct, prev_at)) = prev {
+            let prev_at = DateTime::parse_from_rfc3339(&prev_at)?.with_timezone(&Utc);
+            if is_cycle_reset_on(
+                (prev_pct, prev_at),
+                (remaining_percentage, date_time),
+                *crate::backend::usage::CONFIGURED_RESET_DAY,
+            ) {
+                self.insert_cycle_start(date_time).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn insert_cycle_start(&self, started_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO cycles (started_at, detected_at) VALUES (?1, ?2)")
+            .bind(started_at.to_rfc3339())
+            .bind(Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Recompute all cycle starts from the stored readings, e.g. after an
+    /// out-of-order bulk import. Returns the number of cycles found.
+    pub async fn rebuild_cycles(&self) -> Result<usize> {
+        let rows: Vec<(i32, String)> = sqlx::query_as(
+            "SELECT remaining_percentage, date_time FROM data_status ORDER BY date_time ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM cycles").execute(&mut *tx).await?;
+        let reset_day = *crate::backend::usage::CONFIGURED_RESET_DAY;
+        let mut found = 0;
+        for pair in rows.windows(2) {
+            let ((prev_pct, prev_at), (pct, date_time)) = (&pair[0], &pair[1]);
+            let prev_at = DateTime::parse_from_rfc3339(prev_at)?.with_timezone(&Utc);
+            let at = DateTime::parse_from_rfc3339(date_time)?.with_timezone(&Utc);
+            if is_cycle_reset_on((*prev_pct, prev_at), (*pct, at), reset_day) {
+                sqlx::query(
+                    "INSERT OR IGNORE INTO cycles (started_at, detected_at) VALUES (?1, ?2)",
+                )
+                .bind(date_time)
+                .bind(Utc::now().to_rfc3339())
+                .execute(&mut *tx)
+                .await?;
+                found += 1;
+            }
+        }
+        tx.commit().await?;
+        Ok(found)
+    }
+
+    /// Start of the newest detected billing cycle, if any reset has been seen.
+    pub async fn current_cycle_start(&self) -> Result<Option<DateTime<Utc>>> {
+        let row: Option<(String,)> =
+            sqlx::query_as("SELECT started_at FROM cycles ORDER BY started_at DESC LIMIT 1")
+                .fetch_optional(&self.pool)
+                .await?;
+        row.map(|(s,)| Ok(DateTime::parse_from_rfc3339(&s)?.with_timezone(&Utc)))
+            .transpose()
+    }
+
+    /// Recorded cycle starts at or after `since`, oldest first.
+    pub async fn cycle_starts_since(&self, since: DateTime<Utc>) -> Result<Vec<DateTime<Utc>>> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT started_at FROM cycles WHERE started_at >= ?1 ORDER BY started_at ASC",
+        )
+        .bind(since.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|(s,)| Ok(DateTime::parse_from_rfc3339(s)?.with_timezone(&Utc)))
+            .collect()
+    }
+
+    /// Like `insert_data_status`, but skip readings that repeat the newest row's
+    /// percentage and MB within `window` (the router re-reports the same values
+    /// at slightly different seconds).
+    pub async fn insert_data_status_dedup(
+        &self,
+        remaining_percentage: i32,
+        remaining_data_mb: i32,
+        date_time: DateTime<Utc>,
+        window: chrono::Duration,
+    ) -> Result<InsertOutcome> {
+        if let Some(newest) = self.get_latest_data_status().await? {
+            if newest.remaining_percentage == remaining_percentage
+                && newest.remaining_data_mb == remaining_data_mb
+                && (date_time - newest.date_time).abs() <= window
+            {
+                info!(
+                    target: AUDIT_TARGET,
+                    remaining_percentage,
+                    remaining_data_mb,
+                    %date_time,
+                    newest_id = newest.id,
+                    "reading ignored: same values as the newest row"
+                );
+                return Ok(InsertOutcome::Unchanged);
+            }
+        }
+        let rowid = self
+            .insert_data_status(remaining_percentage, remaining_data_mb, date_time)
+            .await?;
+        Ok(if rowid == 0 {
+            InsertOutcome::Duplicate
+        } else {
+            InsertOutcome::Inserted(rowid)
+        })
+    }
+
+    /// Flag a reading whose MB doesn't match its percentage; it stays stored but is
+    /// left out of usage stats and charts.
+    pub async fn mark_suspect(&self, id: i64) -> Result<()> {
+        sqlx::query("UPDATE data_status SET suspect = 1 WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Plan size implied by the newest trusted reading (MB / percentage). Only readings
+    /// at 20% or more are used, where the 1% rounding of the percentage stays small.
+    pub async fn implied_plan_total_mb(&self) -> Result<Option<i32>> {
+        let row: Option<(i32, i32)> = sqlx::query_as(
+            r#"SELECT remaining_percentage, remaining_data_mb FROM data_status
+            WHERE suspect IS NOT 1 AND remaining_percentage >= 20
+            ORDER BY date_time DESC LIMIT 1"#,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|(pct, mb)| (mb as f64 * 100.0 / pct as f64).round() as i32))
+    }
+
+    fn invalidate_latest_cache(&self) {
+        let mut cache = self.latest_cache.write().unwrap();
+        self.latest_cache_gen.fetch_add(1, Ordering::SeqCst);
+        *cache = None;
+    }
+
+    /// `get_latest_data_status` behind a short-TTL cache for frequently rendered views.
+    /// Writes invalidate the cache, so new readings are visible immediately.
+    pub async fn get_latest_data_status_cached(&self) -> Result<Option<DataStatusRow>> {
+        if let Some((at, row)) = &*self.latest_cache.read().unwrap() {
+            if at.elapsed() < LATEST_CACHE_TTL {
+                return Ok(Some(row.clone()));
+            }
+        }
+        let gen = self.latest_cache_gen.load(Ordering::SeqCst);
+        let started = Instant::now();
+        let latest = self.get_latest_data_status().await?;
+        if let Some(row) = &latest {
+            let mut cache = self.latest_cache.write().unwrap();
+            if self.latest_cache_gen.load(Ordering::SeqCst) == gen {
+                *cache = Some((started, row.clone()));
+            }
+        }
+        Ok(latest)
     }
 
     pub async fn get_latest_data_status(&self) -> Result<Option<DataStatusRow>> {
@@ -109,62 +715,421 @@ impl Db {
         .fetch_optional(&self.pool)
         .await?;
 
-        if let Some(r) = row {
-            let id: i64 = r.try_get("id")?;
-            let remaining_percentage: i32 = r.try_get("remaining_percentage")?;
-            let remaining_data_mb: i32 = r.try_get("remaining_data_mb")?;
-            let date_time_str: String = r.try_get("date_time")?;
-            let created_at_str: String = r.try_get("created_at")?;
-
-            let date_time =
-                DateTime::parse_from_rfc3339(&date_time_str).map(|dt| dt.with_timezone(&Utc))?;
-            let created_at =
-                DateTime::parse_from_rfc3339(&created_at_str).map(|dt| dt.with_timezone(&Utc))?;
-
-            Ok(Some(DataStatusRow {
-                id,
-                remaining_percentage,
-                remaining_data_mb,
-                date_time,
-                created_at,
-            }))
-        } else {
-            Ok(None)
-        }
+        row.as_ref().map(data_status_row_from).transpose()
     }
 
-    pub async fn get_rows_since(&self, since: DateTime<Utc>) -> Result<Vec<DataStatusRow>> {
+    /// Readings from `since` on, oldest first; `include_suspect` keeps rows flagged by
+    /// `mark_suspect`.
+    pub async fn get_rows_since(
+        &self,
+        since: DateTime<Utc>,
+        include_suspect: bool,
+    ) -> Result<Vec<DataStatusRow>> {
         let rows = sqlx::query(
             r#"SELECT id, remaining_percentage, remaining_data_mb, date_time, created_at
             FROM data_status
-            WHERE date_time >= ?1
+            WHERE date_time >= ?1 AND (?2 OR suspect IS NOT 1)
             ORDER BY date_time ASC"#,
         )
         .bind(since.to_rfc3339())
+        .bind(include_suspect)
         .fetch_all(&self.pool)
         .await?;
 
-        let mut out = Vec::with_capacity(rows.len());
-        for r in rows {
-            let id: i64 = r.try_get("id")?;
-            let remaining_percentage: i32 = r.try_get("remaining_percentage")?;
-            let remaining_data_mb: i32 = r.try_get("remaining_data_mb")?;
-            let date_time_str: String = r.try_get("date_time")?;
-            let created_at_str: String = r.try_get("created_at")?;
+        rows.iter().map(data_status_row_from).collect()
+    }
 
-            let date_time =
-                DateTime::parse_from_rfc3339(&date_time_str).map(|dt| dt.with_timezone(&Utc))?;
-            let created_at =
-                DateTime::parse_from_rfc3339(&created_at_str).map(|dt| dt.with_timezone(&Utc))?;
+    /// One page of readings ordered by `date_time`.
+    pub async fn get_rows_paginated(
+        &self,
+        limit: i64,
+        offset: i64,
+        ascending: bool,
+    ) -> Result<Vec<DataStatusRow>> {
+        let order = if ascending { "ASC" } else { "DESC" };
+        let rows = sqlx::query(&format!(
+            r#"SELECT id, remaining_percentage, remaining_data_mb, date_time, created_at
+            FROM data_status
+            ORDER BY date_time {order}
+            LIMIT ?1 OFFSET ?2"#
+        ))
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
 
-            out.push(DataStatusRow {
-                id,
-                remaining_percentage,
-                remaining_data_mb,
-                date_time,
-                created_at,
-            });
+        rows.iter().map(data_status_row_from).collect()
+    }
+
+    /// The newest `limit` readings, newest first.
+    pub async fn get_recent(&self, limit: i64) -> Result<Vec<DataStatusRow>> {
+        self.get_rows_paginated(limit, 0, false).await
+    }
+
+    pub async fn count_rows(&self) -> Result<i64> {
+        let (n,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM data_status")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(n)
+    }
+
+    /// Rows with `start <= date_time <= end`, oldest first; suspect rows only with
+    /// `include_suspect`.
+    pub async fn get_rows_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        include_suspect: bool,
+    ) -> Result<Vec<DataStatusRow>> {
+        let rows = sqlx::query(
+            r#"SELECT id, remaining_percentage, remaining_data_mb, date_time, created_at
+            FROM data_status
+            WHERE date_time >= ?1 AND date_time <= ?2 AND (?3 OR suspect IS NOT 1)
+            ORDER BY date_time ASC"#,
+        )
+        .bind(start.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .bind(include_suspect)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(data_status_row_from).collect()
+    }
+
+    pub async fn save_scheduler_state(&self, state: &PersistedSchedulerState) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO scheduler_state
+            (id, last_loop_at, last_event, last_error, next_iteration_at, updated_at)
+            VALUES (1, ?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(id) DO UPDATE SET
+                last_loop_at = excluded.last_loop_at,
+                last_event = excluded.last_event,
+                last_error = excluded.last_error,
+                next_iteration_at = excluded.next_iteration_at,
+                updated_at = excluded.updated_at"#,
+        )
+        .bind(&state.last_loop_at)
+        .bind(&state.last_event)
+        .bind(&state.last_error)
+        .bind(&state.next_iteration_at)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn load_scheduler_state(&self) -> Result<Option<PersistedSchedulerState>> {
+        let row = sqlx::query(
+            r#"SELECT last_loop_at, last_event, last_error, next_iteration_at
+            FROM scheduler_state WHERE id = 1"#,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(r) = row {
+            Ok(Some(PersistedSchedulerState {
+                last_loop_at: r.try_get("last_loop_at")?,
+                last_event: r.try_get("last_event")?,
+                last_error: r.try_get("last_error")?,
+                next_iteration_at: r.try_get("next_iteration_at")?,
+            }))
+        } else {
+            Ok(None)
         }
-        Ok(out)
+    }
+
+    /// Take or renew the scheduler lease for `owner` until `now + lease`. Fails (false)
+    /// while another owner holds a lease that hasn't expired yet.
+    pub async fn try_acquire_scheduler_lease(
+        &self,
+        owner: &str,
+        lease: chrono::Duration,
+    ) -> Result<bool> {
+        self.try_acquire_scheduler_lease_at(owner, lease, Utc::now())
+            .await
+    }
+
+    async fn try_acquire_scheduler_lease_at(
+        &self,
+        owner: &str,
+        lease: chrono::Duration,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        let rec = sqlx::query(
+            r#"INSERT INTO scheduler_lock (id, owner, lease_until) VALUES (1, ?1, ?2)
+            ON CONFLICT(id) DO UPDATE SET owner = excluded.owner, lease_until = excluded.lease_until
+            WHERE scheduler_lock.owner = excluded.owner OR scheduler_lock.lease_until < ?3"#,
+        )
+        .bind(owner)
+        .bind((now + lease).timestamp())
+        .bind(now.timestamp())
+        .execute(&self.pool)
+        .await?;
+        Ok(rec.rows_affected() == 1)
+    }
+
+    /// Give up the lease if `owner` holds it, so another process can take over now.
+    pub async fn release_scheduler_lease(&self, owner: &str) -> Result<()> {
+        sqlx::query("DELETE FROM scheduler_lock WHERE owner = ?1")
+            .bind(owner)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[tokio::test]
+    async fn rows_between_is_inclusive_and_ascending() {
+        let db = Db::in_memory().await.unwrap();
+        let rows = [
+            (80, 40_000, at("2024-05-01T00:00:00Z")),
+            (78, 39_000, at("2024-05-01T12:00:00Z")),
+            (76, 38_000, at("2024-05-01T23:59:59Z")),
+            (74, 37_000, at("2024-05-02T00:00:00Z")),
+        ];
+        for (pct, mb, t) in rows {
+            db.insert_data_status(pct, mb, t).await.unwrap();
+        }
+        let mb = |rows: Vec<DataStatusRow>| -> Vec<i32> {
+            rows.iter().map(|r| r.remaining_data_mb).collect()
+        };
+        let between = db
+            .get_rows_between(rows[0].2, rows[2].2, true)
+            .await
+            .unwrap();
+        assert_eq!(mb(between), vec![40_000, 39_000, 38_000]);
+        // A date-only range covers the whole day and nothing of the next
+        let (start, end) = (
+            crate::api::parse_range_bound("2024-05-01", false).unwrap(),
+            crate::api::parse_range_bound("2024-05-01", true).unwrap(),
+        );
+        let day = db.get_rows_between(start, end, true).await.unwrap();
+        assert_eq!(mb(day), vec![40_000, 39_000, 38_000]);
+        let point = db
+            .get_rows_between(rows[3].2, rows[3].2, true)
+            .await
+            .unwrap();
+        assert_eq!(mb(point), vec![37_000]);
+        let empty = db
+            .get_rows_between(at("2024-04-01T00:00:00Z"), at("2024-04-30T00:00:00Z"), true)
+            .await
+            .unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[tokio::test]
+    async fn near_duplicate_readings_are_suppressed() {
+        let db = Db::in_memory().await.unwrap();
+        let window = chrono::Duration::minutes(5);
+        let t = at("2024-05-01T08:00:00Z");
+        let secs = |n| t + chrono::Duration::seconds(n);
+        assert!(matches!(
+            db.insert_data_status_dedup(60, 30_000, t, window)
+                .await
+                .unwrap(),
+            InsertOutcome::Inserted(_)
+        ));
+        // Same values a few seconds later: the router re-reporting
+        assert_eq!(
+            db.insert_data_status_dedup(60, 30_000, secs(40), window)
+                .await
+                .unwrap(),
+            InsertOutcome::Unchanged
+        );
+        // A genuine change inside the window is stored
+        assert!(matches!(
+            db.insert_data_status_dedup(59, 29_500, secs(90), window)
+                .await
+                .unwrap(),
+            InsertOutcome::Inserted(_)
+        ));
+        // Same values again, but outside the window
+        assert!(matches!(
+            db.insert_data_status_dedup(59, 29_500, secs(90 + 6 * 60), window)
+                .await
+                .unwrap(),
+            InsertOutcome::Inserted(_)
+        ));
+        assert_eq!(db.count_rows().await.unwrap(), 3);
+    }
+
+    /// Daily readings from May 1 to June 29, 2024 with the plan refilled to 100% on
+    /// the 15th of each month.
+    fn series_with_resets() -> Vec<(i32, i32, DateTime<Utc>)> {
+        use chrono::Datelike;
+        (0..60)
+            .map(|i| {
+                let date_time = at("2024-05-01T09:00:00Z") + chrono::Duration::days(i);
+                let days_into_cycle = (date_time.day() as i32 - 15).rem_euclid(30);
+                let pct = 100 - 3 * days_into_cycle;
+                (pct, pct * 500, date_time)
+            })
+            .collect()
+    }
+
+    async fn cycle_starts(db: &Db) -> Vec<DateTime<Utc>> {
+        let rows: Vec<(String,)> =
+            sqlx::query_as("SELECT started_at FROM cycles ORDER BY started_at ASC")
+                .fetch_all(&db.pool)
+                .await
+                .unwrap();
+        rows.iter().map(|(s,)| at(s)).collect()
+    }
+
+    #[tokio::test]
+    async fn cycles_across_resets() {
+        let expected = vec![at("2024-05-15T09:00:00Z"), at("2024-06-15T09:00:00Z")];
+        let db = Db::in_memory().await.unwrap();
+        for (pct, mb, date_time) in series_with_resets() {
+            db.insert_data_status(pct, mb, date_time).await.unwrap();
+        }
+        assert_eq!(cycle_starts(&db).await, expected);
+        assert_eq!(db.current_cycle_start().await.unwrap(), Some(expected[1]));
+
+        // Readings stored newest first have no earlier row to compare with, so the
+        // resets only show up once the cycles are rebuilt
+        let imported = Db::in_memory().await.unwrap();
+        for (pct, mb, date_time) in series_with_resets().into_iter().rev() {
+            imported
+                .insert_data_status(pct, mb, date_time)
+                .await
+                .unwrap();
+        }
+        assert_eq!(imported.current_cycle_start().await.unwrap(), None);
+        assert_eq!(imported.rebuild_cycles().await.unwrap(), 2);
+        assert_eq!(cycle_starts(&imported).await, expected);
+    }
+
+    #[test]
+    fn top_up_away_from_the_reset_day_is_not_a_cycle() {
+        let prev = (20, at("2024-05-08T09:00:00Z"));
+        let top_up = (45, at("2024-05-09T09:00:00Z"));
+        assert!(is_cycle_reset_on(prev, top_up, None));
+        assert!(!is_cycle_reset_on(prev, top_up, Some(15)));
+        let reset = (100, at("2024-05-15T09:00:00Z"));
+        assert!(is_cycle_reset_on(
+            (58, at("2024-05-14T09:00:00Z")),
+            reset,
+            Some(15)
+        ));
+        // Small rises are rounding noise
+        assert!(!is_cycle_reset_on(
+            (58, at("2024-05-14T09:00:00Z")),
+            (61, reset.1),
+            None
+        ));
+    }
+
+    #[tokio::test]
+    async fn insert_many_skips_a_duplicate_timestamp() {
+        let db = Db::in_memory().await.unwrap();
+        let t = at("2024-05-01T08:00:00Z");
+        let rows = [
+            (80, 40_000, t),
+            (79, 39_500, t + chrono::Duration::hours(1)),
+            (79, 39_400, t),
+        ];
+        assert_eq!(db.insert_many(&rows).await.unwrap(), 2);
+        assert_eq!(db.count_rows().await.unwrap(), 2);
+        // Already stored rows count as duplicates on the next batch too
+        assert_eq!(db.insert_many(&rows[1..]).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn corrupt_file_is_moved_aside_and_recreated() {
+        let dir = std::env::temp_dir().join(format!("trullo-corrupt-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data.db");
+        std::fs::write(&path, vec![0x5a; 8192]).unwrap();
+        let url = format!("sqlite://{}", path.display());
+
+        let err = Db::connect_recovering(&url, false).await.err().unwrap();
+        assert!(is_corruption(&err));
+        assert!(format!("{err:#}").contains("DB_RECOVER_ON_CORRUPT"));
+
+        let db = Db::connect_recovering(&url, true).await.unwrap();
+        assert_eq!(db.count_rows().await.unwrap(), 0);
+        db.insert_many(&[(50, 25_000, at("2024-05-01T08:00:00Z"))])
+            .await
+            .unwrap();
+        let aside = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                e.file_name()
+                    .to_string_lossy()
+                    .starts_with("data.db.corrupt-")
+            })
+            .count();
+        assert_eq!(aside, 1);
+        db.pool.close().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn scheduler_lease_acquisition_and_expiry() {
+        let db = Db::in_memory().await.unwrap();
+        let lease = chrono::Duration::seconds(60);
+        let t0 = at("2024-05-01T08:00:00Z");
+        let later = |secs| t0 + chrono::Duration::seconds(secs);
+        assert!(db
+            .try_acquire_scheduler_lease_at("a", lease, t0)
+            .await
+            .unwrap());
+        // Held: the other instance stays passive, the owner renews
+        assert!(!db
+            .try_acquire_scheduler_lease_at("b", lease, later(30))
+            .await
+            .unwrap());
+        assert!(db
+            .try_acquire_scheduler_lease_at("a", lease, later(30))
+            .await
+            .unwrap());
+        assert!(!db
+            .try_acquire_scheduler_lease_at("b", lease, later(80))
+            .await
+            .unwrap());
+        // Expired (renewed until 90s): taken over, and the old owner is now passive
+        assert!(db
+            .try_acquire_scheduler_lease_at("b", lease, later(91))
+            .await
+            .unwrap());
+        assert!(!db
+            .try_acquire_scheduler_lease_at("a", lease, later(92))
+            .await
+            .unwrap());
+        // Released on shutdown: free right away
+        db.release_scheduler_lease("b").await.unwrap();
+        assert!(db
+            .try_acquire_scheduler_lease_at("a", lease, later(93))
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn vacuum_a_small_database() {
+        let db = Db::in_memory().await.unwrap();
+        let rows: Vec<(i32, i32, DateTime<Utc>)> = (0..50)
+            .map(|i| {
+                (
+                    90 - i / 2,
+                    45_000 - i * 400,
+                    at("2024-05-01T00:00:00Z") + chrono::Duration::hours(i as i64),
+                )
+            })
+            .collect();
+        assert_eq!(db.insert_many(&rows).await.unwrap(), 50);
+        let (before, after) = db.vacuum().await.unwrap();
+        assert!(before > 0 && after > 0);
+        assert!(after <= before);
+        assert_eq!(db.count_rows().await.unwrap(), 50);
     }
 }
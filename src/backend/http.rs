@@ -0,0 +1,247 @@
+#![cfg(feature = "server")]
+//! Axum server wrapping the Dioxus app so plain HTTP routes can live next to it.
+use crate::backend::{
+    db::{Db, InsertOutcome},
+    metrics,
+    mikrotik::Sms,
+    scheduler, windtre, BASE_PATH, GLOBAL_DB,
+};
+use axum::{
+    extract::Query,
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use dioxus::logger::tracing::{info, warn};
+use dioxus::prelude::*;
+use futures::stream::{self, Stream};
+use std::convert::Infallible;
+
+/// Path of the scheduler status event stream.
+pub const EVENTS_PATH: &str = "/events";
+
+async fn metrics_handler() -> impl IntoResponse {
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        metrics::render_metrics().await,
+    )
+}
+
+/// Server-Sent Events: the current `SchedulerStatusDto` as JSON on connect and after every change.
+async fn events_handler() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let rx = scheduler::STATUS_CHANGED.subscribe();
+    let stream = stream::unfold((rx, true), |(mut rx, first)| async move {
+        if !first && rx.changed().await.is_err() {
+            return None;
+        }
+        rx.borrow_and_update();
+        let dto = scheduler::status_dto().await;
+        let event = Event::default()
+            .event("status")
+            .json_data(&dto)
+            .unwrap_or_else(|_| Event::default().comment("serialization error"));
+        Some((Ok(event), (rx, false)))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Plain `GET` JSON mirrors of the read-only server fns, for scripts and REST sensors.
+pub const JSON_LATEST_PATH: &str = "/api/latest";
+pub const JSON_DAILY_PATH: &str = "/api/daily";
+pub const JSON_STATUS_PATH: &str = "/api/status";
+
+/// The server fn's result as JSON; 503 while the DB isn't up, 500 for other errors.
+fn json_response<T: serde::Serialize>(result: Result<T, ServerFnError>) -> Response {
+    match result {
+        Ok(v) => Json(v).into_response(),
+        Err(e) => {
+            let msg = e.to_string();
+            let status = if msg.contains(crate::api::DB_UNAVAILABLE) {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            (status, msg).into_response()
+        }
+    }
+}
+
+async fn json_latest_handler() -> Response {
+    json_response(crate::api::latest_data_status().await)
+}
+
+#[derive(serde::Deserialize)]
+struct DailyQuery {
+    days: Option<i64>,
+    #[serde(default)]
+    include_suspect: bool,
+}
+
+async fn json_daily_handler(Query(q): Query<DailyQuery>) -> Response {
+    let days = q.days.unwrap_or(crate::api::DEFAULT_USAGE_DAYS);
+    json_response(crate::api::get_daily_usage(days, q.include_suspect).await)
+}
+
+async fn json_status_handler() -> Response {
+    json_response(crate::api::get_scheduler_status().await)
+}
+
+/// Path of the incoming SMS webhook (enabled by `WEBHOOK_TOKEN`).
+pub const WEBHOOK_SMS_PATH: &str = "/webhook/sms";
+
+/// Header carrying the shared secret for the webhook.
+const WEBHOOK_TOKEN_HEADER: &str = "x-webhook-token";
+
+/// SMS pushed by a RouterOS `on-receive` script.
+#[derive(serde::Deserialize)]
+struct WebhookSms {
+    message: String,
+    from: Option<String>,
+    /// RouterOS time string; the receive time is used when missing
+    time: Option<String>,
+}
+
+/// Parse and store an SMS pushed by the router. Data status SMS are stored
+/// (201, or 200 when already known); anything else is rejected with 422.
+async fn sms_webhook_handler(
+    headers: HeaderMap,
+    Json(body): Json<WebhookSms>,
+) -> (StatusCode, String) {
+    let expected = std::env::var("WEBHOOK_TOKEN").unwrap_or_default();
+    let given = headers
+        .get(WEBHOOK_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    receive_webhook_sms(
+        GLOBAL_DB.get().map(|db| db.as_ref()),
+        &expected,
+        given,
+        body,
+    )
+    .await
+}
+
+/// `sms_webhook_handler` past the header and env lookups.
+async fn receive_webhook_sms(
+    db: Option<&Db>,
+    expected: &str,
+    given: &str,
+    body: WebhookSms,
+) -> (StatusCode, String) {
+    if expected.is_empty() || !crate::backend::secret_matches(given, expected) {
+        return (StatusCode::UNAUTHORIZED, "invalid webhook token".into());
+    }
+    let sms = Sms {
+        id: "webhook".into(),
+        message: body.message,
+        timestamp: body.time.is_none().then(|| chrono::Utc::now().to_rfc3339()),
+        time: body.time,
+        received: None,
+        from: body.from,
+    };
+    let Some(ds) = windtre::parse_data_status_from_sms(&sms) else {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "not a data status SMS".into(),
+        );
+    };
+    let Some(db) = db else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "DB not initialized".into());
+    };
+    match scheduler::store_reading(db, &ds, "webhook").await {
+        Ok(InsertOutcome::Inserted(_)) => (StatusCode::CREATED, "stored".into()),
+        Ok(InsertOutcome::Stale) => (StatusCode::OK, "older than the newest reading".into()),
+        Ok(_) => (StatusCode::OK, "already stored".into()),
+        Err(e) => {
+            warn!(error = %e, "webhook insert failed");
+            (StatusCode::INTERNAL_SERVER_ERROR, "db insert error".into())
+        }
+    }
+}
+
+/// Serve the fullstack app plus the extra routes. Never returns.
+pub fn launch(root: fn() -> Element) -> ! {
+    tokio::runtime::Runtime::new()
+        .expect("server rt")
+        .block_on(async move {
+            let metrics_path = metrics::metrics_path();
+            let base = BASE_PATH.as_str();
+            info!(path = %metrics_path, base_path = base, "metrics endpoint");
+            let mut router = Router::new()
+                .route(&metrics_path, get(metrics_handler))
+                .route(EVENTS_PATH, get(events_handler))
+                .route(JSON_LATEST_PATH, get(json_latest_handler))
+                .route(JSON_DAILY_PATH, get(json_daily_handler))
+                .route(JSON_STATUS_PATH, get(json_status_handler));
+            if std::env::var("WEBHOOK_TOKEN").is_ok_and(|t| !t.is_empty()) {
+                info!(
+                    path = WEBHOOK_SMS_PATH,
+                    base_path = base,
+                    "SMS webhook endpoint"
+                );
+                router = router.route(WEBHOOK_SMS_PATH, post(sms_webhook_handler));
+            }
+            let router = router.serve_dioxus_application(ServeConfig::builder(), root);
+            // Behind a proxy that keeps the prefix every route, server fns included,
+            // lives under it
+            let router = if base.is_empty() {
+                router
+            } else {
+                info!(base_path = base, "serving under a base path");
+                Router::new().nest(base, router)
+            };
+
+            // Same address resolution as `dioxus::launch` (IP/PORT env, set by the CLI)
+            let address = dioxus::cli_config::fullstack_address_or_localhost();
+            let listener = tokio::net::TcpListener::bind(address)
+                .await
+                .expect("bind server address");
+            axum::serve(listener, router.into_make_service())
+                .await
+                .expect("server");
+        });
+    unreachable!("server should never return")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(json: &str) -> WebhookSms {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn webhook_stores_status_sms_and_rejects_the_rest() {
+        let db = Db::in_memory().await.unwrap();
+        let valid = r#"{"message": "Dati: hai ancora a disposizione il 37% di 100 GIGA", "from": "WINDTRE", "time": "may/01/2024 10:00:00"}"#;
+        let (status, _) = receive_webhook_sms(Some(&db), "s3cret", "s3cret", body(valid)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, _) = receive_webhook_sms(Some(&db), "s3cret", "s3cret", body(valid)).await;
+        assert_eq!(status, StatusCode::OK);
+        let latest = db.get_latest_data_status().await.unwrap().unwrap();
+        assert_eq!(
+            (latest.remaining_percentage, latest.remaining_data_mb),
+            (37, 37_000)
+        );
+
+        let (status, _) = receive_webhook_sms(Some(&db), "s3cret", "wrong", body(valid)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = receive_webhook_sms(Some(&db), "", "", body(valid)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let unparseable = r#"{"message": "Ricarica effettuata", "from": "WINDTRE"}"#;
+        let (status, _) =
+            receive_webhook_sms(Some(&db), "s3cret", "s3cret", body(unparseable)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(db.count_rows().await.unwrap(), 1);
+        assert!(serde_json::from_str::<WebhookSms>(r#"{"from": "WINDTRE"}"#).is_err());
+    }
+}
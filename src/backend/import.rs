@@ -0,0 +1,138 @@
+#![cfg(feature = "server")]
+use crate::backend::db::Db;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use dioxus::logger::tracing::warn;
+use std::path::Path;
+
+#[derive(Debug, Default)]
+pub struct CsvImportReport {
+    pub inserted: usize,
+    pub skipped: usize,
+    pub malformed: usize,
+}
+
+/// Parse one `date_time,remaining_percentage,remaining_data_mb` line.
+fn parse_csv_line(line: &str) -> Result<(DateTime<Utc>, i32, i32), String> {
+    let cols: Vec<&str> = line.split(',').map(|c| c.trim()).collect();
+    let [dt, pct, mb] = cols.as_slice() else {
+        return Err(format!("expected 3 columns, got {}", cols.len()));
+    };
+    let date_time = DateTime::parse_from_rfc3339(dt)
+        .map_err(|e| format!("invalid date_time {:?}: {}", dt, e))?
+        .with_timezone(&Utc);
+    let pct: i32 = pct
+        .parse()
+        .map_err(|_| format!("invalid remaining_percentage {:?}", pct))?;
+    if !(0..=100).contains(&pct) {
+        return Err(format!("remaining_percentage {} out of range 0-100", pct));
+    }
+    let mb: i32 = mb
+        .parse()
+        .map_err(|_| format!("invalid remaining_data_mb {:?}", mb))?;
+    Ok((date_time, pct, mb))
+}
+
+/// Import historical readings from a CSV with columns
+/// `date_time,remaining_percentage,remaining_data_mb` (RFC3339 timestamps, optional header).
+/// Rows with an existing timestamp are skipped by the DB's unique constraint.
+pub async fn import_csv(db: &Db, path: &Path) -> Result<CsvImportReport> {
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let mut report = CsvImportReport::default();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || (i == 0 && line.starts_with("date_time")) {
+            continue;
+        }
+        match parse_csv_line(line) {
+            Ok((date_time, pct, mb)) => {
+                if db.insert_data_status(pct, mb, date_time).await? != 0 {
+                    report.inserted += 1;
+                } else {
+                    report.skipped += 1;
+                }
+            }
+            Err(e) => {
+                warn!(line = i + 1, error = %e, "import-csv: malformed line; skipping");
+                report.malformed += 1;
+            }
+        }
+    }
+    // Readings may have landed between existing ones, so re-derive the resets
+    if report.inserted > 0 {
+        db.rebuild_cycles().await?;
+    }
+    Ok(report)
+}
+
+#[derive(Debug, Default)]
+pub struct ReparseReport {
+    pub inbox: usize,
+    pub unparsed: usize,
+    /// Readings that weren't stored yet
+    pub inserted: usize,
+    /// Stored rows whose values differ from the re-parsed SMS
+    pub mismatched: usize,
+    /// Mismatched rows overwritten (with `fix`)
+    pub corrected: usize,
+}
+
+/// Re-run the parser over the whole inbox, e.g. after a pattern change: readings not
+/// stored yet are inserted, and stored rows that disagree with their SMS are reported,
+/// or overwritten (which also clears their suspect flag) with `fix`. Running it twice
+/// changes nothing the second time.
+pub async fn reparse_inbox(db: &Db, fix: bool) -> Result<ReparseReport> {
+    let smss = crate::backend::mikrotik::get_smses().await?;
+    let mut report = ReparseReport {
+        inbox: smss.len(),
+        ..Default::default()
+    };
+    for sms in smss.iter() {
+        let Some(ds) = crate::backend::windtre::parse_data_status_from_sms(sms) else {
+            report.unparsed += 1;
+            continue;
+        };
+        let stored = db
+            .get_rows_between(ds.date_time, ds.date_time, true)
+            .await?;
+        match stored.first() {
+            None => {
+                if db
+                    .insert_data_status(ds.remaining_percentage, ds.remaining_data_mb, ds.date_time)
+                    .await?
+                    != 0
+                {
+                    report.inserted += 1;
+                }
+            }
+            Some(row)
+                if row.remaining_percentage != ds.remaining_percentage
+                    || row.remaining_data_mb != ds.remaining_data_mb =>
+            {
+                report.mismatched += 1;
+                warn!(
+                    date_time = %ds.date_time,
+                    stored_percentage = row.remaining_percentage,
+                    stored_mb = row.remaining_data_mb,
+                    sms_percentage = ds.remaining_percentage,
+                    sms_mb = ds.remaining_data_mb,
+                    "reparse: stored reading differs from its SMS"
+                );
+                if fix
+                    && db
+                        .update_reading(row.id, ds.remaining_percentage, ds.remaining_data_mb)
+                        .await?
+                {
+                    report.corrected += 1;
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    // New or corrected readings can move cycle boundaries
+    if report.inserted > 0 || report.corrected > 0 {
+        db.rebuild_cycles().await?;
+    }
+    Ok(report)
+}
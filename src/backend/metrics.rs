@@ -0,0 +1,92 @@
+#![cfg(feature = "server")]
+use crate::backend::{scheduler::STATUS, GLOBAL_DB};
+use chrono::Utc;
+use dioxus::logger::tracing::warn;
+use std::fmt::Write as _;
+
+/// Path the metrics are served on (`METRICS_PATH`, default `/metrics`).
+pub fn metrics_path() -> String {
+    match std::env::var("METRICS_PATH") {
+        Ok(p) if p.starts_with('/') && p.len() > 1 => p,
+        Ok(p) => {
+            warn!(value = ?p, "ignoring METRICS_PATH; must start with '/'");
+            "/metrics".into()
+        }
+        Err(_) => "/metrics".into(),
+    }
+}
+
+fn push_metric(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    value: impl std::fmt::Display,
+) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+    let _ = writeln!(out, "{name} {value}");
+}
+
+/// Render the latest reading and scheduler counters in Prometheus text exposition format.
+pub async fn render_metrics() -> String {
+    let mut out = String::new();
+
+    let latest = match GLOBAL_DB.get() {
+        Some(db) => match db.get_latest_data_status().await {
+            Ok(r) => r,
+            Err(e) => {
+                warn!(error = %e, "latest reading query failed");
+                None
+            }
+        },
+        None => None,
+    };
+    // Omit reading gauges entirely when there's no reading rather than reporting 0
+    if let Some(r) = latest {
+        push_metric(
+            &mut out,
+            "windtre_remaining_percentage",
+            "gauge",
+            "Remaining data in percent of the plan, from the latest reading.",
+            r.remaining_percentage,
+        );
+        push_metric(
+            &mut out,
+            "windtre_remaining_data_mb",
+            "gauge",
+            "Remaining data in MB, from the latest reading.",
+            r.remaining_data_mb,
+        );
+        push_metric(
+            &mut out,
+            "windtre_last_reading_age_seconds",
+            "gauge",
+            "Seconds since the latest reading was taken.",
+            (Utc::now() - r.date_time).num_seconds().max(0),
+        );
+    }
+
+    let (runs, errors) = match STATUS.get() {
+        Some(st) => {
+            let s = st.read().await;
+            (s.runs_total, s.errors_total)
+        }
+        None => (0, 0),
+    };
+    push_metric(
+        &mut out,
+        "scheduler_runs_total",
+        "counter",
+        "Scheduler runs since process start.",
+        runs,
+    );
+    push_metric(
+        &mut out,
+        "scheduler_errors_total",
+        "counter",
+        "Scheduler runs that ended in an error since process start.",
+        errors,
+    );
+    out
+}
@@ -1,53 +1,245 @@
 #![cfg(feature = "server")]
+use crate::backend::env_secs;
 use anyhow::{anyhow, Context, Result};
+use dioxus::logger::tracing::{error, info, info_span, warn, Instrument};
 use once_cell::sync::Lazy;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::{Client, Method};
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::error::Error as _;
+use std::time::Duration;
+
+/// Request timeouts: `MIKROTIK_CONNECT_TIMEOUT_SECS` and `MIKROTIK_TIMEOUT_SECS`
+/// (default 5 each) for every request, `MIKROTIK_INBOX_TIMEOUT_SECS` (default: the
+/// request timeout) for listing the inbox, which can be slow when it's full.
+struct Timeouts {
+    connect: Duration,
+    request: Duration,
+    inbox: Duration,
+}
+
+static TIMEOUTS: Lazy<Timeouts> = Lazy::new(|| {
+    let request = env_secs("MIKROTIK_TIMEOUT_SECS", 5);
+    let t = Timeouts {
+        connect: Duration::from_secs(env_secs("MIKROTIK_CONNECT_TIMEOUT_SECS", 5)),
+        request: Duration::from_secs(request),
+        inbox: Duration::from_secs(env_secs("MIKROTIK_INBOX_TIMEOUT_SECS", request)),
+    };
+    info!(
+        connect_secs = t.connect.as_secs(),
+        request_secs = t.request.as_secs(),
+        inbox_secs = t.inbox.as_secs(),
+        "timeouts"
+    );
+    t
+});
+
+/// REST path of the SMS inbox (`MIKROTIK_SMS_INBOX_PATH`, default `/rest/tool/sms/inbox`),
+/// for firmwares that expose it elsewhere.
+static INBOX_PATH: Lazy<String> = Lazy::new(|| {
+    const DEFAULT: &str = "/rest/tool/sms/inbox";
+    match env::var("MIKROTIK_SMS_INBOX_PATH") {
+        Ok(raw) if raw.trim().starts_with('/') => raw.trim().trim_end_matches('/').to_string(),
+        Ok(raw) => {
+            warn!(value = ?raw, "invalid MIKROTIK_SMS_INBOX_PATH (must start with /); using {DEFAULT}");
+            DEFAULT.to_string()
+        }
+        Err(_) => DEFAULT.to_string(),
+    }
+});
+
+/// `User-Agent` of router requests (`MIKROTIK_USER_AGENT`, default `trullo-rs/<version>`),
+/// so they stand out from browser access in the RouterOS logs.
+fn user_agent() -> String {
+    match env::var("MIKROTIK_USER_AGENT") {
+        Ok(raw) if !raw.trim().is_empty() => raw.trim().to_string(),
+        _ => concat!("trullo-rs/", env!("CARGO_PKG_VERSION")).to_string(),
+    }
+}
+
+/// Extra headers sent with every router request (`MIKROTIK_EXTRA_HEADERS`, e.g.
+/// `X-Client: trullo; X-Site: home`). Malformed entries are skipped with a warning.
+fn extra_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let Ok(raw) = env::var("MIKROTIK_EXTRA_HEADERS") else {
+        return headers;
+    };
+    for entry in raw.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+        let parsed = entry.split_once(':').and_then(|(name, value)| {
+            let name = HeaderName::from_bytes(name.trim().as_bytes()).ok()?;
+            let value = HeaderValue::from_str(value.trim()).ok()?;
+            Some((name, value))
+        });
+        match parsed {
+            Some((name, value)) => {
+                headers.insert(name, value);
+            }
+            None => warn!(entry = ?entry, "invalid MIKROTIK_EXTRA_HEADERS entry; skipping"),
+        }
+    }
+    headers
+}
 
 static CLIENT: Lazy<Client> = Lazy::new(|| {
+    // RouterOS often serves the REST API with a self-signed cert
+    let insecure = crate::backend::env_flag("MIKROTIK_INSECURE_TLS");
+    if insecure {
+        warn!("MIKROTIK_INSECURE_TLS is set; TLS certificates are NOT verified");
+    }
+    let user_agent = user_agent();
+    info!(user_agent = %user_agent, "client");
     Client::builder()
-        .connect_timeout(std::time::Duration::from_secs(5))
-        .timeout(std::time::Duration::from_secs(5))
+        .user_agent(user_agent)
+        .default_headers(extra_headers())
+        .connect_timeout(TIMEOUTS.connect)
+        .timeout(TIMEOUTS.request)
+        .danger_accept_invalid_certs(insecure)
         .build()
         .expect("client")
 });
 
+/// Development mode (`MIKROTIK_MOCK`): serve a canned inbox instead of talking to
+/// a router, and answer every sent SMS with a new status reply.
+static MOCK: Lazy<bool> = Lazy::new(|| {
+    let on = crate::backend::env_flag("MIKROTIK_MOCK");
+    if on {
+        warn!("MIKROTIK_MOCK is set; using a fake inbox, no SMS are sent");
+    }
+    on
+});
+
+/// Mock inbox, from `MIKROTIK_MOCK_FILE` (JSON array shaped like the RouterOS inbox)
+/// or a few WindTre status SMS from the last hours.
+fn mock_inbox() -> &'static std::sync::Mutex<Vec<Sms>> {
+    static INBOX: Lazy<std::sync::Mutex<Vec<Sms>>> = Lazy::new(|| {
+        let from_file = env::var("MIKROTIK_MOCK_FILE").ok().and_then(|path| {
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|c| serde_json::from_str::<Vec<Sms>>(&c).map_err(Into::into));
+            match parsed {
+                Ok(smss) => Some(smss),
+                Err(e) => {
+                    warn!(path = %path, error = %e, "mock: ignoring MIKROTIK_MOCK_FILE");
+                    None
+                }
+            }
+        });
+        let smss = from_file.unwrap_or_else(|| {
+            let now = chrono::Utc::now();
+            [(5, 61), (3, 60), (1, 58)]
+                .into_iter()
+                .map(|(hours_ago, pct)| {
+                    mock_status_sms(pct, now - chrono::Duration::hours(hours_ago))
+                })
+                .collect()
+        });
+        std::sync::Mutex::new(smss)
+    });
+    &INBOX
+}
+
+fn mock_status_sms(percentage: i32, at: chrono::DateTime<chrono::Utc>) -> Sms {
+    Sms {
+        id: format!("*mock{}", at.timestamp()),
+        message: format!("Dati: hai ancora a disposizione il {percentage}% di 100 GIGA"),
+        timestamp: Some(at.to_rfc3339()),
+        time: None,
+        received: None,
+        from: Some("WINDTRE".into()),
+    }
+}
+
+/// Append a reply one percent below the newest mock status SMS.
+fn mock_reply() {
+    let mut inbox = mock_inbox().lock().unwrap();
+    let last_pct = inbox
+        .iter()
+        .filter_map(|s| {
+            let rest = s.message.split("il ").nth(1)?;
+            rest.split('%').next()?.parse::<i32>().ok()
+        })
+        .next_back()
+        .unwrap_or(80);
+    inbox.push(mock_status_sms((last_pct - 1).max(0), chrono::Utc::now()));
+}
+
 fn base_url() -> Result<String> {
     env::var("MIKROTIK_URL").map_err(|_| anyhow!("MIKROTIK_URL not set"))
 }
 
+/// Trimmed contents of the file at env var `var` (Docker/systemd secrets), if set.
+fn secret_file(var: &str) -> Result<Option<String>> {
+    match env::var(var) {
+        Ok(path) => std::fs::read_to_string(&path)
+            .map(|raw| Some(raw.trim().to_string()))
+            .map_err(|e| anyhow!("failed to read {var} {path:?}: {e}")),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Value of the secret known as any of `names`: the `{name}_FILE` variants are checked
+/// first, in order, then the plain env vars.
+fn secret(names: &[&str]) -> Result<Option<String>> {
+    for name in names {
+        if let Some(v) = secret_file(&format!("{name}_FILE"))? {
+            return Ok(Some(v));
+        }
+    }
+    Ok(names.iter().find_map(|name| env::var(name).ok()))
+}
+
+fn basic(creds: &str) -> String {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    format!("Basic {}", STANDARD.encode(creds))
+}
+
 fn auth_header() -> Result<String> {
-    if let Ok(b64) = env::var("MIKROTIK_AUTH_BASE64") {
+    if let Some(creds) = secret_file("MIKROTIK_AUTH_FILE")? {
+        Ok(basic(&creds))
+    } else if let Some(b64) = secret(&["MIKROTIK_AUTH_BASE64"])? {
         Ok(format!("Basic {}", b64))
-    } else if let (Ok(user), Ok(pass)) = (
-        env::var("MIKROTIK_USER"),
-        env::var("MIKROTIK_PASS").or_else(|_| env::var("MIKROTIK_PASSWORD")),
+    } else if let (Some(user), Some(pass)) = (
+        secret(&["MIKROTIK_USER"])?,
+        secret(&["MIKROTIK_PASSWORD", "MIKROTIK_PASS"])?,
     ) {
-        use base64::{engine::general_purpose::STANDARD, Engine as _};
-        let creds = format!("{}:{}", user, pass);
-        let enc = STANDARD.encode(creds);
-        Ok(format!("Basic {}", enc))
+        Ok(basic(&format!("{}:{}", user, pass)))
     } else {
         Err(anyhow!(
-            "Set MIKROTIK_AUTH_BASE64 or MIKROTIK_USER and MIKROTIK_PASSWORD (or MIKROTIK_PASS)"
+            "Set MIKROTIK_AUTH_FILE (a file holding username:password), MIKROTIK_AUTH_BASE64, \
+             or MIKROTIK_USER and MIKROTIK_PASSWORD (or MIKROTIK_PASS); each of the latter \
+             can also be read from a file named by the same variable with a _FILE suffix"
         ))
     }
 }
 
+/// What's missing for talking to the router (URL or credentials); `None` when the
+/// env is complete or `MIKROTIK_MOCK` is set.
+pub fn config_problem() -> Option<String> {
+    if *MOCK {
+        return None;
+    }
+    base_url()
+        .and_then(|_| auth_header())
+        .err()
+        .map(|e| e.to_string())
+}
+
+/// Inbox entry. Aliases cover the field names seen across RouterOS versions and
+/// firmwares; `parse_data_status_from_sms` tries `timestamp`, `received`, then `time`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sms {
-    #[serde(rename = ".id")]
+    #[serde(rename = ".id", alias = "id")]
     pub id: String,
+    #[serde(alias = "msg", alias = "text")]
     pub message: String,
-    #[serde(default)]
+    #[serde(default, alias = "date-time", alias = "datetime")]
     pub timestamp: Option<String>,
-    #[serde(rename = "time")]
+    #[serde(rename = "time", alias = "date")]
     pub time: Option<String>, // RouterOS time string e.g. "aug/17/2024 15:27:02"
-    #[serde(rename = "received")]
+    #[serde(rename = "received", alias = "received-at")]
     pub received: Option<String>,
-    #[serde(rename = "from")]
+    #[serde(rename = "from", alias = "phone", alias = "sender")]
     pub from: Option<String>,
 }
 
@@ -56,8 +248,50 @@ pub async fn fetch_mikrotik<T: for<'de> Deserialize<'de> + Send + 'static>(
     method: Method,
     body: Option<serde_json::Value>,
 ) -> Result<T> {
+    fetch_mikrotik_with_timeout(path, method, body, None).await
+}
+
+/// Id of the next router request, for correlating its log lines.
+static NEXT_REQUEST_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+/// `fetch_mikrotik` with a per-request timeout overriding the client default. Each
+/// call runs in a `mikrotik_request` span carrying its id, method and path, and ends
+/// with one event: `elapsed_ms` and the HTTP `status` on success, the error (with its
+/// causes) on failure.
+pub async fn fetch_mikrotik_with_timeout<T: for<'de> Deserialize<'de> + Send + 'static>(
+    path: &str,
+    method: Method,
+    body: Option<serde_json::Value>,
+    timeout: Option<Duration>,
+) -> Result<T> {
+    let req_id = NEXT_REQUEST_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let span = info_span!("mikrotik_request", req_id, method = %method, path = %path);
+    async move {
+        let started = std::time::Instant::now();
+        let result = send_request(path, method, body, timeout).await;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        match result {
+            Ok((status, data)) => {
+                info!(elapsed_ms, status = status.as_u16(), "request ok");
+                Ok(data)
+            }
+            Err(e) => {
+                error!(elapsed_ms, error = %format_args!("{e:#}"), "request failed");
+                Err(e)
+            }
+        }
+    }
+    .instrument(span)
+    .await
+}
+
+async fn send_request<T: for<'de> Deserialize<'de> + Send + 'static>(
+    path: &str,
+    method: Method,
+    body: Option<serde_json::Value>,
+    timeout: Option<Duration>,
+) -> Result<(reqwest::StatusCode, T)> {
     let url = format!("{}{}", base_url()?, path);
-    eprintln!("[mikrotik] {} {}", method.as_str(), url);
     let method_s = method.as_str().to_string();
     let auth = auth_header()?;
     let mut req = CLIENT
@@ -68,20 +302,20 @@ pub async fn fetch_mikrotik<T: for<'de> Deserialize<'de> + Send + 'static>(
     if let Some(b) = body {
         req = req.json(&b);
     }
+    let timeout = timeout.unwrap_or(TIMEOUTS.request);
+    req = req.timeout(timeout);
     let res = match req.send().await {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("[mikrotik] request error on {} {}: {}", method_s, url, e);
+            warn!(url = %url, error = %e, "send error");
             if e.is_timeout() {
-                eprintln!("[mikrotik] hint: request timed out (client timeout ~10s)");
+                warn!(timeout_secs = timeout.as_secs(), "hint: request timed out");
             }
             if e.is_connect() {
-                eprintln!(
-                    "[mikrotik] hint: connection failed (DNS/route/refused/TLS). Check MIKROTIK_URL and network reachability"
-                );
+                warn!("hint: connection failed (DNS/route/refused/TLS). Check MIKROTIK_URL and network reachability");
             }
             if e.is_builder() {
-                eprintln!("[mikrotik] hint: request build error (invalid URL or headers)");
+                warn!("hint: request build error (invalid URL or headers)");
             }
             let mut chain = Vec::new();
             let mut src: Option<&dyn std::error::Error> = e.source();
@@ -90,7 +324,7 @@ pub async fn fetch_mikrotik<T: for<'de> Deserialize<'de> + Send + 'static>(
                 src = s.source();
             }
             if !chain.is_empty() {
-                eprintln!("[mikrotik] error chain: {}", chain.join(" -> "));
+                warn!(chain = %chain.join(" -> "), "error chain");
             }
             return Err(anyhow!("sending {} {}: {}", method_s, url, e));
         }
@@ -103,12 +337,9 @@ pub async fn fetch_mikrotik<T: for<'de> Deserialize<'de> + Send + 'static>(
             .get("www-authenticate")
             .and_then(|v| v.to_str().ok())
         {
-            eprintln!("[mikrotik] WWW-Authenticate: {}", www);
+            warn!(www_authenticate = %www, "authentication challenge");
         }
-        eprintln!(
-            "[mikrotik] request failed: status={} body=\n{}",
-            status, text
-        );
+        warn!(status = %status, body = %text, "error status");
         return Err(anyhow!(
             "{} {} failed with status {}",
             method_s,
@@ -116,6 +347,7 @@ pub async fn fetch_mikrotik<T: for<'de> Deserialize<'de> + Send + 'static>(
             status
         ));
     }
+    let status = res.status();
     let bytes = res
         .bytes()
         .await
@@ -131,20 +363,129 @@ pub async fn fetch_mikrotik<T: for<'de> Deserialize<'de> + Send + 'static>(
             snip
         )
     })?;
-    Ok(data)
+    Ok((status, data))
 }
 
 pub async fn get_smses() -> Result<Vec<Sms>> {
-    fetch_mikrotik("/rest/tool/sms/inbox", Method::GET, None).await
+    if *MOCK {
+        return Ok(mock_inbox().lock().unwrap().clone());
+    }
+    fetch_mikrotik_with_timeout(INBOX_PATH.as_str(), Method::GET, None, Some(TIMEOUTS.inbox)).await
+}
+
+/// Cheap reachability check: list only the inbox ids.
+pub async fn probe(timeout: Duration) -> Result<()> {
+    if *MOCK {
+        return Ok(());
+    }
+    let _: serde_json::Value = fetch_mikrotik_with_timeout(
+        &format!("{}?.proplist=.id", *INBOX_PATH),
+        Method::GET,
+        None,
+        Some(timeout),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Outbound message as listed by `/rest/tool/sms/sent`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SentSms {
+    #[serde(default)]
+    pub message: String,
+    #[serde(rename = "phone-number", alias = "phone", default)]
+    pub phone_number: Option<String>,
+}
+
+pub async fn get_sent_smses() -> Result<Vec<SentSms>> {
+    fetch_mikrotik("/rest/tool/sms/sent", Method::GET, None).await
+}
+
+/// How long `send_sms` waits for the message to show up in the sent folder.
+const VERIFY_SEND_WINDOW: Duration = Duration::from_secs(10);
+
+async fn count_sent(phone_number: &str, message: &str) -> Result<usize> {
+    Ok(get_sent_smses()
+        .await
+        .context("listing sent SMS")?
+        .iter()
+        .filter(|s| s.message == message && s.phone_number.as_deref() == Some(phone_number))
+        .count())
 }
 
+/// Send an SMS. With `MIKROTIK_VERIFY_SEND`, only succeed once a new matching
+/// message appears in the router's sent folder (RouterOS can accept the request
+/// and still fail to transmit).
 pub async fn send_sms(phone_number: &str, message: &str) -> Result<()> {
     let body = serde_json::json!({
         "phone-number": phone_number,
         "message": message,
     });
-    eprintln!("[mikrotik] sending SMS to {}", phone_number);
+    if *MOCK {
+        info!(
+            sms = message,
+            phone_number, "mock: not sending SMS; queueing a canned reply"
+        );
+        mock_reply();
+        return Ok(());
+    }
+    let verify = crate::backend::env_flag("MIKROTIK_VERIFY_SEND");
+    let sent_before = if verify {
+        count_sent(phone_number, message).await?
+    } else {
+        0
+    };
+    info!(phone_number, "sending SMS");
     let _: serde_json::Value =
         fetch_mikrotik("/rest/tool/sms/send", Method::POST, Some(body)).await?;
-    Ok(())
+    if !verify {
+        return Ok(());
+    }
+    let started = std::time::Instant::now();
+    while started.elapsed() < VERIFY_SEND_WINDOW {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        if count_sent(phone_number, message).await? > sent_before {
+            info!(phone_number, "SMS confirmed in sent folder");
+            return Ok(());
+        }
+    }
+    Err(anyhow!(
+        "SMS to {} not in the sent folder after {}s",
+        phone_number,
+        VERIFY_SEND_WINDOW.as_secs()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sms_from_different_firmwares() {
+        let routeros: Sms = serde_json::from_str(
+            r#"{".id": "*1", "message": "Dati: hai ancora", "phone": "4155",
+                "time": "aug/17/2024 15:27:02", "type": "class-1"}"#,
+        )
+        .unwrap();
+        assert_eq!(routeros.id, "*1");
+        assert_eq!(routeros.message, "Dati: hai ancora");
+        assert_eq!(routeros.from.as_deref(), Some("4155"));
+        assert_eq!(routeros.time.as_deref(), Some("aug/17/2024 15:27:02"));
+        assert_eq!((routeros.timestamp, routeros.received), (None, None));
+
+        let other: Sms = serde_json::from_str(
+            r#"{"id": "7", "msg": "Data: you have", "sender": "WINDTRE",
+                "date-time": "2024-08-17T15:27:02+02:00", "received-at": "2024-08-17T13:27:05Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(other.id, "7");
+        assert_eq!(other.message, "Data: you have");
+        assert_eq!(other.from.as_deref(), Some("WINDTRE"));
+        assert_eq!(
+            other.timestamp.as_deref(),
+            Some("2024-08-17T15:27:02+02:00")
+        );
+        assert_eq!(other.received.as_deref(), Some("2024-08-17T13:27:05Z"));
+        assert_eq!(other.time, None);
+    }
 }
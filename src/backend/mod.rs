@@ -1,16 +1,183 @@
 #![cfg(feature = "server")]
 
 pub mod db;
+pub mod http;
+pub mod import;
+pub mod metrics;
 pub mod mikrotik;
+pub mod notify;
 pub mod scheduler;
+pub mod usage;
 pub mod windtre;
 
 pub use crate::backend::db::GLOBAL_DB;
 pub use db::Db;
 pub use scheduler::ensure_scheduler_started_with;
 
+use crate::shared::types::{GaugeMode, GaugeStyle, LocaleConfig};
+use crate::utils::format::parse_locale;
+use dioxus::logger::tracing::warn;
+
 pub use tracing_subscriber::{fmt, prelude::*, util::SubscriberInitExt, EnvFilter};
 
+/// True when the env var is set to `1`, `true`, `yes` or `on` (case-insensitive).
+pub fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .map(|v| {
+            matches!(
+                v.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false)
+}
+
+/// Compare a client-supplied secret in constant time, so response timing doesn't leak
+/// how much of it matched.
+pub fn secret_matches(given: &str, expected: &str) -> bool {
+    use subtle::ConstantTimeEq;
+    given.as_bytes().ct_eq(expected.as_bytes()).into()
+}
+
+/// Seconds from env var `name`, else `default`; see `env_u64`.
+pub fn env_secs(name: &str, default: u64) -> u64 {
+    env_u64(name, default)
+}
+
+/// Positive integer from env var `name` (minutes, hours, ...), else `default`.
+pub fn env_u64(name: &str, default: u64) -> u64 {
+    match std::env::var(name) {
+        Ok(raw) => match raw.trim().parse::<u64>() {
+            Ok(v) if v > 0 => v,
+            _ => {
+                warn!(value = ?raw, default, "invalid {name}; using the default");
+                default
+            }
+        },
+        Err(_) => default,
+    }
+}
+
+/// Display settings for dates and times (`LOCALE`, see `utils::format::parse_locale`).
+pub static LOCALE: once_cell::sync::Lazy<LocaleConfig> =
+    once_cell::sync::Lazy::new(|| match std::env::var("LOCALE") {
+        Ok(raw) => parse_locale(&raw).unwrap_or_else(|| {
+            warn!(value = ?raw, "invalid LOCALE; using dd.mm.yyyy 24h");
+            LocaleConfig::default()
+        }),
+        Err(_) => LocaleConfig::default(),
+    });
+
+/// Timezone of times rendered on the server (`DISPLAY_TZ`, IANA name); unset leaves
+/// them as RFC 3339 until the browser formats them.
+pub static DISPLAY_TZ: once_cell::sync::Lazy<Option<chrono_tz::Tz>> =
+    once_cell::sync::Lazy::new(|| match std::env::var("DISPLAY_TZ") {
+        Ok(raw) if !raw.trim().is_empty() => match raw.trim().parse() {
+            Ok(tz) => Some(tz),
+            Err(e) => {
+                warn!(value = ?raw, error = %e, "invalid DISPLAY_TZ; ignoring it");
+                None
+            }
+        },
+        _ => None,
+    });
+
+/// Prefix the app is served under behind a reverse proxy (`BASE_PATH`, e.g. `/trullo`),
+/// else the base path the bundle was built with; empty when served at the root.
+pub fn base_path_config() -> anyhow::Result<String> {
+    resolve_base_path(
+        std::env::var("BASE_PATH").ok().as_deref(),
+        dioxus::cli_config::base_path().as_deref(),
+    )
+}
+
+/// A `BASE_PATH` the bundle wasn't built with is an error: asset URLs and the browser's
+/// server fn calls come from the bundle and would miss the prefix.
+fn resolve_base_path(env: Option<&str>, bundled: Option<&str>) -> anyhow::Result<String> {
+    let bundled = normalize_base_path(bundled.unwrap_or_default());
+    let Some(env) = env else {
+        return Ok(bundled);
+    };
+    let base = normalize_base_path(env);
+    if base != bundled {
+        anyhow::bail!(
+            "BASE_PATH {base:?} differs from the bundle's base path {bundled:?}; \
+             build with `dx bundle --base-path {}`",
+            base.trim_start_matches('/')
+        );
+    }
+    Ok(base)
+}
+
+/// Validated once at startup (see `main`), so reading it later can't fail.
+pub static BASE_PATH: once_cell::sync::Lazy<String> =
+    once_cell::sync::Lazy::new(|| base_path_config().expect("invalid BASE_PATH"));
+
+/// `trullo`, `/trullo/` and `/trullo` all become `/trullo`; `/` and empty become empty.
+pub fn normalize_base_path(raw: &str) -> String {
+    match raw.trim().trim_matches('/') {
+        "" => String::new(),
+        trimmed => format!("/{trimmed}"),
+    }
+}
+
+/// Center of the card's gauge (`DISPLAY_MODE`: `percent` or `remaining-gb`).
+pub static DISPLAY_MODE: once_cell::sync::Lazy<GaugeMode> =
+    once_cell::sync::Lazy::new(|| match std::env::var("DISPLAY_MODE") {
+        Ok(raw) => match raw.trim().to_ascii_lowercase().as_str() {
+            "percent" | "" => GaugeMode::Percent,
+            "remaining-gb" | "gb" => GaugeMode::RemainingGb,
+            _ => {
+                warn!(value = ?raw, "invalid DISPLAY_MODE; using percent");
+                GaugeMode::Percent
+            }
+        },
+        Err(_) => GaugeMode::Percent,
+    });
+
+/// Shape of the card's gauge (`GAUGE_STYLE`: `three-quarter`, `half` or `full`).
+pub static GAUGE_STYLE: once_cell::sync::Lazy<GaugeStyle> =
+    once_cell::sync::Lazy::new(|| match std::env::var("GAUGE_STYLE") {
+        Ok(raw) => match raw.trim().to_ascii_lowercase().as_str() {
+            "three-quarter" | "" => GaugeStyle::ThreeQuarter,
+            "half" => GaugeStyle::Half,
+            "full" => GaugeStyle::Full,
+            _ => {
+                warn!(value = ?raw, "invalid GAUGE_STYLE; using three-quarter");
+                GaugeStyle::ThreeQuarter
+            }
+        },
+        Err(_) => GaugeStyle::ThreeQuarter,
+    });
+
+/// Heading of the card and page title: `APP_TITLE`, else `<CARRIER_NAME> Data Status`,
+/// else `crate::api::DEFAULT_APP_TITLE`.
+pub static APP_TITLE: once_cell::sync::Lazy<String> = once_cell::sync::Lazy::new(|| {
+    let var = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    var("APP_TITLE")
+        .or_else(|| var("CARRIER_NAME").map(|c| format!("{c} Data Status")))
+        .unwrap_or_else(|| crate::api::DEFAULT_APP_TITLE.to_string())
+});
+
+/// Remaining data (MB) below which the UI warns (`LOW_DATA_THRESHOLD`); unset or
+/// invalid means no warning.
+pub static LOW_DATA_THRESHOLD_MB: once_cell::sync::Lazy<Option<i32>> =
+    once_cell::sync::Lazy::new(|| match std::env::var("LOW_DATA_THRESHOLD") {
+        Ok(raw) => match raw.trim().parse::<i32>() {
+            Ok(mb) if mb > 0 => Some(mb),
+            _ => {
+                warn!(value = ?raw, "invalid LOW_DATA_THRESHOLD; no warning");
+                None
+            }
+        },
+        Err(_) => None,
+    });
+
 pub fn init_tracing() {
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("info,sqlx::query=off,sqlx::query::describe=off"));
@@ -19,3 +186,20 @@ pub fn init_tracing() {
         .with(filter)
         .init();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_path_must_match_the_bundle() {
+        assert_eq!(resolve_base_path(None, None).unwrap(), "");
+        assert_eq!(resolve_base_path(None, Some("trullo")).unwrap(), "/trullo");
+        assert_eq!(
+            resolve_base_path(Some("/trullo/"), Some("trullo")).unwrap(),
+            "/trullo"
+        );
+        assert!(resolve_base_path(Some("/trullo"), None).is_err());
+        assert!(resolve_base_path(Some("/other"), Some("trullo")).is_err());
+    }
+}
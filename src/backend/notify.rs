@@ -0,0 +1,57 @@
+#![cfg(feature = "server")]
+//! Optional Telegram notifications (`TELEGRAM_BOT_TOKEN` and `TELEGRAM_CHAT_ID`), e.g.
+//! for the stale data alert.
+use anyhow::{Context, Result};
+use dioxus::logger::tracing::{info, warn};
+use once_cell::sync::Lazy;
+use std::time::Duration;
+
+struct Telegram {
+    token: String,
+    chat_id: String,
+}
+
+/// `None` unless both variables are set.
+static TELEGRAM: Lazy<Option<Telegram>> = Lazy::new(|| {
+    let var = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    match (var("TELEGRAM_BOT_TOKEN"), var("TELEGRAM_CHAT_ID")) {
+        (Some(token), Some(chat_id)) => Some(Telegram { token, chat_id }),
+        (None, None) => None,
+        _ => {
+            warn!("set both TELEGRAM_BOT_TOKEN and TELEGRAM_CHAT_ID; notifications are off");
+            None
+        }
+    }
+});
+
+/// Send `text` to the configured chat; a no-op without Telegram config. Failures are
+/// only logged.
+pub async fn notify(text: &str) {
+    let Some(telegram) = TELEGRAM.as_ref() else {
+        return;
+    };
+    match send(telegram, text).await {
+        Ok(()) => info!("telegram notification sent"),
+        Err(e) => warn!(error = %format_args!("{e:#}"), "telegram notification failed"),
+    }
+}
+
+async fn send(telegram: &Telegram, text: &str) -> Result<()> {
+    let url = format!("https://api.telegram.org/bot{}/sendMessage", telegram.token);
+    reqwest::Client::new()
+        .post(url)
+        .timeout(Duration::from_secs(10))
+        .json(&serde_json::json!({ "chat_id": telegram.chat_id, "text": text }))
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        // The URL carries the bot token
+        .map_err(|e| e.without_url())
+        .context("sending telegram message")?;
+    Ok(())
+}
@@ -1,16 +1,213 @@
 #![cfg(feature = "server")]
-use crate::backend::{db, windtre};
-use once_cell::sync::OnceCell;
+use crate::backend::{db, env_secs, env_u64, mikrotik, notify, windtre};
+use crate::shared::types::{
+    DataStatusDto, ForceRefreshDto, ForceRefreshStatus, RefreshMode, SchedulerStatusDto,
+};
+use dioxus::logger::tracing::{error, info, warn};
+use once_cell::sync::{Lazy, OnceCell};
 use serde::Serialize;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 
 pub static SCHED_INTERVAL_MINUTES: u64 = 60;
 
+/// How long to wait for the reply SMS and how often to check the inbox meanwhile.
+#[derive(Debug, Clone, Copy)]
+pub struct PollConfig {
+    pub timeout_secs: u64,
+    pub interval_secs: u64,
+}
+
+impl Default for PollConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 30,
+            interval_secs: 2,
+        }
+    }
+}
+
+/// `WINDTRE_POLL_TIMEOUT_SECS` / `WINDTRE_POLL_INTERVAL_SECS`; the interval must be below the timeout.
+pub static POLL_CONFIG: Lazy<PollConfig> = Lazy::new(|| {
+    let default = PollConfig::default();
+    let cfg = PollConfig {
+        timeout_secs: env_secs("WINDTRE_POLL_TIMEOUT_SECS", default.timeout_secs),
+        interval_secs: env_secs("WINDTRE_POLL_INTERVAL_SECS", default.interval_secs),
+    };
+    if cfg.interval_secs >= cfg.timeout_secs {
+        warn!(
+            interval_secs = cfg.interval_secs,
+            timeout_secs = cfg.timeout_secs,
+            "poll interval must be below poll timeout; using defaults"
+        );
+        return default;
+    }
+    cfg
+});
+
+/// Bounds of the adaptive interval, in minutes.
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveConfig {
+    pub min_minutes: u64,
+    pub max_minutes: u64,
+}
+
+/// Shortest adaptive interval accepted, since every run may send a request SMS.
+const ADAPTIVE_MIN_FLOOR_MINUTES: u64 = 5;
+
+/// Stretch or shorten the interval after each run from the recent usage
+/// (`SCHED_ADAPTIVE`, off by default) between `SCHED_ADAPTIVE_MIN_MINUTES` (default 30,
+/// at least 5) and `SCHED_ADAPTIVE_MAX_MINUTES` (default 240). `None` keeps the fixed
+/// cadence.
+static ADAPTIVE: Lazy<Option<AdaptiveConfig>> = Lazy::new(|| {
+    if !crate::backend::env_flag("SCHED_ADAPTIVE") {
+        return None;
+    }
+    let mut cfg = AdaptiveConfig {
+        min_minutes: env_secs("SCHED_ADAPTIVE_MIN_MINUTES", 30),
+        max_minutes: env_secs("SCHED_ADAPTIVE_MAX_MINUTES", 240),
+    };
+    if cfg.min_minutes < ADAPTIVE_MIN_FLOOR_MINUTES {
+        warn!(
+            min_minutes = cfg.min_minutes,
+            "SCHED_ADAPTIVE_MIN_MINUTES below {ADAPTIVE_MIN_FLOOR_MINUTES}; using {ADAPTIVE_MIN_FLOOR_MINUTES}"
+        );
+        cfg.min_minutes = ADAPTIVE_MIN_FLOOR_MINUTES;
+    }
+    if cfg.min_minutes > SCHED_INTERVAL_MINUTES || cfg.max_minutes < SCHED_INTERVAL_MINUTES {
+        warn!(
+            min_minutes = cfg.min_minutes,
+            max_minutes = cfg.max_minutes,
+            "adaptive bounds must include the {SCHED_INTERVAL_MINUTES}-minute interval; using 30-240"
+        );
+        return Some(AdaptiveConfig {
+            min_minutes: 30,
+            max_minutes: 240,
+        });
+    }
+    Some(cfg)
+});
+
+/// Usage below this rate counts as flat and stretches the interval.
+const FLAT_MB_PER_HOUR: f64 = 5.0;
+/// Usage at or above this rate counts as heavy and shortens it.
+const HEAVY_MB_PER_HOUR: f64 = 500.0;
+/// At or below this remaining share runs get more frequent whatever the usage.
+const LOW_REMAINING_PERCENT: i32 = 10;
+
+/// Next adaptive interval: halved while usage is heavy or the plan is nearly used up,
+/// doubled while it's flat, back to the base interval in between, and unchanged
+/// without a usage rate. E.g. 60 → 120 → 240 over a quiet night, 60 → 30 while
+/// streaming.
+pub fn adaptive_interval_minutes(
+    current: u64,
+    mb_per_hour: Option<f64>,
+    remaining_percentage: Option<i32>,
+    cfg: AdaptiveConfig,
+) -> u64 {
+    let low = remaining_percentage.is_some_and(|p| p <= LOW_REMAINING_PERCENT);
+    let next = match mb_per_hour {
+        _ if low => current / 2,
+        Some(rate) if rate >= HEAVY_MB_PER_HOUR => current / 2,
+        Some(rate) if rate < FLAT_MB_PER_HOUR => current * 2,
+        Some(_) => SCHED_INTERVAL_MINUTES,
+        None => current,
+    };
+    next.clamp(cfg.min_minutes, cfg.max_minutes)
+}
+
+/// Average usage in MB per hour between the first and last of `rows` (oldest first).
+/// `None` when they span under an hour or a reset lies between them.
+pub fn recent_mb_per_hour(rows: &[db::DataStatusRow]) -> Option<f64> {
+    let (first, last) = (rows.first()?, rows.last()?);
+    let hours = (last.date_time - first.date_time).num_minutes() as f64 / 60.0;
+    if hours < 1.0
+        || rows
+            .windows(2)
+            .any(|w| db::is_cycle_reset(w[0].remaining_percentage, w[1].remaining_percentage))
+    {
+        return None;
+    }
+    Some((first.remaining_data_mb - last.remaining_data_mb).max(0) as f64 / hours)
+}
+
+/// `adaptive_interval_minutes` over the readings of the last three intervals; a DB
+/// error keeps the current interval.
+async fn next_adaptive_minutes(db: &db::Db, current: u64, cfg: AdaptiveConfig) -> u64 {
+    let since = chrono::Utc::now() - chrono::Duration::minutes(current as i64 * 3);
+    match db.get_rows_since(since, false).await {
+        Ok(rows) => adaptive_interval_minutes(
+            current,
+            recent_mb_per_hour(&rows),
+            rows.last().map(|r| r.remaining_percentage),
+            cfg,
+        ),
+        Err(e) => {
+            error!(error = %e, "adaptive interval query failed");
+            current
+        }
+    }
+}
+
+/// A reading in the inbox younger than this is used as is, without sending the request
+/// SMS (`WINDTRE_MAX_AGE_MINUTES`, default one minute short of the scheduler interval,
+/// or of the shortest adaptive one).
+static MAX_AGE: Lazy<chrono::Duration> = Lazy::new(|| {
+    let interval = ADAPTIVE.map_or(SCHED_INTERVAL_MINUTES, |a| a.min_minutes);
+    chrono::Duration::minutes(
+        env_secs("WINDTRE_MAX_AGE_MINUTES", interval.saturating_sub(1)) as i64,
+    )
+});
+
+/// Upper bound of one scheduled run, inbox reads and reply polling included
+/// (`SCHED_RUN_TIMEOUT_SECS`, default the poll timeout plus a minute). A run cut off
+/// here counts as failed; the next tick starts fresh.
+static RUN_TIMEOUT: Lazy<std::time::Duration> = Lazy::new(|| {
+    std::time::Duration::from_secs(env_secs(
+        "SCHED_RUN_TIMEOUT_SECS",
+        POLL_CONFIG.timeout_secs + 60,
+    ))
+});
+
+/// With an inbox holding no parseable reading, count the newest DB reading as fresh
+/// when it's within `MAX_AGE` instead of sending an SMS (`WINDTRE_DB_FALLBACK`, off by
+/// default), for routers that delete read SMS.
+static DB_FALLBACK: Lazy<bool> = Lazy::new(|| crate::backend::env_flag("WINDTRE_DB_FALLBACK"));
+
+/// Alert when the newest stored reading is older than this (`STALE_ALERT_HOURS`, default 8).
+static STALE_ALERT_AFTER: Lazy<chrono::Duration> =
+    Lazy::new(|| chrono::Duration::hours(env_u64("STALE_ALERT_HOURS", 8) as i64));
+
+/// Readings repeating the newest row's values within this window are not stored
+/// (`DEDUP_WINDOW_SECS`, default 300).
+static DEDUP_WINDOW: Lazy<chrono::Duration> =
+    Lazy::new(|| chrono::Duration::seconds(env_secs("DEDUP_WINDOW_SECS", 300) as i64));
+
+/// Skip readings dated more than `SCHED_NEWER_ONLY_TOLERANCE_SECS` (default 60) before
+/// the newest stored one (`SCHED_NEWER_ONLY`, off by default), so a router clock
+/// running behind can't put readings out of order. `None` when off.
+static NEWER_ONLY_TOLERANCE: Lazy<Option<chrono::Duration>> = Lazy::new(|| {
+    crate::backend::env_flag("SCHED_NEWER_ONLY")
+        .then(|| chrono::Duration::seconds(env_secs("SCHED_NEWER_ONLY_TOLERANCE_SECS", 60) as i64))
+});
+
+/// Upper bound of the random delay added to the aligned first tick (`SCHED_JITTER_SECS`,
+/// default 0), so instances sharing a router don't all send their SMS at the same time.
+static SCHED_JITTER_MAX_SECS: Lazy<u64> = Lazy::new(|| match std::env::var("SCHED_JITTER_SECS") {
+    Ok(raw) => raw.trim().parse::<u64>().unwrap_or_else(|_| {
+        warn!(value = ?raw, "invalid SCHED_JITTER_SECS; using 0");
+        0
+    }),
+    Err(_) => 0,
+});
+
 pub static SCHED_HANDLE: OnceCell<Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>> =
     OnceCell::new();
 pub static STATUS: OnceCell<Arc<RwLock<SchedulerState>>> = OnceCell::new();
 
+/// Ticks whenever `STATUS` changes; subscribers (SSE) re-read the status on change.
+pub static STATUS_CHANGED: Lazy<watch::Sender<()>> = Lazy::new(|| watch::channel(()).0);
+
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct SchedulerState {
     pub started: bool,
@@ -19,75 +216,518 @@ pub struct SchedulerState {
     pub last_event: Option<String>,
     pub last_error: Option<String>,
     pub next_iteration_at: Option<String>,
+    /// `scheduler_run_once` calls since process start
+    pub runs_total: u64,
+    /// Runs that ended in an error since process start
+    pub errors_total: u64,
+    /// "no fresh data for Nh" while the newest stored reading is too old
+    pub stale_alert: Option<String>,
+    /// Set instead of starting the loop when the Mikrotik env is incomplete
+    pub unconfigured: Option<String>,
+    /// Another process holds the scheduler lease on this DB; runs are skipped
+    pub passive: bool,
+    /// Current interval in adaptive mode (`SCHED_ADAPTIVE`)
+    pub effective_interval_minutes: Option<u64>,
 }
 
-pub async fn scheduler_task(db: Arc<db::Db>) {
-    use chrono::{Timelike, Utc};
-    use tokio::time::{timeout, Duration, Instant};
+/// Current status as exposed to clients.
+pub async fn status_dto() -> SchedulerStatusDto {
+    if let Some(st) = STATUS.get() {
+        let s = st.read().await.clone();
+        // Derive true running status from the join handle, if present
+        let running = if let Some(hcell) = SCHED_HANDLE.get() {
+            let h = hcell.read().await;
+            h.as_ref().map(|j| !j.is_finished()).unwrap_or(false)
+        } else {
+            false
+        };
+        return SchedulerStatusDto {
+            started: s.started,
+            running,
+            db_url: s.db_url,
+            last_loop_at: s.last_loop_at,
+            last_event: s.last_event,
+            last_error: s.last_error,
+            next_iteration_at: s.next_iteration_at,
+            interval_minutes: s
+                .effective_interval_minutes
+                .unwrap_or(SCHED_INTERVAL_MINUTES),
+            stale_alert: s.stale_alert,
+            stale_threshold_hours: STALE_ALERT_AFTER.num_hours() as u64,
+            unconfigured: s.unconfigured,
+            passive: s.passive,
+        };
+    }
+    SchedulerStatusDto {
+        started: false,
+        running: false,
+        db_url: String::new(),
+        last_loop_at: None,
+        last_event: Some("not started".into()),
+        last_error: None,
+        next_iteration_at: None,
+        interval_minutes: SCHED_INTERVAL_MINUTES,
+        stale_alert: None,
+        stale_threshold_hours: STALE_ALERT_AFTER.num_hours() as u64,
+        unconfigured: None,
+        passive: false,
+    }
+}
 
-    eprintln!("[scheduler] background task started");
-    if let Err(_elapsed) = timeout(Duration::from_secs(10), scheduler_run_once(&db)).await {
-        eprintln!("[scheduler] initial run timed out; continuing to schedule");
-        // set in status
-        if let Some(st) = STATUS.get() {
-            let mut w = st.write().await;
-            w.last_error = Some("initial run timed out".into());
+fn notify_status_changed() {
+    STATUS_CHANGED.send_replace(());
+}
+
+/// Notify subscribers and write the state to the DB (best-effort; failures are only logged).
+async fn persist_status(db: &db::Db) {
+    notify_status_changed();
+    let Some(st) = STATUS.get() else {
+        return;
+    };
+    let snapshot = {
+        let s = st.read().await;
+        db::PersistedSchedulerState {
+            last_loop_at: s.last_loop_at.clone(),
+            last_event: s.last_event.clone(),
+            last_error: s.last_error.clone(),
+            next_iteration_at: s.next_iteration_at.clone(),
+        }
+    };
+    if let Err(e) = db.save_scheduler_state(&snapshot).await {
+        error!(error = %e, "failed to persist scheduler state");
+    }
+}
+
+/// Identifies this process as the holder of the scheduler lease.
+static LEASE_OWNER: Lazy<String> = Lazy::new(|| {
+    format!(
+        "{}-{:08x}",
+        std::process::id(),
+        rand::Rng::gen::<u32>(&mut rand::thread_rng())
+    )
+});
+
+/// How long a lease lasts without renewal: the longest run interval (up to
+/// `SCHED_ADAPTIVE_MAX_MINUTES` when adaptive) plus ten minutes, so the owner renews it
+/// at every run and a crashed owner is replaced once that much time has passed.
+fn lease_duration() -> chrono::Duration {
+    let longest = ADAPTIVE.map_or(SCHED_INTERVAL_MINUTES, |a| a.max_minutes);
+    chrono::Duration::minutes(longest as i64 + 10)
+}
+
+/// Take or renew the lease on this DB; false while another process holds it (then
+/// the scheduler stays passive). DB errors count as not holding it.
+async fn hold_lease(db: &db::Db) -> bool {
+    let held = match db
+        .try_acquire_scheduler_lease(&LEASE_OWNER, lease_duration())
+        .await
+    {
+        Ok(held) => held,
+        Err(e) => {
+            error!(error = %e, "scheduler lease check failed");
+            false
         }
+    };
+    if let Some(st) = STATUS.get() {
+        let mut w = st.write().await;
+        if w.passive == held {
+            if held {
+                info!(owner = %*LEASE_OWNER, "scheduler lease acquired; running");
+            } else {
+                warn!("another instance holds the scheduler lease; staying passive");
+                w.last_event = Some("passive (another instance active)".into());
+            }
+        }
+        w.passive = !held;
     }
+    held
+}
+
+/// `scheduler_run_once` if this process holds the lease, within `RUN_TIMEOUT`. A
+/// skipped run counts as successful, so it isn't retried early.
+async fn run_as_owner(db: &Arc<db::Db>) -> bool {
+    if !hold_lease(db).await {
+        persist_status(db).await;
+        return true;
+    }
+    run_bounded(db, *RUN_TIMEOUT, scheduler_run_once(db)).await
+}
+
+/// Await `run` for at most `timeout`; a run cut off is recorded as failed.
+async fn run_bounded(
+    db: &db::Db,
+    timeout: std::time::Duration,
+    run: impl std::future::Future<Output = bool>,
+) -> bool {
+    match tokio::time::timeout(timeout, run).await {
+        Ok(ok) => ok,
+        Err(_elapsed) => {
+            let secs = timeout.as_secs();
+            warn!(timeout_secs = secs, "run timed out");
+            if let Some(st) = STATUS.get() {
+                let mut w = st.write().await;
+                w.errors_total += 1;
+                w.last_error = Some(format!("run timed out after {secs}s"));
+                w.last_event = Some("run timed out".into());
+            }
+            persist_status(db).await;
+            false
+        }
+    }
+}
+
+/// Delay of the one early retry after a failed initial run.
+const INITIAL_RETRY_SECS: u64 = 120;
+
+/// When to retry a failed initial run: after `INITIAL_RETRY_SECS`, unless the first
+/// aligned tick (`aligned_delay_secs` away) comes soon enough that it would overlap.
+pub fn initial_retry_delay(initial_ok: bool, aligned_delay_secs: u64) -> Option<u64> {
+    (!initial_ok && aligned_delay_secs >= 2 * INITIAL_RETRY_SECS).then_some(INITIAL_RETRY_SECS)
+}
+
+pub async fn scheduler_task(db: Arc<db::Db>) {
+    use chrono::{Timelike, Utc};
+    use tokio::time::{Duration, Instant};
+
+    info!("background task started");
+    // Bounded by `RUN_TIMEOUT` like every run, so a reply that's on its way is still read
+    let initial_ok = run_as_owner(&db).await;
     let interval_secs = SCHED_INTERVAL_MINUTES * 60;
     let now = Utc::now();
     let secs_in_hour = (now.minute() as u64) * 60 + (now.second() as u64);
     let rem = secs_in_hour % interval_secs;
+    // Picked once per process; the cadence below keeps the same offset
+    let jitter_secs = match (*SCHED_JITTER_MAX_SECS).min(interval_secs) {
+        0 => 0,
+        max => rand::Rng::gen_range(&mut rand::thread_rng(), 0..=max),
+    };
     let next_delay_secs = if rem == 0 {
         interval_secs
     } else {
         interval_secs - rem
-    };
-    let mins_until = (next_delay_secs + 59) / 60;
+    } + jitter_secs;
+    let mins_until = next_delay_secs.div_ceil(60);
     // Compute and store next iteration timestamp
     let next_ts = (Utc::now() + chrono::Duration::seconds(next_delay_secs as i64)).to_rfc3339();
     if let Some(st) = STATUS.get() {
         let mut w = st.write().await;
         w.next_iteration_at = Some(next_ts.clone());
     }
-    eprintln!(
-        "[scheduler] next run in {} minute(s); cadence every {} minute(s)",
-        mins_until, SCHED_INTERVAL_MINUTES
+    persist_status(&db).await;
+    info!(
+        mins_until,
+        next_at = %next_ts,
+        cadence_min = SCHED_INTERVAL_MINUTES,
+        jitter_secs,
+        jitter_max_secs = *SCHED_JITTER_MAX_SECS,
+        "next run scheduled"
     );
     let start = Instant::now() + Duration::from_secs(next_delay_secs);
+    if let Some(retry_secs) = initial_retry_delay(initial_ok, next_delay_secs) {
+        warn!(retry_secs, "initial run failed; retrying");
+        if let Some(st) = STATUS.get() {
+            let mut w = st.write().await;
+            w.last_event = Some("initial run failed; retrying shortly".into());
+            w.next_iteration_at =
+                Some((Utc::now() + chrono::Duration::seconds(retry_secs as i64)).to_rfc3339());
+        }
+        persist_status(&db).await;
+        tokio::time::sleep(Duration::from_secs(retry_secs)).await;
+        run_as_owner(&db).await;
+        // Back to the aligned cadence
+        if let Some(st) = STATUS.get() {
+            st.write().await.next_iteration_at = Some(next_ts.clone());
+        }
+        persist_status(&db).await;
+    }
     let mut interval = tokio::time::interval_at(start, Duration::from_secs(interval_secs));
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut current_minutes = SCHED_INTERVAL_MINUTES;
     loop {
         interval.tick().await;
 
-        // Run the scheduled task
-        scheduler_run_once(&db).await;
+        // Run the scheduled task (renewing the lease first)
+        run_as_owner(&db).await;
+
+        // In adaptive mode the next tick moves with the recent usage
+        let period_secs = match *ADAPTIVE {
+            Some(cfg) => {
+                let next = next_adaptive_minutes(&db, current_minutes, cfg).await;
+                if next != current_minutes {
+                    info!(
+                        from = current_minutes,
+                        to = next,
+                        "adaptive interval changed"
+                    );
+                    current_minutes = next;
+                }
+                interval.reset_after(Duration::from_secs(next * 60));
+                next * 60
+            }
+            None => interval_secs,
+        };
 
         // Update next_iteration_at for the following tick
         if let Some(st) = STATUS.get() {
             let mut w = st.write().await;
-            let next = Utc::now() + chrono::Duration::seconds(interval_secs as i64);
+            let next = Utc::now() + chrono::Duration::seconds(period_secs as i64);
             w.next_iteration_at = Some(next.to_rfc3339());
+            if ADAPTIVE.is_some() {
+                w.effective_interval_minutes = Some(current_minutes);
+            }
         }
+        persist_status(&db).await;
     }
 }
 
-pub async fn scheduler_run_once(db: &Arc<db::Db>) {
-    eprintln!("[scheduler] run start");
+/// Set or clear `stale_alert` from the age of the newest stored reading, notifying
+/// (see `notify`) when the alert is raised and when it clears.
+async fn update_stale_alert(db: &db::Db) {
+    use chrono::Utc;
+    let newest = match db.get_latest_data_status().await {
+        Ok(r) => r.map(|r| r.date_time),
+        Err(e) => {
+            error!(error = %e, "stale check failed");
+            return;
+        }
+    };
+    let alert = match newest {
+        Some(at) if Utc::now() - at > *STALE_ALERT_AFTER => Some(format!(
+            "no fresh data for {}h",
+            (Utc::now() - at).num_hours()
+        )),
+        _ => None,
+    };
+    let Some(st) = STATUS.get() else {
+        return;
+    };
+    let mut w = st.write().await;
+    let message = match (&alert, &w.stale_alert) {
+        (Some(a), None) => {
+            warn!(alert = %a, "stale data");
+            Some(format!("Data status alert: {a}"))
+        }
+        (None, Some(_)) => {
+            info!("fresh data again; clearing stale alert");
+            Some("Data status: fresh data again".to_string())
+        }
+        _ => None,
+    };
+    if let Some(a) = &alert {
+        w.last_event = Some(a.clone());
+    }
+    w.stale_alert = alert;
+    drop(w);
+    if let Some(message) = message {
+        // Don't hold up the run on a slow notification
+        tokio::spawn(async move { notify::notify(&message).await });
+    }
+}
+
+/// Store a reading that arrived outside the scheduler loop (manual refresh, webhook)
+/// and publish it as the scheduler's last event.
+pub async fn store_reading(
+    db: &db::Db,
+    ds: &windtre::DataStatus,
+    source: &str,
+) -> anyhow::Result<db::InsertOutcome> {
+    let outcome = insert_checked(db, ds).await?;
+    let event = match outcome {
+        db::InsertOutcome::Inserted(_) => "stored fresh data",
+        db::InsertOutcome::Duplicate => "reading already stored",
+        db::InsertOutcome::Unchanged => "no change",
+        db::InsertOutcome::Stale => "stale SMS ignored",
+    };
+    info!(source, "{event}");
+    if let Some(st) = STATUS.get() {
+        st.write().await.last_event = Some(format!("{event} ({source})"));
+    }
+    update_stale_alert(db).await;
+    persist_status(db).await;
+    Ok(outcome)
+}
+
+/// Dedup insert that flags the row as suspect when its MB doesn't match the percentage
+/// of the plan (`WINDTRE_PLAN_TOTAL_MB`, else the size implied by the newest reading).
+/// With `SCHED_NEWER_ONLY` a reading older than the newest row is skipped as stale.
+async fn insert_checked(
+    db: &db::Db,
+    ds: &windtre::DataStatus,
+) -> anyhow::Result<db::InsertOutcome> {
+    insert_checked_with(db, ds, *NEWER_ONLY_TOLERANCE).await
+}
+
+/// `insert_checked` with the `SCHED_NEWER_ONLY` tolerance passed in (`None` when off).
+async fn insert_checked_with(
+    db: &db::Db,
+    ds: &windtre::DataStatus,
+    newer_only: Option<chrono::Duration>,
+) -> anyhow::Result<db::InsertOutcome> {
+    if let Some(tolerance) = newer_only {
+        if let Some(newest) = db.get_latest_data_status().await? {
+            if ds.date_time < newest.date_time - tolerance {
+                warn!(
+                    at = %ds.date_time,
+                    newest = %newest.date_time,
+                    "stale SMS ignored"
+                );
+                return Ok(db::InsertOutcome::Stale);
+            }
+        }
+    }
+    let plan_total_mb = match windtre::plan_total_mb() {
+        Some(mb) => Some(mb),
+        None => db.implied_plan_total_mb().await?,
+    };
+    let outcome = db
+        .insert_data_status_dedup(
+            ds.remaining_percentage,
+            ds.remaining_data_mb,
+            ds.date_time,
+            *DEDUP_WINDOW,
+        )
+        .await?;
+    if let (db::InsertOutcome::Inserted(id), Some(total)) = (outcome, plan_total_mb) {
+        if !windtre::is_consistent(ds.remaining_percentage, ds.remaining_data_mb, total) {
+            warn!(
+                id,
+                remaining_pct = ds.remaining_percentage,
+                remaining_mb = ds.remaining_data_mb,
+                plan_total_mb = total,
+                "suspect reading doesn't match the plan; flagged"
+            );
+            db.mark_suspect(id).await?;
+        }
+    }
+    Ok(outcome)
+}
+
+/// Manual refresh: send the status request SMS and store the reply.
+/// `Blocking` waits for the reply, `FireAndPoll` returns after sending and
+/// stores the reply from a background task. Refused while another instance holds
+/// the scheduler lease, so two processes can't both send.
+pub async fn force_refresh(db: Arc<db::Db>, mode: RefreshMode) -> ForceRefreshDto {
+    use chrono::Duration as ChronoDuration;
+    use windtre::{SmsRequestOutcome, WindtreError};
+
+    let error = |message: String| ForceRefreshDto {
+        status: ForceRefreshStatus::Error,
+        data_status: None,
+        message,
+        wait_secs: 0,
+    };
+    if !hold_lease(&db).await {
+        return error("passive: another instance holds the scheduler lease".into());
+    }
+    // The reply has to be newer than whatever is in the inbox now
+    let after = match windtre::most_recent_data_status().await {
+        Ok(ds) => ds.map(|d| d.date_time),
+        Err(e) => return error(WindtreError::MikrotikUnreachable(e).to_string()),
+    };
+    match windtre::request_data_status_sms().await {
+        Ok(SmsRequestOutcome::Sent) => {}
+        Ok(SmsRequestOutcome::Throttled { retry_in_secs }) => {
+            return error(format!(
+                "An SMS was sent recently, try again in {retry_in_secs}s"
+            ))
+        }
+        Err(e) => return error(WindtreError::SmsSendFailed(e).to_string()),
+    }
+
+    let cfg = *POLL_CONFIG;
+    let wait = async move {
+        let reply = windtre::wait_for_newer_data_status(
+            after,
+            ChronoDuration::seconds(cfg.timeout_secs as i64),
+            ChronoDuration::seconds(cfg.interval_secs as i64),
+        )
+        .await;
+        match reply {
+            Ok(Some(ds)) => match store_reading(&db, &ds, "manual refresh").await {
+                Ok(outcome) => Ok((ds, outcome)),
+                Err(e) => Err(format!("db insert error: {e}")),
+            },
+            Ok(None) => Err(WindtreError::Timeout.to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    };
+
+    match mode {
+        RefreshMode::Blocking => match wait.await {
+            Ok((ds, outcome)) => {
+                // Only a new row has an id; the other outcomes didn't store the reply
+                let (id, message) = match outcome {
+                    db::InsertOutcome::Inserted(id) => (id, "reading updated"),
+                    db::InsertOutcome::Duplicate => (0, "reading already stored"),
+                    db::InsertOutcome::Unchanged => (0, "no change"),
+                    db::InsertOutcome::Stale => (0, "stale SMS ignored"),
+                };
+                ForceRefreshDto {
+                    status: ForceRefreshStatus::Fresh,
+                    data_status: Some(DataStatusDto {
+                        remaining_percentage: ds.remaining_percentage,
+                        remaining_data_mb: ds.remaining_data_mb,
+                        date_time: ds.date_time.to_rfc3339(),
+                        id,
+                        display_time: None,
+                    }),
+                    message: message.into(),
+                    wait_secs: 0,
+                }
+            }
+            Err(e) => error(e),
+        },
+        RefreshMode::FireAndPoll => {
+            tokio::spawn(async move {
+                if let Err(e) = wait.await {
+                    error!(error = %e, "manual refresh failed");
+                }
+            });
+            ForceRefreshDto {
+                status: ForceRefreshStatus::AwaitingReply,
+                data_status: None,
+                message: "awaiting reply".into(),
+                wait_secs: cfg.timeout_secs,
+            }
+        }
+    }
+}
+
+/// One poll for a fresh reading; false when it ended in an error.
+pub async fn scheduler_run_once(db: &Arc<db::Db>) -> bool {
+    info!("run start");
     use chrono::{Duration as ChronoDuration, Utc};
     use windtre::{get_data_status_fresh, DataStatus};
     if let Some(st) = STATUS.get() {
         let mut s = st.write().await;
+        s.runs_total += 1;
         s.last_loop_at = Some(Utc::now().to_rfc3339());
         s.last_event = Some("polling for data status".into());
     }
+    let stored = if *DB_FALLBACK {
+        match db.get_latest_data_status().await {
+            Ok(row) => row.map(|r| DataStatus {
+                remaining_percentage: r.remaining_percentage,
+                remaining_data_mb: r.remaining_data_mb,
+                date_time: r.date_time,
+            }),
+            Err(e) => {
+                warn!(error = %e, "latest reading query failed; no DB fallback this run");
+                None
+            }
+        }
+    } else {
+        None
+    };
     let result = get_data_status_fresh(
         false,
-        ChronoDuration::minutes((SCHED_INTERVAL_MINUTES - 1) as i64),
-        ChronoDuration::seconds(30),
-        ChronoDuration::seconds(2),
+        *MAX_AGE,
+        ChronoDuration::seconds(POLL_CONFIG.timeout_secs as i64),
+        ChronoDuration::seconds(POLL_CONFIG.interval_secs as i64),
+        stored,
     )
     .await;
+    let mut ok = true;
     match result {
         Ok(windtre::GetDataStatusEvent::Fresh {
             data_status:
@@ -96,33 +736,56 @@ pub async fn scheduler_run_once(db: &Arc<db::Db>) {
                     remaining_data_mb,
                     date_time,
                 },
+            sms_sent,
         }) => {
-            eprintln!(
-                "[scheduler] fresh data: {}% ({} MB) at {} (age: {} min)",
-                remaining_percentage,
-                remaining_data_mb,
-                date_time,
-                (Utc::now() - date_time).num_minutes()
+            info!(
+                sms_sent,
+                remaining_pct = remaining_percentage,
+                remaining_mb = remaining_data_mb,
+                at = %date_time,
+                age_min = (Utc::now() - date_time).num_minutes(),
+                "fresh data"
             );
-            if let Err(e) = db
-                .insert_data_status(remaining_percentage, remaining_data_mb, date_time)
-                .await
-            {
-                eprintln!("[scheduler] db insert error: {e}");
-                if let Some(st) = STATUS.get() {
-                    let mut w = st.write().await;
-                    w.last_error = Some(format!("db insert error: {e}"));
+            let stored = insert_checked(
+                db,
+                &DataStatus {
+                    remaining_percentage,
+                    remaining_data_mb,
+                    date_time,
+                },
+            )
+            .await;
+            match stored {
+                Err(e) => {
+                    ok = false;
+                    error!(error = %e, "db insert error");
+                    if let Some(st) = STATUS.get() {
+                        let mut w = st.write().await;
+                        w.errors_total += 1;
+                        w.last_error = Some(format!("db insert error: {e}"));
+                    }
+                }
+                Ok(outcome) => {
+                    let event = match outcome {
+                        db::InsertOutcome::Inserted(_) => "stored fresh data",
+                        db::InsertOutcome::Stale => "stale SMS ignored",
+                        _ if !sms_sent => "still fresh, no SMS sent",
+                        db::InsertOutcome::Duplicate => "reading already stored",
+                        db::InsertOutcome::Unchanged => "no change",
+                    };
+                    info!("{event}");
+                    if let Some(st) = STATUS.get() {
+                        let mut w = st.write().await;
+                        w.last_event = Some(event.into());
+                    }
                 }
-            } else if let Some(st) = STATUS.get() {
-                let mut w = st.write().await;
-                w.last_event = Some("stored fresh data".into());
             }
         }
         Ok(windtre::GetDataStatusEvent::Loading {
             data_status: _,
             is_stale,
         }) => {
-            eprintln!("[scheduler] loading... stale={}", is_stale);
+            info!(stale = is_stale, "loading");
             if let Some(st) = STATUS.get() {
                 let mut w = st.write().await;
                 w.last_event = Some(format!("loading (stale={})", is_stale));
@@ -133,22 +796,29 @@ pub async fn scheduler_run_once(db: &Arc<db::Db>) {
             data_status: _,
             is_stale,
         }) => {
-            eprintln!("[scheduler] error: {} (stale={})", error, is_stale);
+            ok = false;
+            warn!(error = %error, kind = ?error.kind(), stale = is_stale, "status request failed");
             if let Some(st) = STATUS.get() {
                 let mut w = st.write().await;
+                w.errors_total += 1;
                 w.last_error = Some(format!("{}", error));
-                w.last_event = Some(format!("error (stale={})", is_stale));
+                w.last_event = Some(format!("error: {} (stale={})", error.kind(), is_stale));
             }
         }
         Err(e) => {
-            eprintln!("[scheduler] unexpected error: {e}");
+            ok = false;
+            error!(error = %e, "unexpected error");
             if let Some(st) = STATUS.get() {
                 let mut w = st.write().await;
+                w.errors_total += 1;
                 w.last_error = Some(format!("unexpected error: {e}"));
             }
         }
     }
-    eprintln!("[scheduler] run complete");
+    update_stale_alert(db).await;
+    persist_status(db).await;
+    info!(ok, "run complete");
+    ok
 }
 
 pub async fn ensure_scheduler_started_with(db: Arc<db::Db>, db_url: String) -> anyhow::Result<()> {
@@ -162,13 +832,44 @@ pub async fn ensure_scheduler_started_with(db: Arc<db::Db>, db_url: String) -> a
         }
     }
     if STATUS.get().is_none() {
+        // Restore the last known values so the UI has something to show right after boot
+        let persisted = match db.load_scheduler_state().await {
+            Ok(p) => p.unwrap_or_default(),
+            Err(e) => {
+                error!(error = %e, "failed to load persisted scheduler state");
+                Default::default()
+            }
+        };
         let _ = STATUS.set(Arc::new(RwLock::new(SchedulerState {
             started: false,
             db_url: db_url.clone(),
+            last_loop_at: persisted.last_loop_at,
+            last_event: persisted.last_event,
+            last_error: persisted.last_error,
+            next_iteration_at: persisted.next_iteration_at,
             ..Default::default()
         })));
     }
-    eprintln!("[scheduler] starting with DB: {}", db_url);
+    // Without a router every run would fail the same way; wait for the next boot
+    if let Some(reason) = mikrotik::config_problem() {
+        warn!(reason = %reason, "Mikrotik not configured; scheduler not started");
+        if let Some(st) = STATUS.get() {
+            let mut w = st.write().await;
+            w.started = false;
+            w.last_event = Some("Mikrotik not configured".into());
+            w.last_error = None;
+            w.next_iteration_at = None;
+            w.unconfigured = Some(reason);
+        }
+        notify_status_changed();
+        return Ok(());
+    }
+    info!(db_url = %db_url, "starting");
+    info!(
+        interval_secs = POLL_CONFIG.interval_secs,
+        timeout_secs = POLL_CONFIG.timeout_secs,
+        "reply SMS poll"
+    );
     let handle = tokio::spawn(scheduler_task(db));
     {
         let mut h_opt = handle_cell.write().await;
@@ -177,29 +878,86 @@ pub async fn ensure_scheduler_started_with(db: Arc<db::Db>, db_url: String) -> a
     if let Some(st) = STATUS.get() {
         let mut w = st.write().await;
         w.started = true;
+        w.unconfigured = None;
     }
+    notify_status_changed();
     Ok(())
 }
 
+/// Abort the background task (if any) and mark the scheduler as stopped.
+pub async fn stop() {
+    let handle = match SCHED_HANDLE.get() {
+        Some(cell) => cell.write().await.take(),
+        None => None,
+    };
+    if let Some(h) = handle {
+        h.abort();
+        // Wait for the cancellation to land so `running` is accurate right away
+        let _ = h.await;
+        info!("stopped");
+    }
+    // Let another instance take over without waiting for the lease to expire
+    if let Some(db) = db::GLOBAL_DB.get() {
+        if let Err(e) = db.release_scheduler_lease(&LEASE_OWNER).await {
+            error!(error = %e, "failed to release scheduler lease");
+        }
+    }
+    if let Some(st) = STATUS.get() {
+        let mut w = st.write().await;
+        w.started = false;
+        w.passive = false;
+        w.last_event = Some("stopped".into());
+        w.next_iteration_at = None;
+    }
+    notify_status_changed();
+}
+
+/// Stop the scheduler and spawn it again with the global DB.
+pub async fn restart() -> anyhow::Result<()> {
+    stop().await;
+    let Some(db) = db::GLOBAL_DB.get() else {
+        anyhow::bail!("GLOBAL_DB not initialized");
+    };
+    let db_url = match STATUS.get() {
+        Some(st) => st.read().await.db_url.clone(),
+        None => db::resolve_db_url(),
+    };
+    ensure_scheduler_started_with(db.clone(), db_url).await
+}
+
 // --- Test data generator (server) ---
-pub async fn generate_test_data(db: Arc<db::Db>, plan_total_mb: i32) -> anyhow::Result<()> {
+/// Days of history `gen-test-data` writes when no count is given.
+pub const TEST_DATA_DAYS: i64 = 90;
+/// RNG seed `gen-test-data` uses when none is given; the same seed gives the same data.
+pub const TEST_DATA_SEED: u64 = 42;
+
+pub async fn generate_test_data(
+    db: Arc<db::Db>,
+    plan_total_mb: i32,
+    days: i64,
+    seed: u64,
+) -> anyhow::Result<()> {
     use chrono::{Datelike, Duration, Utc};
     use dotenvy::dotenv;
     use rand::{rngs::StdRng, Rng, SeedableRng};
     dotenv().ok();
-    let mut rng = StdRng::seed_from_u64(42);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let days = days.max(1);
+    let reset_day = *crate::backend::usage::BILLING_RESET_DAY;
     let total = plan_total_mb.max(1024 * 10);
     let now = Utc::now();
-    let mut day = (now - Duration::days(90)).date_naive();
+    let mut day = (now - Duration::days(days)).date_naive();
     let end_day = now.date_naive();
-    let mut remaining: i32 = if day.day() == 1 {
+    let mut remaining: i32 = if day.day() == reset_day {
         total
     } else {
         rng.gen_range((total as f32 * 0.3) as i32..=total)
     };
+    // Written in one transaction at the end, so a failure leaves no partial data
+    let mut rows: Vec<(i32, i32, chrono::DateTime<Utc>)> = Vec::new();
     while day <= end_day {
         let mut sample_times: Vec<chrono::DateTime<Utc>> = Vec::new();
-        if day.day() == 1 {
+        if day.day() == reset_day {
             remaining = total;
             let reset_min = rng.gen_range(0..=29);
             let reset_sec = rng.gen_range(0..=59);
@@ -209,13 +967,11 @@ pub async fn generate_test_data(db: Arc<db::Db>, plan_total_mb: i32) -> anyhow::
             let reset_dt = chrono::DateTime::<Utc>::from_naive_utc_and_offset(reset_naive, Utc);
             let pct = ((remaining as f32) / (total as f32) * 100.0).round() as i32;
             if reset_dt <= now {
-                let _ = db
-                    .insert_data_status(pct.clamp(0, 100), remaining, reset_dt)
-                    .await?;
+                rows.push((pct.clamp(0, 100), remaining, reset_dt));
             }
         }
         let k: usize = rng.gen_range(1..=3);
-        let mut last_hour: u32 = if day.day() == 1 { 1 } else { 0 };
+        let mut last_hour: u32 = if day.day() == reset_day { 1 } else { 0 };
         for _ in 0..k {
             let remaining_slots = k - sample_times.len();
             let max_hour_cap = 23u32.saturating_sub((remaining_slots as u32 - 1) * 2);
@@ -256,9 +1012,7 @@ pub async fn generate_test_data(db: Arc<db::Db>, plan_total_mb: i32) -> anyhow::
             remaining_usage = remaining_usage.saturating_sub(drop_i);
             remaining = remaining.saturating_sub(drop_i);
             let pct = ((remaining as f32) / (total as f32) * 100.0).round() as i32;
-            let _ = db
-                .insert_data_status(pct.clamp(0, 100), remaining, ts)
-                .await?;
+            rows.push((pct.clamp(0, 100), remaining, ts));
         }
         if let Some(next) = day.checked_add_signed(chrono::Duration::days(1)) {
             day = next;
@@ -266,9 +1020,168 @@ pub async fn generate_test_data(db: Arc<db::Db>, plan_total_mb: i32) -> anyhow::
             break;
         }
     }
+    let inserted = db.insert_many(&rows).await?;
     eprintln!(
-        "Inserted synthetic data for ~90 days ending at {} (monthly reset to {} MB)",
-        end_day, total
+        "Inserted {} synthetic readings for {} days ending at {} (reset to {} MB on day {} of each month, seed {})",
+        inserted, days, end_day, total, reset_day, seed
     );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn reading(remaining_percentage: i32, date_time: &str) -> windtre::DataStatus {
+        windtre::DataStatus {
+            remaining_percentage,
+            remaining_data_mb: remaining_percentage * 500,
+            date_time: at(date_time),
+        }
+    }
+
+    #[tokio::test]
+    async fn inconsistent_reading_is_stored_as_suspect() {
+        let db = db::Db::in_memory().await.unwrap();
+        // 60% of the 50 GB plan: consistent, and sets the implied plan size
+        let consistent = reading(60, "2024-05-01T12:00:00Z");
+        insert_checked_with(&db, &consistent, None).await.unwrap();
+        // 90% but only 100 MB left
+        let malformed = windtre::DataStatus {
+            remaining_data_mb: 100,
+            ..reading(90, "2024-05-01T13:00:00Z")
+        };
+        let outcome = insert_checked_with(&db, &malformed, None).await.unwrap();
+        assert!(matches!(outcome, db::InsertOutcome::Inserted(_)));
+        assert_eq!(db.count_rows().await.unwrap(), 2);
+        let since = at("2024-05-01T00:00:00Z");
+        let trusted = db.get_rows_since(since, false).await.unwrap();
+        assert_eq!(trusted.len(), 1);
+        assert_eq!(trusted[0].remaining_data_mb, 30_000);
+        assert_eq!(db.get_rows_since(since, true).await.unwrap().len(), 2);
+        assert!(windtre::is_consistent(37, 37_400, 100_000));
+        assert!(!windtre::is_consistent(90, 100, 100_000));
+    }
+
+    #[tokio::test]
+    async fn stuck_run_times_out_as_failed() {
+        let db = db::Db::in_memory().await.unwrap();
+        let timeout = std::time::Duration::from_millis(50);
+        // A poll loop whose reply never arrives
+        let stuck = std::future::pending::<bool>();
+        assert!(!run_bounded(&db, timeout, stuck).await);
+        assert!(run_bounded(&db, timeout, async { true }).await);
+        assert!(!run_bounded(&db, timeout, async { false }).await);
+    }
+
+    #[test]
+    fn failed_initial_run_retries_early() {
+        // First tick 55 minutes away: retry in two minutes
+        assert_eq!(
+            initial_retry_delay(false, 55 * 60),
+            Some(INITIAL_RETRY_SECS)
+        );
+        // A successful run waits for the aligned tick
+        assert_eq!(initial_retry_delay(true, 55 * 60), None);
+        // The aligned tick comes soon anyway
+        assert_eq!(initial_retry_delay(false, 3 * 60), None);
+        assert_eq!(
+            initial_retry_delay(false, 2 * INITIAL_RETRY_SECS),
+            Some(INITIAL_RETRY_SECS)
+        );
+    }
+
+    const CFG: AdaptiveConfig = AdaptiveConfig {
+        min_minutes: 30,
+        max_minutes: 240,
+    };
+
+    fn row(
+        date_time: &str,
+        remaining_percentage: i32,
+        remaining_data_mb: i32,
+    ) -> db::DataStatusRow {
+        db::DataStatusRow {
+            id: 0,
+            remaining_percentage,
+            remaining_data_mb,
+            date_time: at(date_time),
+            created_at: at(date_time),
+        }
+    }
+
+    #[test]
+    fn adaptive_interval_follows_usage() {
+        // Quiet night: 60 -> 120 -> 240, capped there
+        assert_eq!(adaptive_interval_minutes(60, Some(1.0), Some(50), CFG), 120);
+        assert_eq!(
+            adaptive_interval_minutes(120, Some(0.0), Some(50), CFG),
+            240
+        );
+        assert_eq!(
+            adaptive_interval_minutes(240, Some(0.0), Some(50), CFG),
+            240
+        );
+        // Streaming halves it down to the minimum
+        assert_eq!(
+            adaptive_interval_minutes(60, Some(800.0), Some(50), CFG),
+            30
+        );
+        assert_eq!(
+            adaptive_interval_minutes(30, Some(800.0), Some(50), CFG),
+            30
+        );
+        // Moderate usage goes back to the base interval
+        assert_eq!(
+            adaptive_interval_minutes(240, Some(50.0), Some(50), CFG),
+            SCHED_INTERVAL_MINUTES
+        );
+        // Low remaining data polls faster even when flat; no rate keeps the interval
+        assert_eq!(adaptive_interval_minutes(120, Some(0.0), Some(5), CFG), 60);
+        assert_eq!(adaptive_interval_minutes(120, None, None, CFG), 120);
+    }
+
+    #[test]
+    fn mb_per_hour_over_recent_rows() {
+        let rows = [
+            row("2024-05-01T10:00:00Z", 50, 25_000),
+            row("2024-05-01T11:00:00Z", 49, 24_700),
+            row("2024-05-01T13:00:00Z", 49, 24_400),
+        ];
+        assert_eq!(recent_mb_per_hour(&rows), Some(200.0));
+        // Under an hour apart
+        assert_eq!(recent_mb_per_hour(&rows[..1]), None);
+        let short = [rows[0].clone(), row("2024-05-01T10:30:00Z", 50, 24_900)];
+        assert_eq!(recent_mb_per_hour(&short), None);
+        // A reset in between
+        let reset = [rows[0].clone(), row("2024-05-01T12:00:00Z", 100, 50_000)];
+        assert_eq!(recent_mb_per_hour(&reset), None);
+    }
+
+    #[tokio::test]
+    async fn out_of_order_reading_is_skipped_with_newer_only() {
+        let db = db::Db::in_memory().await.unwrap();
+        let tolerance = Some(chrono::Duration::seconds(60));
+        let newest = insert_checked_with(&db, &reading(60, "2024-05-01T12:00:00Z"), tolerance)
+            .await
+            .unwrap();
+        assert!(matches!(newest, db::InsertOutcome::Inserted(_)));
+        // A skewed router clock reports an hour earlier
+        let skewed = reading(58, "2024-05-01T11:00:00Z");
+        let outcome = insert_checked_with(&db, &skewed, tolerance).await.unwrap();
+        assert_eq!(outcome, db::InsertOutcome::Stale);
+        assert_eq!(db.count_rows().await.unwrap(), 1);
+        // Within the tolerance it's still stored
+        let close = reading(59, "2024-05-01T11:59:30Z");
+        let outcome = insert_checked_with(&db, &close, tolerance).await.unwrap();
+        assert!(matches!(outcome, db::InsertOutcome::Inserted(_)));
+        // And without `SCHED_NEWER_ONLY` the old one goes in too
+        let outcome = insert_checked_with(&db, &skewed, None).await.unwrap();
+        assert!(matches!(outcome, db::InsertOutcome::Inserted(_)));
+    }
+}
@@ -0,0 +1,754 @@
+#![cfg(feature = "server")]
+//! Day-over-day usage derived from the stored readings.
+use crate::api::{DEFAULT_USAGE_DAYS, MAX_USAGE_DAYS};
+use crate::backend::db::DataStatusRow;
+use crate::shared::types::{
+    CoverageDto, CycleProjectionDto, DailyUsagePointDto, Granularity, RemainingPointDto,
+};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc};
+use dioxus::logger::tracing::warn;
+use once_cell::sync::Lazy;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// `BILLING_RESET_DAY` (1-28) when set to a valid value. Cycle detection only
+/// checks readings against the reset day when it has been configured explicitly.
+pub static CONFIGURED_RESET_DAY: Lazy<Option<u32>> =
+    Lazy::new(|| match std::env::var("BILLING_RESET_DAY") {
+        Ok(raw) => match raw.trim().parse::<u32>() {
+            Ok(d) if (1..=28).contains(&d) => Some(d),
+            _ => {
+                warn!(value = ?raw, "invalid BILLING_RESET_DAY (1-28); using 1");
+                None
+            }
+        },
+        Err(_) => None,
+    });
+
+/// Day of the month the plan resets (`BILLING_RESET_DAY`, 1-28, default 1).
+pub static BILLING_RESET_DAY: Lazy<u32> = Lazy::new(|| CONFIGURED_RESET_DAY.unwrap_or(1));
+
+/// Midnight (UTC) of the last `reset_day` on or before `now`.
+pub fn scheduled_cycle_start(now: DateTime<Utc>, reset_day: u32) -> DateTime<Utc> {
+    let today = now.date_naive();
+    let month_start = today.with_day(1).expect("day 1 exists");
+    let start = if today.day() >= reset_day {
+        month_start
+    } else {
+        // reset_day <= 28 exists in every month
+        (month_start - Duration::days(1))
+            .with_day(1)
+            .expect("day 1 exists")
+    };
+    start
+        .with_day(reset_day)
+        .expect("reset day exists")
+        .and_time(NaiveTime::MIN)
+        .and_utc()
+}
+
+/// Midnight (UTC) of the first `reset_day` after `start`'s day: the scheduled end of
+/// the cycle that began at `start`.
+pub fn scheduled_cycle_end(start: DateTime<Utc>, reset_day: u32) -> DateTime<Utc> {
+    let day = start.date_naive();
+    let month_start = day.with_day(1).expect("day 1 exists");
+    let end_month = if day.day() < reset_day {
+        month_start
+    } else {
+        // the 1st plus 31 days always lands in the next month
+        (month_start + Duration::days(31))
+            .with_day(1)
+            .expect("day 1 exists")
+    };
+    end_month
+        .with_day(reset_day)
+        .expect("reset day exists")
+        .and_time(NaiveTime::MIN)
+        .and_utc()
+}
+
+/// Slack around the scheduled reset, for operators that apply it a day late or
+/// readings that arrive long after it.
+const RESET_DAY_TOLERANCE_DAYS: i64 = 2;
+
+/// Whether a scheduled reset on `reset_day` falls between the readings at `prev`
+/// and `cur` (give or take [`RESET_DAY_TOLERANCE_DAYS`]).
+pub fn spans_reset_day(prev: DateTime<Utc>, cur: DateTime<Utc>, reset_day: u32) -> bool {
+    let tolerance = Duration::days(RESET_DAY_TOLERANCE_DAYS);
+    scheduled_cycle_start(cur + tolerance, reset_day) >= prev - tolerance
+}
+
+/// Remaining MB at `cycle_end` after spending `avg_daily_mb` per day from `now`.
+/// E.g. 20 GB left, 1 GB a day and 8 days to go ends with ~12 GB; 4 GB left at the
+/// same pace runs out 4 days early.
+pub fn project_cycle_end(
+    remaining_mb: i32,
+    avg_daily_mb: i32,
+    now: DateTime<Utc>,
+    cycle_end: DateTime<Utc>,
+) -> CycleProjectionDto {
+    let days_left = (cycle_end - now).num_seconds().max(0) as f64 / 86_400.0;
+    let avg = avg_daily_mb.max(0) as f64;
+    let projected = remaining_mb as f64 - avg * days_left;
+    let will_run_out = projected < 0.0;
+    let days_early = if will_run_out {
+        // avg > 0 here, or nothing would be spent
+        (days_left - remaining_mb.max(0) as f64 / avg).round() as i64
+    } else {
+        0
+    };
+    CycleProjectionDto {
+        projected_remaining_at_cycle_end_mb: projected.round() as i32,
+        will_run_out,
+        days_early,
+    }
+}
+
+/// The last reading of each day that has one, oldest first. Unlike
+/// `daily_usage_series` the percentage is kept as is, resets included.
+pub fn remaining_series(rows: &[DataStatusRow]) -> Vec<RemainingPointDto> {
+    let mut last_by_day: BTreeMap<NaiveDate, &DataStatusRow> = BTreeMap::new();
+    for r in rows {
+        let day = r.date_time.date_naive();
+        match last_by_day.get(&day) {
+            Some(prev) if r.date_time <= prev.date_time => {}
+            _ => {
+                last_by_day.insert(day, r);
+            }
+        }
+    }
+    last_by_day
+        .into_values()
+        .map(|r| RemainingPointDto {
+            date_time: r.date_time.to_rfc3339(),
+            timestamp: r.date_time.timestamp(),
+            remaining_percentage: r.remaining_percentage,
+        })
+        .collect()
+}
+
+/// Readings (oldest first) split into billing cycles at the recorded cycle `starts`
+/// (ascending, see `Db::cycle_starts_since`). Only the first slice may start mid-cycle.
+pub fn split_cycles<'a>(
+    rows: &'a [DataStatusRow],
+    starts: &[DateTime<Utc>],
+) -> Vec<&'a [DataStatusRow]> {
+    let mut out = Vec::new();
+    let mut rest = rows;
+    for start in starts {
+        let at = rest.partition_point(|r| r.date_time < *start);
+        if at > 0 {
+            out.push(&rest[..at]);
+        }
+        rest = &rest[at..];
+    }
+    if !rest.is_empty() {
+        out.push(rest);
+    }
+    out
+}
+
+/// Usage (MB) accumulated by the end of each day of a cycle, counted from its first
+/// reading; index 0 is that reading's day. Days without a reading repeat the previous
+/// total, and at most `max_days` days are returned.
+pub fn cumulative_by_cycle_day(cycle: &[DataStatusRow], max_days: usize) -> Vec<i32> {
+    let Some(first) = cycle.first() else {
+        return vec![];
+    };
+    let start = first.date_time.date_naive();
+    let days = cycle
+        .last()
+        .map(|r| (r.date_time.date_naive() - start).num_days() as usize + 1)
+        .unwrap_or(1)
+        .min(max_days);
+    let mut out = vec![0; days];
+    for r in cycle {
+        let day = (r.date_time.date_naive() - start).num_days() as usize;
+        if day < days {
+            // Readings are in order, so the last one of the day wins
+            out[day] = first.remaining_data_mb - r.remaining_data_mb;
+        }
+    }
+    // Fill gaps and ignore small rises (bonus data, rounding): the total never drops
+    let mut running = 0;
+    for v in out.iter_mut() {
+        running = running.max(*v);
+        *v = running;
+    }
+    out
+}
+
+/// Usage of one day: `None` when it can't be measured (no reading that day or the day
+/// before, or remaining went up because the plan reset).
+pub struct DayUsage {
+    pub date: NaiveDate,
+    pub used_mb: Option<i32>,
+}
+
+/// Per-day usage for the `days` days ending at `now`: the last reading of each day
+/// is compared with the last reading of the previous day that had one.
+pub fn daily_usage_series(rows: &[DataStatusRow], days: i64, now: DateTime<Utc>) -> Vec<DayUsage> {
+    // For each day, keep the LAST reading (latest timestamp) of remaining_data_mb
+    let mut last_by_day: BTreeMap<NaiveDate, (DateTime<Utc>, i32)> = BTreeMap::new();
+    for r in rows {
+        let day = r.date_time.date_naive();
+        match last_by_day.get(&day) {
+            Some((ts, _)) if r.date_time <= *ts => {
+                // keep existing (we want the last of the day)
+            }
+            _ => {
+                last_by_day.insert(day, (r.date_time, r.remaining_data_mb));
+            }
+        }
+    }
+
+    // Walk the last `days` days in order, computing usage as prev_day_remaining - curr_day_remaining when both exist
+    let mut out = Vec::new();
+    let mut prev_remaining: Option<i32> = None;
+    for i in (0..days).rev() {
+        let date = (now - Duration::days(i)).date_naive();
+        let curr_remaining = last_by_day.get(&date).map(|(_, v)| *v);
+        let used_mb = match (prev_remaining, curr_remaining) {
+            (Some(prev), Some(curr)) if prev >= curr => Some(prev - curr),
+            _ => None,
+        };
+        // do not update prev_remaining when there's no reading for this day
+        if let Some(curr) = curr_remaining {
+            prev_remaining = Some(curr);
+        }
+        out.push(DayUsage { date, used_mb });
+    }
+    out
+}
+
+/// Total usage from `start` to `end` (both inclusive): the sum of the measurable days
+/// of `daily_usage_series`, so gaps and reset jumps add nothing. `rows` should start
+/// the day before `start`, which the first day is measured against.
+pub fn total_usage(rows: &[DataStatusRow], start: NaiveDate, end: NaiveDate) -> i32 {
+    if end < start {
+        return 0;
+    }
+    // Walk from the day before `start` so `start` itself has a baseline
+    let days = (end - start).num_days() + 2;
+    let now = end.and_time(NaiveTime::MIN).and_utc();
+    daily_usage_series(rows, days, now)
+        .iter()
+        .skip(1)
+        .filter_map(|d| d.used_mb)
+        .sum()
+}
+
+/// `daily_usage_series` for the chart: unmeasurable days count as 0.
+pub fn reduce_daily_usage(
+    rows: &[DataStatusRow],
+    days: i64,
+    now: DateTime<Utc>,
+) -> Vec<DailyUsagePointDto> {
+    daily_usage_series(rows, days, now)
+        .into_iter()
+        .map(|d| DailyUsagePointDto {
+            date: d.date.to_string(),
+            used_mb: d.used_mb.unwrap_or(0),
+            bucket_days: 1,
+            label: None,
+            has_data: d.used_mb.is_some(),
+        })
+        .collect()
+}
+
+/// Longest range still returned one bar per day; longer ones are bucketed by week.
+pub const DAILY_MAX_DAYS: i64 = 90;
+
+/// Sums daily points into buckets of consecutive days sharing `key`. The first and last
+/// bucket may cover fewer days; each is dated with its first day inside the range.
+fn bucket_by<K: PartialEq>(
+    points: Vec<DailyUsagePointDto>,
+    bucket_days: u32,
+    key: impl Fn(NaiveDate) -> K,
+    label: impl Fn(NaiveDate) -> String,
+) -> Vec<DailyUsagePointDto> {
+    let mut out: Vec<(K, DailyUsagePointDto)> = Vec::new();
+    for p in points {
+        let Ok(date) = p.date.parse::<NaiveDate>() else {
+            continue;
+        };
+        let k = key(date);
+        match out.last_mut() {
+            Some((last, bucket)) if *last == k => {
+                bucket.used_mb += p.used_mb;
+                bucket.has_data |= p.has_data;
+            }
+            _ => out.push((
+                k,
+                DailyUsagePointDto {
+                    date: p.date,
+                    used_mb: p.used_mb,
+                    bucket_days,
+                    label: Some(label(date)),
+                    has_data: p.has_data,
+                },
+            )),
+        }
+    }
+    out.into_iter().map(|(_, p)| p).collect()
+}
+
+/// Sums daily points into ISO weeks (Monday to Sunday), labeled like `2024-W20`. Keyed
+/// by ISO year and week, so the week around New Year stays one bucket.
+pub fn bucket_weekly(points: Vec<DailyUsagePointDto>) -> Vec<DailyUsagePointDto> {
+    bucket_by(
+        points,
+        7,
+        |d| {
+            let w = d.iso_week();
+            (w.year(), w.week())
+        },
+        |d| {
+            let w = d.iso_week();
+            format!("{}-W{:02}", w.year(), w.week())
+        },
+    )
+}
+
+/// Sums daily points into calendar months, labeled like `2024-05`.
+pub fn bucket_monthly(points: Vec<DailyUsagePointDto>) -> Vec<DailyUsagePointDto> {
+    bucket_by(
+        points,
+        30,
+        |d| (d.year(), d.month()),
+        |d| format!("{}-{:02}", d.year(), d.month()),
+    )
+}
+
+/// The chart series for `days` at `granularity`. Days switch to weeks past
+/// `DAILY_MAX_DAYS`. Reset days count as 0, so a reset never inflates a bucket.
+pub fn usage_series(
+    rows: &[DataStatusRow],
+    days: i64,
+    now: DateTime<Utc>,
+    granularity: Granularity,
+) -> Vec<DailyUsagePointDto> {
+    let days = trim_days(rows, days, now);
+    let daily = reduce_daily_usage(rows, days, now);
+    match granularity {
+        Granularity::Day if days <= DAILY_MAX_DAYS => daily,
+        Granularity::Day | Granularity::Week => bucket_weekly(daily),
+        Granularity::Month => bucket_monthly(daily),
+    }
+}
+
+/// Ranges longer than a year start at the first reading rather than with empty bars.
+fn trim_days(rows: &[DataStatusRow], days: i64, now: DateTime<Utc>) -> i64 {
+    if days > 365 {
+        let since_first = rows
+            .iter()
+            .map(|r| (now.date_naive() - r.date_time.date_naive()).num_days() + 1)
+            .max()
+            .unwrap_or(1);
+        days.min(since_first.max(1))
+    } else {
+        days
+    }
+}
+
+/// How complete the readings of the `days` days ending at `now` are. Ranges longer than
+/// a year start at the first reading, like the chart. The longest gap includes the
+/// time from the window start to the first reading and since the last one; without
+/// readings it's the whole window.
+pub fn coverage(rows: &[DataStatusRow], days: i64, now: DateTime<Utc>) -> CoverageDto {
+    let days = trim_days(rows, days, now);
+    let first_day = now.date_naive() - Duration::days(days - 1);
+    let window_start = first_day.and_time(NaiveTime::MIN).and_utc();
+    let in_window: Vec<DateTime<Utc>> = rows
+        .iter()
+        .map(|r| r.date_time)
+        .filter(|t| *t >= window_start && *t <= now)
+        .collect();
+    let with_data: BTreeSet<NaiveDate> = in_window.iter().map(|t| t.date_naive()).collect();
+    let last = rows.iter().map(|r| r.date_time).max();
+    let mut times = vec![window_start];
+    times.extend(in_window);
+    times.sort();
+    times.push(now);
+    let longest_gap_hours = times
+        .windows(2)
+        .map(|w| (w[1] - w[0]).num_hours())
+        .max()
+        .unwrap_or(0);
+    CoverageDto {
+        days_total: days,
+        days_with_data: with_data.len() as i64,
+        longest_gap_hours,
+        last_reading_age_hours: last.map(|t| (now - t).num_hours().max(0)),
+    }
+}
+
+/// Non-positive means "default", and cap at `MAX_USAGE_DAYS`.
+pub fn clamp_usage_days(days: i64) -> i64 {
+    if days <= 0 {
+        DEFAULT_USAGE_DAYS
+    } else {
+        days.min(MAX_USAGE_DAYS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn row(date_time: &str, remaining_data_mb: i32) -> DataStatusRow {
+        let date_time = at(date_time);
+        DataStatusRow {
+            id: 0,
+            remaining_percentage: remaining_data_mb / 1000,
+            remaining_data_mb,
+            date_time,
+            created_at: date_time,
+        }
+    }
+
+    #[test]
+    fn reduce_daily_usage_uses_the_last_reading_of_each_day() {
+        let now = at("2024-05-04T12:00:00Z");
+        let rows = vec![
+            row("2024-05-01T22:00:00Z", 50_000),
+            row("2024-05-02T08:00:00Z", 49_500),
+            row("2024-05-02T21:00:00Z", 49_000),
+            // No reading on the 3rd; the 4th is measured against the 2nd
+            row("2024-05-04T09:00:00Z", 47_500),
+        ];
+        let days = reduce_daily_usage(&rows, 4, now);
+        let used: Vec<(i32, bool)> = days.iter().map(|d| (d.used_mb, d.has_data)).collect();
+        assert_eq!(days[0].date, "2024-05-01");
+        assert_eq!(
+            used,
+            vec![(0, false), (1_000, true), (0, false), (1_500, true)]
+        );
+    }
+
+    #[test]
+    fn reduce_daily_usage_skips_a_reset_jump() {
+        let now = at("2024-05-02T12:00:00Z");
+        let rows = vec![
+            row("2024-05-01T20:00:00Z", 1_000),
+            row("2024-05-02T08:00:00Z", 100_000),
+        ];
+        assert_eq!(daily_usage_series(&rows, 2, now)[1].used_mb, None);
+        assert_eq!(daily_usage_series(&rows, 2, now)[1].used_mb, None);
+        let days = reduce_daily_usage(&rows, 2, now);
+        assert_eq!(days[1].used_mb, 0);
+        assert!(!days[1].has_data);
+    }
+
+    #[test]
+    fn clamp_usage_days_defaults_and_caps() {
+        assert_eq!(clamp_usage_days(0), DEFAULT_USAGE_DAYS);
+        assert_eq!(clamp_usage_days(-5), DEFAULT_USAGE_DAYS);
+        assert_eq!(clamp_usage_days(30), 30);
+        assert_eq!(clamp_usage_days(MAX_USAGE_DAYS + 1), MAX_USAGE_DAYS);
+    }
+
+    #[test]
+    fn cycle_bounds_with_reset_day_15() {
+        let start = scheduled_cycle_start(at("2024-03-10T12:00:00Z"), 15);
+        assert_eq!(start, at("2024-02-15T00:00:00Z"));
+        assert_eq!(scheduled_cycle_end(start, 15), at("2024-03-15T00:00:00Z"));
+        let start = scheduled_cycle_start(at("2024-03-15T00:00:00Z"), 15);
+        assert_eq!(start, at("2024-03-15T00:00:00Z"));
+        assert_eq!(scheduled_cycle_end(start, 15), at("2024-04-15T00:00:00Z"));
+        // Across the year boundary
+        let start = scheduled_cycle_start(at("2025-01-03T08:00:00Z"), 15);
+        assert_eq!(start, at("2024-12-15T00:00:00Z"));
+        assert_eq!(scheduled_cycle_end(start, 15), at("2025-01-15T00:00:00Z"));
+    }
+
+    #[test]
+    fn forecast_with_reset_day_15() {
+        let now = at("2024-03-10T00:00:00Z");
+        let end = scheduled_cycle_end(scheduled_cycle_start(now, 15), 15);
+        // Five days to the reset on the 15th
+        let p = project_cycle_end(20_000, 1_000, now, end);
+        assert_eq!(p.projected_remaining_at_cycle_end_mb, 15_000);
+        assert!(!p.will_run_out);
+        let p = project_cycle_end(2_000, 1_000, now, end);
+        assert_eq!(p.projected_remaining_at_cycle_end_mb, -3_000);
+        assert_eq!(p.days_early, 3);
+    }
+
+    #[test]
+    fn reset_detection_follows_reset_day_15() {
+        let before = (40, at("2024-03-14T20:00:00Z"));
+        let after = (100, at("2024-03-15T08:00:00Z"));
+        assert!(spans_reset_day(before.1, after.1, 15));
+        assert!(crate::backend::db::is_cycle_reset_on(
+            before,
+            after,
+            Some(15)
+        ));
+        // A mid-cycle top-up on the 3rd is no reset with day 15, but is without one
+        let top_up = (
+            (40, at("2024-03-02T20:00:00Z")),
+            (80, at("2024-03-03T08:00:00Z")),
+        );
+        assert!(!crate::backend::db::is_cycle_reset_on(
+            top_up.0,
+            top_up.1,
+            Some(15)
+        ));
+        assert!(crate::backend::db::is_cycle_reset_on(
+            top_up.0, top_up.1, None
+        ));
+        // The operator resetting a day late still counts
+        assert!(spans_reset_day(
+            at("2024-03-15T20:00:00Z"),
+            at("2024-03-16T08:00:00Z"),
+            15
+        ));
+    }
+
+    #[test]
+    fn scheduled_cycle_bounds_with_the_default_reset_day() {
+        let start = scheduled_cycle_start(at("2024-02-29T23:59:00Z"), 1);
+        assert_eq!(start, at("2024-02-01T00:00:00Z"));
+        assert_eq!(scheduled_cycle_end(start, 1), at("2024-03-01T00:00:00Z"));
+        let start = scheduled_cycle_start(at("2024-12-01T00:00:00Z"), 1);
+        assert_eq!(scheduled_cycle_end(start, 1), at("2025-01-01T00:00:00Z"));
+    }
+
+    fn point(date: &str, used_mb: i32) -> DailyUsagePointDto {
+        DailyUsagePointDto {
+            date: date.to_string(),
+            used_mb,
+            bucket_days: 1,
+            label: None,
+            has_data: true,
+        }
+    }
+
+    #[test]
+    fn bucket_weekly_groups_iso_weeks_across_new_year() {
+        // Mon 2024-12-30 to Sun 2025-01-05 is ISO week 2025-W01
+        let points = vec![
+            point("2024-12-29", 100),
+            point("2024-12-30", 200),
+            point("2024-12-31", 300),
+            point("2025-01-01", 400),
+            point("2025-01-06", 500),
+        ];
+        let weeks = bucket_weekly(points);
+        let got: Vec<(&str, i32, Option<&str>)> = weeks
+            .iter()
+            .map(|w| (w.date.as_str(), w.used_mb, w.label.as_deref()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                ("2024-12-29", 100, Some("2024-W52")),
+                ("2024-12-30", 900, Some("2025-W01")),
+                ("2025-01-06", 500, Some("2025-W02")),
+            ]
+        );
+        assert!(weeks.iter().all(|w| w.bucket_days == 7));
+    }
+
+    #[test]
+    fn bucket_monthly_groups_calendar_months() {
+        let mut points = vec![
+            point("2024-04-29", 100),
+            point("2024-04-30", 200),
+            point("2024-05-01", 300),
+        ];
+        points[2].has_data = false;
+        let months = bucket_monthly(points);
+        assert_eq!(months.len(), 2);
+        assert_eq!(
+            (months[0].used_mb, months[0].label.as_deref()),
+            (300, Some("2024-04"))
+        );
+        assert_eq!(
+            (months[1].used_mb, months[1].label.as_deref()),
+            (300, Some("2024-05"))
+        );
+        assert!(!months[1].has_data);
+    }
+
+    #[test]
+    fn multi_year_series_stays_bounded() {
+        let now = at("2024-05-10T12:00:00Z");
+        // Four readings a day for five years
+        let start = now - Duration::days(5 * 365);
+        let rows: Vec<DataStatusRow> = (0..5 * 365 * 4_i64)
+            .map(|i| {
+                let date_time = start + Duration::hours(6 * i);
+                DataStatusRow {
+                    id: i,
+                    remaining_percentage: 100 - (i % 120) as i32 * 100 / 120,
+                    remaining_data_mb: 100_000 - (i % 120) as i32 * 800,
+                    date_time,
+                    created_at: date_time,
+                }
+            })
+            .collect();
+        let daily = usage_series(&rows, MAX_USAGE_DAYS, now, Granularity::Day);
+        // Weekly buckets past `DAILY_MAX_DAYS`, plus partial weeks at both ends
+        assert!(daily.len() <= (5 * 365 / 7 + 2) as usize, "{}", daily.len());
+        assert!(daily.iter().all(|p| p.bucket_days == 7));
+        let monthly = usage_series(&rows, MAX_USAGE_DAYS, now, Granularity::Month);
+        assert!(monthly.len() <= 5 * 12 + 1);
+        // Up to 90 days stays daily
+        let recent = usage_series(&rows, DAILY_MAX_DAYS, now, Granularity::Day);
+        assert_eq!(recent.len(), DAILY_MAX_DAYS as usize);
+        assert!(recent.iter().all(|p| p.bucket_days == 1));
+    }
+
+    #[test]
+    fn remaining_series_keeps_the_last_percentage_of_each_day() {
+        let rows = vec![
+            row("2024-04-30T09:00:00Z", 12_000),
+            row("2024-04-30T21:00:00Z", 8_000),
+            // The reset shows as a rise, not a usage delta
+            row("2024-05-01T00:30:00Z", 100_000),
+            row("2024-05-01T20:00:00Z", 97_000),
+        ];
+        let points = remaining_series(&rows);
+        let got: Vec<(&str, i32)> = points
+            .iter()
+            .map(|p| (p.date_time.as_str(), p.remaining_percentage))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                ("2024-04-30T21:00:00+00:00", 8),
+                ("2024-05-01T20:00:00+00:00", 97),
+            ]
+        );
+        assert_eq!(points[1].timestamp, at("2024-05-01T20:00:00Z").timestamp());
+    }
+
+    #[test]
+    fn cycles_align_by_day_of_cycle() {
+        // A 28-day February cycle against the March one so far
+        let rows = vec![
+            row("2024-02-01T08:00:00Z", 100_000),
+            row("2024-02-02T08:00:00Z", 97_000),
+            row("2024-02-02T20:00:00Z", 96_000),
+            // No reading on the 3rd; a small rise on the 4th is bonus data
+            row("2024-02-04T08:00:00Z", 96_500),
+            row("2024-02-29T20:00:00Z", 40_000),
+            row("2024-03-01T08:00:00Z", 100_000),
+            row("2024-03-03T08:00:00Z", 95_000),
+        ];
+        let starts = [at("2024-03-01T08:00:00Z")];
+        let cycles = split_cycles(&rows, &starts);
+        assert_eq!(cycles.len(), 2);
+        let current = cumulative_by_cycle_day(cycles[1], 31);
+        assert_eq!(current, vec![0, 0, 5_000]);
+        // The previous cycle is cut to the current one's length
+        let previous = cumulative_by_cycle_day(cycles[0], current.len());
+        assert_eq!(previous, vec![0, 4_000, 4_000]);
+        assert_eq!(cumulative_by_cycle_day(cycles[0], 31).len(), 29);
+        assert_eq!(cumulative_by_cycle_day(cycles[0], 31)[28], 60_000);
+    }
+
+    #[test]
+    fn split_cycles_cuts_at_the_recorded_starts() {
+        let rows = vec![
+            row("2024-04-20T08:00:00Z", 40_000),
+            row("2024-04-30T08:00:00Z", 10_000),
+            row("2024-05-01T08:00:00Z", 100_000),
+            row("2024-05-20T08:00:00Z", 60_000),
+            row("2024-06-01T08:00:00Z", 100_000),
+        ];
+        let starts = [at("2024-05-01T08:00:00Z"), at("2024-06-01T08:00:00Z")];
+        let cycles = split_cycles(&rows, &starts);
+        let lens: Vec<usize> = cycles.iter().map(|c| c.len()).collect();
+        assert_eq!(lens, vec![2, 2, 1]);
+        assert_eq!(cycles[1][0].remaining_data_mb, 100_000);
+        // A start before the first reading adds no empty slice
+        let cycles = split_cycles(&rows, &[at("2024-01-01T00:00:00Z")]);
+        assert_eq!(cycles.len(), 1);
+        assert!(split_cycles(&[], &starts).is_empty());
+    }
+
+    #[test]
+    fn total_usage_over_a_window() {
+        let day = |s: &str| s.parse::<NaiveDate>().unwrap();
+        let rows = vec![
+            row("2024-05-01T20:00:00Z", 50_000),
+            row("2024-05-02T20:00:00Z", 49_000),
+            row("2024-05-03T20:00:00Z", 47_500),
+            // Nothing from the 4th to the 9th
+            row("2024-05-10T20:00:00Z", 40_000),
+            // Reset
+            row("2024-05-11T20:00:00Z", 100_000),
+            row("2024-05-12T20:00:00Z", 99_000),
+        ];
+        assert_eq!(
+            total_usage(&rows, day("2024-05-02"), day("2024-05-03")),
+            2_500
+        );
+        // The reset jump adds nothing
+        assert_eq!(
+            total_usage(&rows, day("2024-05-11"), day("2024-05-12")),
+            1_000
+        );
+        // Fully inside the gap
+        assert_eq!(total_usage(&rows, day("2024-05-05"), day("2024-05-08")), 0);
+        assert_eq!(total_usage(&rows, day("2024-05-03"), day("2024-05-02")), 0);
+    }
+
+    #[test]
+    fn coverage_reports_a_multi_day_gap() {
+        let now = at("2024-05-10T12:00:00Z");
+        let rows = vec![
+            row("2024-05-04T08:00:00Z", 90_000),
+            row("2024-05-05T08:00:00Z", 89_000),
+            // Nothing for three days
+            row("2024-05-08T20:00:00Z", 85_000),
+            row("2024-05-10T08:00:00Z", 84_000),
+        ];
+        let c = coverage(&rows, 7, now);
+        assert_eq!(c.days_total, 7);
+        assert_eq!(c.days_with_data, 4);
+        assert_eq!(c.longest_gap_hours, 3 * 24 + 12);
+        assert_eq!(c.last_reading_age_hours, Some(4));
+    }
+
+    #[test]
+    fn coverage_counts_the_window_start_and_an_empty_window() {
+        let now = at("2024-05-10T12:00:00Z");
+        // Window starts 2024-05-04 00:00; the first reading is two and a half days in
+        let rows = vec![row("2024-05-06T12:00:00Z", 90_000)];
+        assert_eq!(coverage(&rows, 7, now).longest_gap_hours, 4 * 24);
+        let empty = coverage(&[], 7, now);
+        assert_eq!(empty.days_with_data, 0);
+        assert_eq!(empty.longest_gap_hours, 6 * 24 + 12);
+        assert_eq!(empty.last_reading_age_hours, None);
+    }
+
+    #[test]
+    fn projection_with_data_to_spare() {
+        // 20 GB left at 1 GB a day with 8 days to go
+        let now = at("2024-05-23T00:00:00Z");
+        let p = project_cycle_end(20_000, 1_000, now, at("2024-05-31T00:00:00Z"));
+        assert_eq!(p.projected_remaining_at_cycle_end_mb, 12_000);
+        assert!(!p.will_run_out);
+        assert_eq!(p.days_early, 0);
+    }
+
+    #[test]
+    fn projection_with_a_shortfall() {
+        // 4 GB at the same pace lasts 4 of the 8 days
+        let now = at("2024-05-23T00:00:00Z");
+        let p = project_cycle_end(4_000, 1_000, now, at("2024-05-31T00:00:00Z"));
+        assert_eq!(p.projected_remaining_at_cycle_end_mb, -4_000);
+        assert!(p.will_run_out);
+        assert_eq!(p.days_early, 4);
+        // Nothing spent means nothing runs out
+        let idle = project_cycle_end(0, 0, now, at("2024-05-31T00:00:00Z"));
+        assert!(!idle.will_run_out);
+    }
+}
@@ -1,8 +1,55 @@
 #![cfg(feature = "server")]
 use crate::backend::mikrotik::{get_smses, send_sms, Sms};
 use anyhow::Result;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, LocalResult, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use dioxus::logger::tracing::{debug, warn};
+use once_cell::sync::Lazy;
 use regex::Regex;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Minimum spacing between status request SMS (`WINDTRE_MIN_SMS_INTERVAL_SECS`, default 60).
+static MIN_SMS_INTERVAL: Lazy<std::time::Duration> = Lazy::new(|| {
+    let secs = match std::env::var("WINDTRE_MIN_SMS_INTERVAL_SECS") {
+        Ok(raw) => raw.trim().parse::<u64>().unwrap_or_else(|_| {
+            warn!(value = ?raw, "invalid WINDTRE_MIN_SMS_INTERVAL_SECS; using 60");
+            60
+        }),
+        Err(_) => 60,
+    };
+    std::time::Duration::from_secs(secs)
+});
+
+/// When the last status request SMS went out (manual or scheduled).
+static LAST_SMS_SENT: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// Number and keyword of the carrier's data status request (default: WindTre Italy).
+#[derive(Debug, Clone)]
+pub struct CarrierConfig {
+    pub shortcode: String,
+    pub request_body: String,
+}
+
+/// Read `CARRIER_SHORTCODE` / `CARRIER_REQUEST_BODY`. Set-but-blank values are an error
+/// rather than silently falling back to WindTre's `4155`.
+pub fn carrier_config() -> Result<CarrierConfig> {
+    fn var(name: &str, default: &str) -> Result<String> {
+        match std::env::var(name) {
+            Ok(v) if v.trim().is_empty() => anyhow::bail!("{name} is set but empty"),
+            Ok(v) => Ok(v.trim().to_string()),
+            Err(_) => Ok(default.to_string()),
+        }
+    }
+    Ok(CarrierConfig {
+        shortcode: var("CARRIER_SHORTCODE", "4155")?,
+        request_body: var("CARRIER_REQUEST_BODY", "Dati")?,
+    })
+}
+
+/// Validated once at startup (see `main`), so reading it later can't fail.
+static CARRIER: Lazy<CarrierConfig> =
+    Lazy::new(|| carrier_config().expect("invalid carrier config"));
 
 #[derive(Debug, Clone)]
 pub struct DataStatus {
@@ -11,20 +58,185 @@ pub struct DataStatus {
     pub date_time: DateTime<Utc>,
 }
 
-fn regex() -> &'static Regex {
-    static REGEX: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
-        Regex::new(r"Dati: hai ancora a disposizione il (\d+)% di ([\d,]+) GIGA.*").unwrap()
+/// Status SMS patterns, tried in order. Group 1 is the remaining percentage,
+/// group 2 the plan total in GB (see `parse_total_gb`).
+fn patterns() -> &'static [Regex] {
+    static PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+        [
+            // Italian (home network)
+            r"Dati: hai ancora a disposizione il (\d+)% di ([\d.,]+) ?(?:GIGA|GB)",
+            // English (sometimes sent while roaming)
+            r"(?i)Data: you (?:still )?have (\d+)% of ([\d.,]+) ?(?:GIGA|GB)",
+        ]
+        .iter()
+        .map(|p| Regex::new(p).unwrap())
+        .collect()
     });
-    &REGEX
+    &PATTERNS
+}
+
+/// Fixed plan size (`WINDTRE_PLAN_TOTAL_MB`) used instead of the total quoted in the SMS.
+static PLAN_TOTAL_MB: Lazy<Option<i32>> = Lazy::new(|| {
+    let raw = std::env::var("WINDTRE_PLAN_TOTAL_MB").ok()?;
+    match raw.trim().parse::<i32>() {
+        Ok(mb) if mb > 0 => Some(mb),
+        _ => {
+            warn!(
+                value = ?raw,
+                "ignoring invalid WINDTRE_PLAN_TOTAL_MB; using the total from the SMS"
+            );
+            None
+        }
+    }
+});
+
+/// Only SMS from this sender count as status SMS (`WINDTRE_SENDER`, e.g. `4155` or
+/// `WINDTRE`, compared ignoring case); unset accepts any sender.
+static SENDER: Lazy<Option<String>> = Lazy::new(|| {
+    std::env::var("WINDTRE_SENDER")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+});
+
+/// Whether `sms` may be a status SMS under `WINDTRE_SENDER`. Without a known sender it
+/// can't be trusted, so it's rejected once the filter is set.
+fn from_expected_sender(sms: &Sms) -> bool {
+    from_sender(sms, SENDER.as_deref())
+}
+
+fn from_sender(sms: &Sms, expected: Option<&str>) -> bool {
+    let Some(expected) = expected else {
+        return true;
+    };
+    let ok = sms
+        .from
+        .as_deref()
+        .is_some_and(|from| from.trim().eq_ignore_ascii_case(expected));
+    if !ok {
+        debug!(id = %sms.id, from = ?sms.from, "ignoring SMS from another sender");
+    }
+    ok
+}
+
+/// `WINDTRE_PLAN_TOTAL_MB`, when set.
+pub fn plan_total_mb() -> Option<i32> {
+    *PLAN_TOTAL_MB
+}
+
+/// How far (in percent of the plan) the MB may be from `percentage × plan` before a
+/// reading counts as inconsistent; covers the 1% rounding of the percentage.
+const CONSISTENCY_TOLERANCE_PERCENT: f64 = 5.0;
+
+/// Whether `remaining_data_mb` matches `remaining_percentage` of a `plan_total_mb` plan.
+pub fn is_consistent(
+    remaining_percentage: i32,
+    remaining_data_mb: i32,
+    plan_total_mb: i32,
+) -> bool {
+    let expected = remaining_percentage as f64 / 100.0 * plan_total_mb as f64;
+    let tolerance = CONSISTENCY_TOLERANCE_PERCENT / 100.0 * plan_total_mb as f64;
+    (remaining_data_mb as f64 - expected).abs() <= tolerance
+}
+
+/// Plan total in GB as quoted in the SMS: `50`, a decimal `1,5` / `1.5`, or grouped
+/// thousands (`1.024.000`, `1.024,5`, `1,024.5`). A single separator before exactly
+/// three digits (`1.024`) may be either and is rejected rather than guessed.
+fn parse_total_gb(s: &str) -> Option<f64> {
+    let seps: Vec<(usize, char)> = s
+        .char_indices()
+        .filter(|(_, c)| !c.is_ascii_digit())
+        .collect();
+    let Some(&(last_at, last)) = seps.last() else {
+        return s.parse().ok();
+    };
+    let all_same = seps.iter().all(|(_, c)| *c == last);
+    // Where the decimal part starts, if any
+    let decimal_at = match (all_same, seps.len()) {
+        (true, 1) => match s.len() - last_at - 1 {
+            1 | 2 => Some(last_at),
+            _ => return None,
+        },
+        (true, _) => None,
+        (false, _) if seps[..seps.len() - 1].iter().all(|(_, c)| *c != last) => Some(last_at),
+        (false, _) => return None,
+    };
+    let (int_part, frac) = match decimal_at {
+        Some(i) if i + 1 < s.len() => (&s[..i], &s[i + 1..]),
+        Some(_) => return None,
+        None => (s, ""),
+    };
+    // Every group after the first has three digits
+    let mut groups = int_part.split(|c: char| !c.is_ascii_digit());
+    let first = groups.next()?;
+    if first.is_empty() || first.len() > 3 && int_part.len() != first.len() {
+        return None;
+    }
+    let mut digits = first.to_string();
+    for g in groups {
+        if g.len() != 3 {
+            return None;
+        }
+        digits.push_str(g);
+    }
+    if !frac.is_empty() {
+        digits.push('.');
+        digits.push_str(frac);
+    }
+    digits.parse().ok()
 }
 
 fn parse_sms_message(message: &str, date_time: DateTime<Utc>) -> Option<DataStatus> {
-    let re = regex();
-    let caps = re.captures(message)?;
-    let remaining_percentage: i32 = caps.get(1)?.as_str().parse().ok()?;
-    let total_gb_str = caps.get(2)?.as_str().replace(',', ".");
-    let total_gb: f64 = total_gb_str.parse().ok()?;
-    let total_mb = (total_gb * 1000.0).round() as i32;
+    parse_sms_message_with_total(message, date_time, *PLAN_TOTAL_MB)
+}
+
+/// Parse a status SMS; `plan_total_mb` overrides the (rounded) total quoted in the message.
+/// A percentage outside 0–100 rejects the message rather than being clamped, since the
+/// MB figure derived from it would be just as wrong.
+fn parse_sms_message_with_total(
+    message: &str,
+    date_time: DateTime<Utc>,
+    plan_total_mb: Option<i32>,
+) -> Option<DataStatus> {
+    let caps = patterns().iter().find_map(|re| re.captures(message))?;
+    status_from_fields(
+        caps.get(1)?.as_str(),
+        caps.get(2).map(|m| m.as_str()),
+        date_time,
+        plan_total_mb,
+    )
+}
+
+/// A reading from the captured percentage and plan total (`total_raw` may be missing
+/// when `plan_total_mb` is set).
+fn status_from_fields(
+    percentage_raw: &str,
+    total_raw: Option<&str>,
+    date_time: DateTime<Utc>,
+    plan_total_mb: Option<i32>,
+) -> Option<DataStatus> {
+    let remaining_percentage: i32 = percentage_raw.parse().ok()?;
+    if !(0..=100).contains(&remaining_percentage) {
+        warn!(
+            percentage = remaining_percentage,
+            "status SMS with a percentage outside 0-100; ignoring it"
+        );
+        return None;
+    }
+    let total_mb = match plan_total_mb {
+        Some(mb) => mb,
+        None => {
+            let raw = total_raw?;
+            let Some(total_gb) = parse_total_gb(raw) else {
+                warn!(
+                    total = raw,
+                    "ambiguous or malformed plan total in status SMS; ignoring it"
+                );
+                return None;
+            };
+            (total_gb * 1000.0).round() as i32
+        }
+    };
     let remaining_data_mb =
         ((remaining_percentage as f64 / 100.0) * total_mb as f64).round() as i32;
 
@@ -35,6 +247,33 @@ fn parse_sms_message(message: &str, date_time: DateTime<Utc>) -> Option<DataStat
     })
 }
 
+/// Timezone the router's clock runs in (`ROUTER_TZ`, IANA name, default UTC).
+static ROUTER_TZ: Lazy<Tz> = Lazy::new(|| match std::env::var("ROUTER_TZ") {
+    Ok(name) if !name.trim().is_empty() => name.trim().parse().unwrap_or_else(|e| {
+        warn!(value = ?name, error = %e, "invalid ROUTER_TZ; falling back to UTC");
+        Tz::UTC
+    }),
+    _ => Tz::UTC,
+});
+
+/// Parse a RouterOS local time string (`aug/17/2024 15:27:02` or the newer
+/// `2024-08-17 15:27:02`) and interpret it in `tz`.
+fn parse_router_time(t: &str, tz: Tz) -> Option<DateTime<Utc>> {
+    // RouterOS capitalization of month names varies between versions
+    let norm = t.trim().to_ascii_lowercase();
+    let naive = NaiveDateTime::parse_from_str(&norm, "%b/%d/%Y %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(&norm, "%Y-%m-%d %H:%M:%S"))
+        .ok()?;
+    match tz.from_local_datetime(&naive) {
+        LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => Some(dt.with_timezone(&Utc)),
+        // Inside a DST gap: the clock can't show this time, so treat it as already shifted
+        LocalResult::None => tz
+            .from_local_datetime(&(naive + Duration::hours(1)))
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc)),
+    }
+}
+
 fn sms_date(sms: &Sms) -> Option<DateTime<Utc>> {
     if let Some(ts) = &sms.timestamp {
         if let Ok(dt) = DateTime::parse_from_rfc3339(ts) {
@@ -47,42 +286,230 @@ fn sms_date(sms: &Sms) -> Option<DateTime<Utc>> {
         }
     }
     if let Some(t) = &sms.time {
-        let norm = t.replace("Aug", "aug").replace("Sep", "sep");
-        if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(&norm, "%b/%d/%Y %H:%M:%S") {
-            return Some(chrono::DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc));
-        }
+        return parse_router_time(t, *ROUTER_TZ);
     }
     None
 }
 
-pub async fn most_recent_data_status() -> Result<Option<DataStatus>> {
+/// The inbox newest first; SMS without a readable date sort last.
+async fn smses_newest_first() -> Result<Vec<Sms>> {
     let mut smss = get_smses().await?;
     smss.sort_by_key(|s| sms_date(s).map(|d| d.timestamp()).unwrap_or(0));
     smss.reverse();
-    for sms in smss.iter() {
-        if let Some(dt) = sms_date(sms) {
-            if let Some(ds) = parse_sms_message(&sms.message, dt) {
-                return Ok(Some(ds));
+    Ok(smss)
+}
+
+/// The `limit` newest SMS with their parsed date, unparsed, for comparing the wording
+/// against the patterns.
+pub async fn newest_smses(limit: usize) -> Result<Vec<(Sms, Option<DateTime<Utc>>)>> {
+    let smss = smses_newest_first().await?;
+    Ok(smss
+        .into_iter()
+        .take(limit)
+        .map(|s| {
+            let date = sms_date(&s);
+            (s, date)
+        })
+        .collect())
+}
+
+/// Part patterns for a status split over several SMS, group 1 holding the value: the
+/// remaining percentage and the plan total (see `MatchStrategy::Combined`).
+fn part_patterns() -> &'static (Regex, Regex) {
+    static PARTS: Lazy<(Regex, Regex)> = Lazy::new(|| {
+        (
+            Regex::new(r"(?i)(?:hai ancora a disposizione il|you (?:still )?have) (\d+)%").unwrap(),
+            Regex::new(r"(?i)\b(?:di|of|totale|total):? ([\d.,]+) ?(?:GIGA|GB)").unwrap(),
+        )
+    });
+    &PARTS
+}
+
+/// How the inbox is searched for the newest status.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchStrategy {
+    /// One SMS matching a whole status pattern
+    Single,
+    /// As `Single`, or SMS received within `window` of each other whose parts
+    /// together carry the percentage and the plan total
+    Combined { window: Duration },
+}
+
+/// `Combined` when `WINDTRE_COMBINE_WINDOW_SECS` is set, else `Single`.
+static MATCH_STRATEGY: Lazy<MatchStrategy> =
+    Lazy::new(|| match std::env::var("WINDTRE_COMBINE_WINDOW_SECS") {
+        Ok(_) => MatchStrategy::Combined {
+            window: Duration::seconds(
+                crate::backend::env_secs("WINDTRE_COMBINE_WINDOW_SECS", 120) as i64
+            ),
+        },
+        Err(_) => MatchStrategy::Single,
+    });
+
+/// The newest status among `dated` (message and time, newest first). A combined
+/// reading only wins over a single SMS that is older than its newest part.
+fn find_data_status(
+    dated: &[(&str, DateTime<Utc>)],
+    strategy: MatchStrategy,
+) -> Option<DataStatus> {
+    for (i, (message, dt)) in dated.iter().enumerate() {
+        if let Some(ds) = parse_sms_message(message, *dt) {
+            return Some(ds);
+        }
+        if let MatchStrategy::Combined { window } = strategy {
+            if let Some(ds) = combine_parts(&dated[i..], window) {
+                return Some(ds);
             }
-        } else {
-            eprintln!(
-                "[windtre] could not parse date for SMS id={} from={:?}",
-                sms.id, sms.from
-            );
         }
     }
-    Ok(None)
+    None
+}
+
+/// Merge the fields of the parts within `window` of the first (newest) SMS in `dated`,
+/// which must be a part itself. Each field comes from the newest part carrying it, and
+/// the reading is dated like the newest part that contributed. SMS that parse as a
+/// whole status are not parts.
+fn combine_parts(dated: &[(&str, DateTime<Utc>)], window: Duration) -> Option<DataStatus> {
+    let (percentage_re, total_re) = part_patterns();
+    let &(first, newest) = dated.first()?;
+    if !percentage_re.is_match(first) && !total_re.is_match(first) {
+        return None;
+    }
+    let capture = |re: &Regex, message: &'_ str| -> Option<String> {
+        Some(re.captures(message)?.get(1)?.as_str().to_string())
+    };
+    let mut percentage = None;
+    let mut total = None;
+    let mut date_time = None;
+    for &(message, dt) in dated.iter().take_while(|(_, dt)| newest - *dt <= window) {
+        if parse_sms_message(message, dt).is_some() {
+            continue;
+        }
+        let (p, t) = (capture(percentage_re, message), capture(total_re, message));
+        if (percentage.is_none() && p.is_some()) || (total.is_none() && t.is_some()) {
+            date_time = date_time.or(Some(dt));
+        }
+        percentage = percentage.or(p);
+        total = total.or(t);
+    }
+    status_from_fields(&percentage?, total.as_deref(), date_time?, *PLAN_TOTAL_MB)
 }
 
-pub async fn request_data_status_sms() -> Result<()> {
-    send_sms("4155", "Dati").await
+pub async fn most_recent_data_status() -> Result<Option<DataStatus>> {
+    let smss = smses_newest_first().await?;
+    let mut dated = Vec::new();
+    for sms in smss.iter().filter(|s| from_expected_sender(s)) {
+        match sms_date(sms) {
+            Some(dt) => dated.push((sms.message.as_str(), dt)),
+            None => warn!(id = %sms.id, from = ?sms.from, "could not parse date for SMS"),
+        }
+    }
+    Ok(find_data_status(&dated, *MATCH_STRATEGY))
+}
+
+pub enum SmsRequestOutcome {
+    Sent,
+    Throttled { retry_in_secs: u64 },
+}
+
+/// Record a send at `now` unless the last one was less than `min_interval` ago.
+/// Returns the previous send time (to restore if sending fails), or the seconds
+/// until the next send is allowed.
+fn reserve_sms_slot(
+    last: &mut Option<Instant>,
+    now: Instant,
+    min_interval: std::time::Duration,
+) -> Result<Option<Instant>, u64> {
+    if let Some(at) = *last {
+        let elapsed = now.saturating_duration_since(at);
+        if elapsed < min_interval {
+            return Err((min_interval - elapsed).as_secs().max(1));
+        }
+    }
+    Ok(last.replace(now))
+}
+
+/// Send the status request SMS unless one went out within `MIN_SMS_INTERVAL`.
+/// Shared by the scheduler and manual requests so neither can burn SMS quota.
+pub async fn request_data_status_sms() -> Result<SmsRequestOutcome> {
+    // Reserve the slot up front so concurrent callers can't both send
+    let reserved = reserve_sms_slot(
+        &mut LAST_SMS_SENT.lock().unwrap(),
+        Instant::now(),
+        *MIN_SMS_INTERVAL,
+    );
+    let previous = match reserved {
+        Ok(previous) => previous,
+        Err(retry_in_secs) => return Ok(SmsRequestOutcome::Throttled { retry_in_secs }),
+    };
+    if let Err(e) = send_sms(&CARRIER.shortcode, &CARRIER.request_body).await {
+        *LAST_SMS_SENT.lock().unwrap() = previous;
+        return Err(e);
+    }
+    Ok(SmsRequestOutcome::Sent)
+}
+
+/// Poll the inbox until a reading newer than `after` shows up. `Ok(None)` on timeout.
+pub async fn wait_for_newer_data_status(
+    after: Option<DateTime<Utc>>,
+    timeout: Duration,
+    poll: Duration,
+) -> Result<Option<DataStatus>, WindtreError> {
+    let start = Utc::now();
+    loop {
+        tokio::time::sleep(poll.to_std().unwrap()).await;
+        if let Some(ds) = most_recent_data_status()
+            .await
+            .map_err(WindtreError::MikrotikUnreachable)?
+        {
+            if after.is_none_or(|a| ds.date_time > a) {
+                return Ok(Some(ds));
+            }
+        }
+        if Utc::now() - start > timeout {
+            return Ok(None);
+        }
+    }
 }
 
 pub fn parse_data_status_from_sms(sms: &Sms) -> Option<DataStatus> {
+    if !from_expected_sender(sms) {
+        return None;
+    }
     let dt = sms_date(sms)?;
     parse_sms_message(&sms.message, dt)
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum WindtreError {
+    #[error("Mikrotik unreachable: {0:#}")]
+    MikrotikUnreachable(#[source] anyhow::Error),
+    #[error("sending status SMS failed: {0:#}")]
+    SmsSendFailed(#[source] anyhow::Error),
+    #[error("Timeout waiting for new SMS")]
+    Timeout,
+    #[error("no parseable WindTre SMS in inbox")]
+    NoParseableSms,
+    #[error("inbox is empty, no reply to the status SMS yet")]
+    EmptyInbox,
+    #[error("throttled: a status SMS was sent recently, next allowed in {retry_in_secs}s")]
+    Throttled { retry_in_secs: u64 },
+}
+
+impl WindtreError {
+    /// Short machine-friendly name, e.g. for the scheduler's `last_event`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            WindtreError::MikrotikUnreachable(_) => "mikrotik unreachable",
+            WindtreError::SmsSendFailed(_) => "sms send failed",
+            WindtreError::Timeout => "timeout",
+            WindtreError::NoParseableSms => "no parseable sms",
+            WindtreError::EmptyInbox => "empty inbox",
+            WindtreError::Throttled { .. } => "throttled",
+        }
+    }
+}
+
 pub enum GetDataStatusEvent {
     Loading {
         #[allow(dead_code)]
@@ -91,58 +518,139 @@ pub enum GetDataStatusEvent {
     },
     Fresh {
         data_status: DataStatus,
+        /// False when the inbox already held a reading within `max_age`
+        sms_sent: bool,
     },
     Error {
-        error: anyhow::Error,
+        error: WindtreError,
         #[allow(dead_code)]
         data_status: Option<DataStatus>,
         is_stale: bool,
     },
 }
 
+/// Whether the inbox's newest reading is older than `max_age`. No reading at all (cold
+/// start with an empty inbox, or nothing parses) counts as stale too, so the request
+/// SMS goes out instead of waiting for one to show up.
+fn is_stale(current: Option<&DataStatus>, now: DateTime<Utc>, max_age: Duration) -> bool {
+    current.is_none_or(|d| now - d.date_time > max_age)
+}
+
+/// Why polling found no reading: the reply just hasn't arrived, vs. SMS the patterns
+/// don't match.
+fn no_status_error(inbox: &[Sms]) -> WindtreError {
+    if inbox.is_empty() {
+        WindtreError::EmptyInbox
+    } else {
+        WindtreError::NoParseableSms
+    }
+}
+
+/// Unless `force`, a stored reading within `max_age` if the inbox has no parseable status.
+fn fresh_from_stored(
+    force: bool,
+    current: Option<&DataStatus>,
+    stored: Option<DataStatus>,
+    now: DateTime<Utc>,
+    max_age: Duration,
+) -> Option<GetDataStatusEvent> {
+    if force || current.is_some() {
+        return None;
+    }
+    stored
+        .filter(|d| now - d.date_time <= max_age)
+        .map(|data_status| GetDataStatusEvent::Fresh {
+            data_status,
+            sms_sent: false,
+        })
+}
+
+/// Newest reading from the inbox, sending the request SMS and polling for the reply
+/// when it's older than `max_age` (or always with `force`). `stored` is the newest
+/// reading already in the DB: when the inbox has nothing parseable, e.g. after the
+/// router cleared it, a `stored` reading within `max_age` is used instead of an SMS.
 pub async fn get_data_status_fresh(
     force: bool,
     max_age: Duration,
     timeout: Duration,
     poll: Duration,
-) -> Result<GetDataStatusEvent> {
+    stored: Option<DataStatus>,
+) -> Result<GetDataStatusEvent, WindtreError> {
     let now = Utc::now();
-    let mut current = most_recent_data_status().await?;
-
-    let stale = current
-        .as_ref()
-        .map(|d| now - d.date_time > max_age)
-        .unwrap_or(true);
-
-    if force || stale {
-        if let Err(e) = request_data_status_sms().await {
+    let mut current = match most_recent_data_status().await {
+        Ok(c) => c,
+        Err(e) => {
             return Ok(GetDataStatusEvent::Error {
-                error: e,
-                data_status: current,
+                error: WindtreError::MikrotikUnreachable(e),
+                data_status: None,
                 is_stale: true,
-            });
+            })
+        }
+    };
+
+    if let Some(event) = fresh_from_stored(force, current.as_ref(), stored, now, max_age) {
+        return Ok(event);
+    }
+
+    if force || is_stale(current.as_ref(), now, max_age) {
+        match request_data_status_sms().await {
+            Ok(SmsRequestOutcome::Sent) => {}
+            Ok(SmsRequestOutcome::Throttled { retry_in_secs }) => {
+                return Ok(GetDataStatusEvent::Error {
+                    error: WindtreError::Throttled { retry_in_secs },
+                    data_status: current,
+                    is_stale: true,
+                });
+            }
+            Err(e) => {
+                return Ok(GetDataStatusEvent::Error {
+                    error: WindtreError::SmsSendFailed(e),
+                    data_status: current,
+                    is_stale: true,
+                });
+            }
         }
         let start = Utc::now();
         loop {
             tokio::time::sleep(poll.to_std().unwrap()).await;
-            current = most_recent_data_status().await?;
+            current = match most_recent_data_status().await {
+                Ok(c) => c,
+                Err(e) => {
+                    return Ok(GetDataStatusEvent::Error {
+                        error: WindtreError::MikrotikUnreachable(e),
+                        data_status: current,
+                        is_stale: true,
+                    })
+                }
+            };
             if let Some(ds) = &current {
                 if now - ds.date_time <= max_age {
                     return Ok(GetDataStatusEvent::Fresh {
                         data_status: ds.clone(),
+                        sms_sent: true,
                     });
                 }
             }
             if Utc::now() - start > timeout {
+                let error = match &current {
+                    Some(_) => WindtreError::Timeout,
+                    None => match get_smses().await {
+                        Ok(smss) => no_status_error(&smss),
+                        Err(_) => WindtreError::NoParseableSms,
+                    },
+                };
                 return Ok(GetDataStatusEvent::Error {
-                    error: anyhow::anyhow!("Timeout waiting for new SMS"),
+                    error,
                     data_status: current,
                     is_stale: true,
                 });
             }
         }
     } else if let Some(ds) = current {
-        Ok(GetDataStatusEvent::Fresh { data_status: ds })
+        Ok(GetDataStatusEvent::Fresh {
+            data_status: ds,
+            sms_sent: false,
+        })
     } else {
         Ok(GetDataStatusEvent::Loading {
             data_status: None,
@@ -150,3 +658,411 @@ pub async fn get_data_status_fresh(
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sms(id: &str, from: &str, timestamp: &str, message: &str) -> Sms {
+        Sms {
+            id: id.to_string(),
+            message: message.to_string(),
+            timestamp: Some(timestamp.to_string()),
+            time: None,
+            received: None,
+            from: Some(from.to_string()),
+        }
+    }
+
+    #[test]
+    fn router_time_months_and_tz() {
+        let months = [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+        for (i, month) in months.iter().enumerate() {
+            let want = format!("2024-{:02}-17T15:27:02+00:00", i + 1);
+            for name in [month.to_string(), month.to_ascii_lowercase()] {
+                let t = format!("{name}/17/2024 15:27:02");
+                let dt = parse_router_time(&t, Tz::UTC).unwrap();
+                assert_eq!(dt.to_rfc3339(), want, "{t}");
+            }
+        }
+        assert_eq!(
+            parse_router_time("2024-08-17 15:27:02", Tz::UTC)
+                .unwrap()
+                .to_rfc3339(),
+            "2024-08-17T15:27:02+00:00"
+        );
+        // Router clock in Rome: CEST (UTC+2) in August, CET (UTC+1) in January
+        let rome = chrono_tz::Europe::Rome;
+        assert_eq!(
+            parse_router_time("Aug/17/2024 15:27:02", rome)
+                .unwrap()
+                .to_rfc3339(),
+            "2024-08-17T13:27:02+00:00"
+        );
+        assert_eq!(
+            parse_router_time("jan/17/2024 00:30:00", rome)
+                .unwrap()
+                .to_rfc3339(),
+            "2024-01-16T23:30:00+00:00"
+        );
+        assert!(parse_router_time("foo/17/2024 15:27:02", Tz::UTC).is_none());
+    }
+
+    #[test]
+    fn parses_status_sms() {
+        let it = sms(
+            "*1",
+            "WINDTRE",
+            "2024-05-01T10:00:00Z",
+            "Dati: hai ancora a disposizione il 37% di 100 GIGA fino al 01/06",
+        );
+        let ds = parse_data_status_from_sms(&it).unwrap();
+        assert_eq!(
+            (ds.remaining_percentage, ds.remaining_data_mb),
+            (37, 37_000)
+        );
+        assert_eq!(ds.date_time.to_rfc3339(), "2024-05-01T10:00:00+00:00");
+        let router_time = Sms {
+            timestamp: None,
+            time: Some("Aug/17/2024 15:27:02".to_string()),
+            ..sms(
+                "*2",
+                "WINDTRE",
+                "",
+                "Dati: hai ancora a disposizione il 8% di 1,5 GIGA",
+            )
+        };
+        let ds = parse_data_status_from_sms(&router_time).unwrap();
+        assert_eq!((ds.remaining_percentage, ds.remaining_data_mb), (8, 120));
+        assert_eq!(
+            ds.date_time,
+            parse_router_time("aug/17/2024 15:27:02", *ROUTER_TZ).unwrap()
+        );
+        let unrelated = sms(
+            "*3",
+            "WINDTRE",
+            "2024-05-01T10:00:00Z",
+            "Ricarica effettuata",
+        );
+        assert!(parse_data_status_from_sms(&unrelated).is_none());
+        // A percentage over 100 is rejected, not clamped
+        let bogus = sms(
+            "*4",
+            "WINDTRE",
+            "2024-05-01T10:00:00Z",
+            "Dati: hai ancora a disposizione il 140% di 50 GIGA",
+        );
+        assert!(parse_data_status_from_sms(&bogus).is_none());
+    }
+
+    #[test]
+    fn percentage_bounds() {
+        let now = DateTime::parse_from_rfc3339("2024-05-01T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let parse = |pct: &str| {
+            let message = format!("Dati: hai ancora a disposizione il {pct}% di 50 GIGA");
+            parse_sms_message_with_total(&message, now, None)
+                .map(|ds| (ds.remaining_percentage, ds.remaining_data_mb))
+        };
+        assert_eq!(parse("0"), Some((0, 0)));
+        assert_eq!(parse("100"), Some((100, 50_000)));
+        assert_eq!(parse("105"), None);
+        assert_eq!(
+            status_from_fields("-3", Some("50"), now, None).map(|d| d.remaining_percentage),
+            None
+        );
+    }
+
+    #[test]
+    fn plan_total_override() {
+        let now = DateTime::parse_from_rfc3339("2024-05-01T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let message = "Dati: hai ancora a disposizione il 37% di 100 GIGA fino al 01/06";
+        let mb = |plan_total_mb| {
+            parse_sms_message_with_total(message, now, plan_total_mb)
+                .unwrap()
+                .remaining_data_mb
+        };
+        // Without the override the total comes from the SMS
+        assert_eq!(mb(None), 37_000);
+        // The real plan is 102400 MB, the SMS rounds it to "100 GIGA"
+        assert_eq!(mb(Some(102_400)), 37_888);
+    }
+
+    #[test]
+    fn italian_and_english_formats() {
+        let now = DateTime::parse_from_rfc3339("2024-05-01T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let parse = |message: &str| {
+            parse_sms_message_with_total(message, now, None)
+                .map(|ds| (ds.remaining_percentage, ds.remaining_data_mb))
+        };
+        let cases = [
+            (
+                "Dati: hai ancora a disposizione il 43% di 50 GIGA fino al 01/06",
+                (43, 21_500),
+            ),
+            (
+                "Dati: hai ancora a disposizione il 8% di 1,5 GIGA",
+                (8, 120),
+            ),
+            ("Dati: hai ancora a disposizione il 8% di 1.5GB", (8, 120)),
+            ("Data: you still have 43% of 50 GB left", (43, 21_500)),
+            ("Data: you have 8% of 1,5 GB", (8, 120)),
+            ("DATA: YOU STILL HAVE 43% OF 50 GIGA LEFT", (43, 21_500)),
+        ];
+        for (message, want) in cases {
+            assert_eq!(parse(message), Some(want), "{message}");
+        }
+        assert_eq!(parse("Data: 43% of 50 MB left"), None);
+    }
+
+    #[test]
+    fn rapid_requests_send_once() {
+        let min_interval = std::time::Duration::from_secs(60);
+        let start = Instant::now();
+        let mut last = None;
+        assert_eq!(reserve_sms_slot(&mut last, start, min_interval), Ok(None));
+        let soon = start + std::time::Duration::from_secs(1);
+        assert_eq!(reserve_sms_slot(&mut last, soon, min_interval), Err(59));
+        assert_eq!(last, Some(start));
+        let later = start + min_interval;
+        assert_eq!(
+            reserve_sms_slot(&mut last, later, min_interval),
+            Ok(Some(start))
+        );
+        assert_eq!(last, Some(later));
+    }
+    #[test]
+    fn empty_inbox_is_not_a_parse_failure() {
+        let now = DateTime::parse_from_rfc3339("2024-05-01T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let max_age = Duration::minutes(59);
+        // Cold start: nothing to show, so the request SMS goes out either way
+        assert!(is_stale(None, now, max_age));
+        assert!(matches!(no_status_error(&[]), WindtreError::EmptyInbox));
+        let unrelated = [sms(
+            "*1",
+            "WINDTRE",
+            "2024-05-01T09:00:00Z",
+            "Ricarica effettuata",
+        )];
+        assert!(parse_data_status_from_sms(&unrelated[0]).is_none());
+        assert!(matches!(
+            no_status_error(&unrelated),
+            WindtreError::NoParseableSms
+        ));
+    }
+
+    #[test]
+    fn no_request_sms_within_max_age() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let now = at("2024-05-01T12:00:00Z");
+        // Polling every 15 minutes but accepting readings up to two hours old
+        let max_age = Duration::minutes(120);
+        let reading = |date_time: &str| DataStatus {
+            remaining_percentage: 40,
+            remaining_data_mb: 20_000,
+            date_time: at(date_time),
+        };
+        assert!(!is_stale(
+            Some(&reading("2024-05-01T10:30:00Z")),
+            now,
+            max_age
+        ));
+        assert!(!is_stale(
+            Some(&reading("2024-05-01T10:00:00Z")),
+            now,
+            max_age
+        ));
+        assert!(is_stale(
+            Some(&reading("2024-05-01T09:59:00Z")),
+            now,
+            max_age
+        ));
+    }
+    /// Receive time of every fixture SMS.
+    const FIXTURE_TIME: &str = "2024-05-01T10:00:00Z";
+
+    /// An `Sms` from a fixture line's sender and message, received at `FIXTURE_TIME`.
+    fn fixture_sms(id: usize, from: &str, message: &str) -> Sms {
+        sms(&format!("*{id}"), from, FIXTURE_TIME, message)
+    }
+
+    /// The parsed `expected` column: `Some((percent, MB))`, or `None` for `none`.
+    fn fixture_expected(s: &str) -> Option<(i32, i32)> {
+        if s == "none" {
+            return None;
+        }
+        let (pct, mb) = s
+            .split_once("% ")
+            .expect("expected `<percent>% <MB>` or `none`");
+        Some((pct.parse().unwrap(), mb.parse().unwrap()))
+    }
+
+    /// Every case in `tests/fixtures/windtre/*.txt` (see the README there).
+    #[test]
+    fn parses_sms_fixtures() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/windtre");
+        let mut files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "txt"))
+            .collect();
+        files.sort();
+        assert!(!files.is_empty(), "no fixtures in {}", dir.display());
+        let mut cases = 0;
+        for file in files {
+            let text = std::fs::read_to_string(&file).unwrap();
+            for (i, line) in text.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let mut cols = line.splitn(3, " | ");
+                let (Some(expected), Some(from), Some(message)) =
+                    (cols.next(), cols.next(), cols.next())
+                else {
+                    panic!("{}:{}: malformed fixture line", file.display(), i + 1);
+                };
+                let it = fixture_sms(i + 1, from, message);
+                let parsed = from_sender(&it, Some("WINDTRE"))
+                    .then(|| parse_data_status_from_sms(&it))
+                    .flatten();
+                assert_eq!(
+                    parsed
+                        .as_ref()
+                        .map(|ds| (ds.remaining_percentage, ds.remaining_data_mb)),
+                    fixture_expected(expected),
+                    "{}:{}: {message}",
+                    file.display(),
+                    i + 1
+                );
+                if let Some(ds) = parsed {
+                    assert_eq!(ds.date_time.to_rfc3339(), "2024-05-01T10:00:00+00:00");
+                }
+                cases += 1;
+            }
+        }
+        assert!(cases >= 15, "only {cases} fixture cases");
+    }
+    #[test]
+    fn empty_inbox_serves_recent_stored_reading() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let now = at("2024-05-01T12:00:00Z");
+        let max_age = Duration::minutes(59);
+        let stored = DataStatus {
+            remaining_percentage: 40,
+            remaining_data_mb: 20_000,
+            date_time: at("2024-05-01T11:30:00Z"),
+        };
+        assert!(matches!(
+            fresh_from_stored(false, None, Some(stored.clone()), now, max_age),
+            Some(GetDataStatusEvent::Fresh {
+                sms_sent: false,
+                ..
+            })
+        ));
+        assert!(fresh_from_stored(true, None, Some(stored.clone()), now, max_age).is_none());
+        let old = DataStatus {
+            date_time: at("2024-05-01T10:00:00Z"),
+            ..stored
+        };
+        assert!(fresh_from_stored(false, None, Some(old), now, max_age).is_none());
+    }
+    #[test]
+    fn plan_total_separators() {
+        assert_eq!(parse_total_gb("50"), Some(50.0));
+        assert_eq!(parse_total_gb("1,5"), Some(1.5));
+        assert_eq!(parse_total_gb("1.5"), Some(1.5));
+        assert_eq!(parse_total_gb("1.024.000"), Some(1_024_000.0));
+        assert_eq!(parse_total_gb("1,024.5"), Some(1_024.5));
+        // One separator before three digits may be decimal or grouping
+        assert_eq!(parse_total_gb("1.024"), None);
+        assert_eq!(parse_total_gb("1,024"), None);
+        assert_eq!(parse_total_gb("1,5,"), None);
+    }
+
+    #[test]
+    fn status_from_another_sender_is_ignored() {
+        let text = "Dati: hai ancora a disposizione il 40% di 50 GIGA";
+        let inbox = [
+            sms(
+                "*2",
+                "+39333000111",
+                "2024-05-02T10:00:00Z",
+                &text.replace("40", "90"),
+            ),
+            sms("*1", "WINDTRE", "2024-05-01T10:00:00Z", text),
+        ];
+        let trusted: Vec<&Sms> = inbox
+            .iter()
+            .filter(|s| from_sender(s, Some("windtre")))
+            .collect();
+        assert_eq!(trusted.len(), 1);
+        let dated: Vec<(&str, DateTime<Utc>)> = trusted
+            .iter()
+            .map(|s| (s.message.as_str(), sms_date(s).unwrap()))
+            .collect();
+        let ds = find_data_status(&dated, MatchStrategy::Single).unwrap();
+        assert_eq!(ds.remaining_percentage, 40);
+        assert_eq!(ds.date_time.to_rfc3339(), "2024-05-01T10:00:00+00:00");
+        // Without `WINDTRE_SENDER` both are accepted
+        assert!(inbox.iter().all(|s| from_sender(s, None)));
+        // A sender-less SMS can't be trusted once the filter is set
+        let mut anonymous = inbox[1].clone();
+        anonymous.from = None;
+        assert!(!from_sender(&anonymous, Some("WINDTRE")));
+    }
+
+    #[test]
+    fn two_part_status_is_combined() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        // Newest first: the total arrives a minute after the percentage
+        let dated = [
+            ("Il tuo piano: totale 50 GIGA", at("2024-05-01T10:01:00Z")),
+            (
+                "Dati: hai ancora a disposizione il 40% del traffico",
+                at("2024-05-01T10:00:00Z"),
+            ),
+        ];
+        assert!(find_data_status(&dated, MatchStrategy::Single).is_none());
+        let window = Duration::seconds(120);
+        let ds = find_data_status(&dated, MatchStrategy::Combined { window }).unwrap();
+        assert_eq!(ds.remaining_percentage, 40);
+        assert_eq!(ds.remaining_data_mb, 20_000);
+        assert_eq!(ds.date_time, at("2024-05-01T10:01:00Z"));
+        // Parts further apart than the window don't belong together
+        let narrow = Duration::seconds(30);
+        assert!(find_data_status(&dated, MatchStrategy::Combined { window: narrow }).is_none());
+    }
+
+    #[test]
+    fn unrelated_sms_is_not_combined_with_a_full_status() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let window = Duration::seconds(120);
+        // Newest first: an offer a minute after a complete status SMS
+        let dated = [
+            ("Nuova offerta: 5 euro al mese", at("2024-05-01T10:01:00Z")),
+            (
+                "Dati: hai ancora a disposizione il 40% di 50 GIGA",
+                at("2024-05-01T10:00:00Z"),
+            ),
+        ];
+        let ds = find_data_status(&dated, MatchStrategy::Combined { window }).unwrap();
+        assert_eq!(ds.remaining_percentage, 40);
+        assert_eq!(ds.date_time, at("2024-05-01T10:00:00Z"));
+        // A complete status isn't taken apart to fill in a newer part
+        let dated = [
+            ("Il tuo piano: totale 80 GIGA", at("2024-05-01T10:01:00Z")),
+            dated[1],
+        ];
+        assert!(combine_parts(&dated, window).is_none());
+    }
+}
@@ -0,0 +1,86 @@
+use dioxus::prelude::*;
+
+use crate::api::add_reading;
+use crate::components::{use_admin_token, use_toasts};
+use crate::utils::format::MB_PER_GB_DECIMAL;
+
+const INPUT_CLASS: &str = "rounded-lg border border-slate-300 dark:border-slate-700 bg-transparent px-2 py-1 text-xs text-slate-700 dark:text-slate-200 tabular-nums";
+
+/// Admin form to store a reading by hand, e.g. read off the carrier app when the
+/// status SMS was missed. Amounts in GB are converted like the carrier's (1000 MB).
+#[allow(non_snake_case)]
+#[component]
+pub fn AddReadingForm() -> Element {
+    let admin_token = use_admin_token();
+    let toasts = use_toasts();
+    let mut percentage = use_signal(String::new);
+    let mut amount = use_signal(String::new);
+    let mut in_gb = use_signal(|| true);
+    // `datetime-local` value in the browser's timezone; empty means now
+    let mut when = use_signal(String::new);
+    let mut saving = use_signal(|| false);
+
+    rsx! {
+        form {
+            class: "rounded-2xl border border-slate-200 dark:border-slate-800 bg-white/60 dark:bg-slate-900/60 backdrop-blur-sm shadow-xl p-6 space-y-3",
+            onsubmit: move |evt| {
+                evt.prevent_default();
+                let pct = percentage().trim().parse::<i32>();
+                let amount_value = amount().trim().replace(',', ".").parse::<f64>();
+                let (Ok(pct), Ok(amount_value)) = (pct, amount_value) else {
+                    toasts.push("Enter a percentage and an amount".to_string());
+                    return;
+                };
+                let mb = if in_gb() { amount_value * MB_PER_GB_DECIMAL } else { amount_value }.round() as i32;
+                let local = when();
+                saving.set(true);
+                spawn(async move {
+                    // The browser knows its timezone; send the timestamp as UTC
+                    let value = serde_json::to_string(&local).unwrap_or_else(|_| "\"\"".into());
+                    let js = format!("const v = {value}; return (v ? new Date(v) : new Date()).toISOString();");
+                    let date_time = match document::eval(&js).await {
+                        Ok(v) => v.as_str().unwrap_or_default().to_string(),
+                        Err(e) => {
+                            toasts.push(format!("Add reading: {e}"));
+                            saving.set(false);
+                            return;
+                        }
+                    };
+                    match add_reading(pct, mb, date_time, admin_token()).await {
+                        Ok(()) => {
+                            toasts.push("Reading added".to_string());
+                            percentage.set(String::new());
+                            amount.set(String::new());
+                            when.set(String::new());
+                        }
+                        Err(e) => toasts.push(format!("Add reading failed: {e}")),
+                    }
+                    saving.set(false);
+                });
+            },
+            h2 { class: "text-lg font-medium text-slate-700 dark:text-slate-200", "Add a reading" }
+            div { class: "flex flex-wrap items-center gap-2",
+                input { class: "{INPUT_CLASS} w-20", r#type: "number", min: "0", max: "100", placeholder: "%", aria_label: "Remaining percentage", value: "{percentage}",
+                    oninput: move |e| percentage.set(e.value()),
+                }
+                input { class: "{INPUT_CLASS} w-24", r#type: "number", min: "0", step: "any", placeholder: "Remaining", aria_label: "Remaining data", value: "{amount}",
+                    oninput: move |e| amount.set(e.value()),
+                }
+                select { class: INPUT_CLASS, aria_label: "Unit",
+                    onchange: move |e| in_gb.set(e.value() == "gb"),
+                    option { value: "gb", selected: in_gb(), "GB" }
+                    option { value: "mb", selected: !in_gb(), "MB" }
+                }
+                input { class: INPUT_CLASS, r#type: "datetime-local", aria_label: "Time of the reading (empty: now)", value: "{when}",
+                    oninput: move |e| when.set(e.value()),
+                }
+                button {
+                    class: "rounded-lg border border-slate-300 dark:border-slate-700 px-3 py-1 text-xs text-slate-600 dark:text-slate-300 hover:bg-slate-200 dark:hover:bg-slate-800 disabled:opacity-50",
+                    r#type: "submit",
+                    disabled: saving(),
+                    "Add"
+                }
+            }
+        }
+    }
+}
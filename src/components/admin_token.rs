@@ -0,0 +1,79 @@
+use dioxus::prelude::*;
+
+/// `localStorage` key holding the admin token.
+const STORAGE_KEY: &str = "trullo-admin-token";
+
+/// Provide the stored admin token to all descendants (called once in `App`).
+/// Empty until it's loaded from `localStorage` in the browser.
+pub fn use_admin_token_provider() {
+    let mut token = use_context_provider(|| Signal::new(String::new()));
+    use_effect(move || {
+        spawn(async move {
+            let js = format!(r#"return localStorage.getItem("{STORAGE_KEY}") || "";"#);
+            if let Ok(v) = document::eval(&js).await {
+                if let Some(t) = v.as_str() {
+                    token.set(t.to_string());
+                }
+            }
+        });
+    });
+}
+
+/// Admin token from the surrounding `App`, passed to mutating server fns.
+pub fn use_admin_token() -> Signal<String> {
+    use_context::<Signal<String>>()
+}
+
+fn store(token: &str) {
+    // JSON string literals are valid JS string literals
+    let value = serde_json::to_string(token).unwrap_or_else(|_| "\"\"".into());
+    let _ = document::eval(&format!(
+        r#"localStorage.setItem("{STORAGE_KEY}", {value});"#
+    ));
+}
+
+/// Field to enter the `ADMIN_TOKEN` once; it's kept in this browser only.
+#[allow(non_snake_case)]
+#[component]
+pub fn AdminLogin() -> Element {
+    let mut token = use_admin_token();
+    let mut draft = use_signal(String::new);
+    let button_class = "rounded-lg border border-slate-300 dark:border-slate-700 px-3 py-1 text-xs text-slate-600 dark:text-slate-300 hover:bg-slate-200 dark:hover:bg-slate-800 disabled:opacity-50";
+
+    rsx! {
+        if token().is_empty() {
+            form {
+                class: "flex items-center gap-2",
+                onsubmit: move |evt| {
+                    evt.prevent_default();
+                    let t = draft().trim().to_string();
+                    store(&t);
+                    token.set(t);
+                    draft.set(String::new());
+                },
+                input {
+                    class: "w-36 rounded-lg border border-slate-300 dark:border-slate-700 bg-transparent px-2 py-1 text-xs text-slate-700 dark:text-slate-200 placeholder:text-slate-400",
+                    r#type: "password",
+                    placeholder: "Admin token",
+                    aria_label: "Admin token",
+                    autocomplete: "current-password",
+                    value: "{draft}",
+                    oninput: move |evt| draft.set(evt.value()),
+                }
+                button { class: button_class, r#type: "submit", disabled: draft().trim().is_empty(), "Save" }
+            }
+        } else {
+            div { class: "flex items-center gap-2 text-xs text-slate-500 dark:text-slate-400",
+                span { "Admin" }
+                button {
+                    class: button_class,
+                    onclick: move |_| {
+                        store("");
+                        token.set(String::new());
+                    },
+                    "Forget token"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use dioxus::prelude::*;
+
+use crate::api::get_remaining_series;
+use crate::components::{use_locale, use_theme, use_toasts};
+use crate::shared::types::RemainingPointDto;
+use crate::utils::format::{format_megabytes, format_ymd};
+
+/// Width of the plot in SVG units; the chart scales to its container.
+const PLOT_WIDTH: f32 = 720.0;
+
+/// Remaining percentage over the last `days` days against the ideal linear burn-down
+/// of the current cycle (100% at its start, 0% at its scheduled end).
+#[allow(non_snake_case)]
+#[component]
+pub fn BurnDownChart(
+    #[props(default = 30)] days: i64,
+    #[props(default = 160.0)] height: f32,
+    #[props(default = 20.0)] padding: f32,
+) -> Element {
+    let data = use_resource(move || async move { get_remaining_series(days).await });
+    let toasts = use_toasts();
+    use_effect(move || {
+        if let Some(Err(e)) = &*data.read() {
+            toasts.push(format!("Burn-down: {e}"));
+        }
+    });
+    let series = match &*data.read_unchecked() {
+        Some(Ok(series)) => series.clone(),
+        _ => Default::default(),
+    };
+    let points = series.points;
+    let locale = use_locale();
+    let theme = use_theme();
+
+    // Time axis covers the readings and the whole current cycle
+    let lo = points
+        .iter()
+        .map(|p| p.timestamp)
+        .chain([series.cycle_start])
+        .min()
+        .unwrap_or(0);
+    let hi = points
+        .iter()
+        .map(|p| p.timestamp)
+        .chain([series.cycle_end])
+        .max()
+        .unwrap_or(0);
+    let span = (hi - lo).max(1) as f32;
+    let x_of = move |ts: i64| padding + (ts - lo) as f32 / span * PLOT_WIDTH;
+    let y_of = move |pct: i32| padding + (100 - pct.clamp(0, 100)) as f32 / 100.0 * height;
+    let width = PLOT_WIDTH + padding * 2.0;
+    let view_box = format!("0 0 {} {}", width, height + padding * 2.0);
+    let path = burn_down_path(&points, x_of, y_of);
+    let show_ideal = series.cycle_end > series.cycle_start;
+    // Warning band from 0% up to the low-data threshold, kept inside the plot
+    let low = series.low_threshold.map(|(mb, pct)| {
+        let y = padding + (1.0 - pct.clamp(0.0, 100.0) / 100.0) * height;
+        // Label below the line near the top, above it otherwise
+        let label_y = if y < padding + 12.0 {
+            y + 11.0
+        } else {
+            y - 3.0
+        };
+        (y, label_y, format!("Low: {}", format_megabytes(mb)))
+    });
+    let projection = series
+        .projection
+        .as_ref()
+        .map(|p| match (p.will_run_out, p.days_early) {
+            (false, _) => format!(
+                "On track to finish with ~{}",
+                format_megabytes(p.projected_remaining_at_cycle_end_mb)
+            ),
+            (true, 0) => "Projected to run out just before the reset".to_string(),
+            (true, 1) => "Projected to run out ~1 day early".to_string(),
+            (true, n) => format!("Projected to run out ~{n} days early"),
+        });
+    let date_of =
+        move |p: &RemainingPointDto| format_ymd(&locale(), p.date_time.get(..10).unwrap_or(""));
+
+    rsx! {
+        div { class: "rounded-2xl border border-slate-200 dark:border-slate-800 bg-white/60 dark:bg-slate-900/60 backdrop-blur-sm shadow-xl p-6 space-y-3",
+            div { class: "flex items-end justify-between",
+                h2 { class: "text-lg font-medium text-slate-700 dark:text-slate-200", "Burn-down (last {days} days)" }
+                if show_ideal { div { class: "text-xs text-slate-500 dark:text-slate-400", "Dashed: ideal pace this cycle" } }
+            }
+            div { class: "w-full overflow-x-auto",
+                svg { class: "block min-w-full", view_box: "{view_box}", width: "100%", height: "{(height + padding*2.0).to_string()}",
+                    for pct in [0, 50, 100] {
+                        g { key: "{pct}",
+                            line { x1: "{padding}", y1: "{y_of(pct)}", x2: "{width - padding}", y2: "{y_of(pct)}", stroke: "{theme().chart_axis()}", stroke_width: "1" }
+                            text { x: "{padding}", y: "{y_of(pct) - 3.0}", class: "text-slate-500 dark:text-slate-400 fill-current text-[10px]", "{pct}%" }
+                        }
+                    }
+                    if let Some((y, label_y, label)) = &low {
+                        g { class: "text-amber-500 dark:text-amber-400",
+                            rect { x: "{padding}", y: "{y}", width: "{PLOT_WIDTH}", height: "{(padding + height - y).max(0.0)}", fill: "currentColor", fill_opacity: "0.12" }
+                            line { x1: "{padding}", y1: "{y}", x2: "{width - padding}", y2: "{y}", stroke: "currentColor", stroke_width: "1", stroke_dasharray: "2 3" }
+                            text { x: "{width - padding}", y: "{label_y}", text_anchor: "end", class: "fill-current text-[10px]", "{label}" }
+                        }
+                    }
+                    if show_ideal {
+                        line {
+                            class: "text-slate-400 dark:text-slate-500",
+                            x1: "{x_of(series.cycle_start)}", y1: "{y_of(100)}",
+                            x2: "{x_of(series.cycle_end)}", y2: "{y_of(0)}",
+                            stroke: "currentColor", stroke_width: "1.5", stroke_dasharray: "4 4",
+                        }
+                    }
+                    if !path.is_empty() {
+                        path { d: "{path}", fill: "none", stroke: "{theme().chart_accent()}", stroke_width: "2", stroke_linejoin: "round", stroke_linecap: "round" }
+                    }
+                    for (i, p) in points.iter().enumerate() {
+                        circle { key: "{i}", cx: "{x_of(p.timestamp)}", cy: "{y_of(p.remaining_percentage)}", r: "2.5", fill: "{theme().chart_accent()}",
+                            title { "{date_of(p)}: {p.remaining_percentage}%" }
+                        }
+                    }
+                    if let (Some(first), Some(last)) = (points.first(), points.last()) {
+                        text { x: "{x_of(first.timestamp)}", y: "{height + padding + 14.0}", class: "text-slate-500 dark:text-slate-400 fill-current text-[10px]", "{date_of(first)}" }
+                        if points.len() > 1 {
+                            text { x: "{x_of(last.timestamp)}", y: "{height + padding + 14.0}", text_anchor: "end", class: "text-slate-500 dark:text-slate-400 fill-current text-[10px]", "{date_of(last)}" }
+                        }
+                    }
+                }
+            }
+            if let Some(projection) = &projection {
+                div { class: "text-xs text-slate-600 dark:text-slate-300", "{projection}" }
+            }
+            // Same series as a table for screen readers
+            table { class: "sr-only",
+                caption { "Remaining data, last reading per day" }
+                thead { tr { th { scope: "col", "Date" } th { scope: "col", "Remaining" } } }
+                tbody {
+                    for p in points.iter() {
+                        tr { key: "{p.timestamp}",
+                            td { "{date_of(p)}" }
+                            td { "{p.remaining_percentage}%" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// SVG path through the points. A rise (the plan reset) isn't drawn as a slope from
+/// the previous day: the line jumps straight up at the first reading after the reset.
+fn burn_down_path(
+    points: &[RemainingPointDto],
+    x_of: impl Fn(i64) -> f32,
+    y_of: impl Fn(i32) -> f32,
+) -> String {
+    let mut d = String::new();
+    let mut prev: Option<i32> = None;
+    for p in points {
+        let (x, y) = (x_of(p.timestamp), y_of(p.remaining_percentage));
+        match prev {
+            None => d.push_str(&format!("M{x:.1},{y:.1}")),
+            Some(pct) if p.remaining_percentage > pct => {
+                d.push_str(&format!(" M{x:.1},{:.1} L{x:.1},{y:.1}", y_of(pct)))
+            }
+            Some(_) => d.push_str(&format!(" L{x:.1},{y:.1}")),
+        }
+        prev = Some(p.remaining_percentage);
+    }
+    d
+}
@@ -0,0 +1,104 @@
+use dioxus::prelude::*;
+
+use crate::api::get_cycle_comparison;
+use crate::components::{use_theme, use_toasts};
+use crate::utils::format::{
+    format_megabytes, format_megabytes_rounded, GbRounding, MB_PER_GB_DECIMAL,
+};
+
+/// Width of the plot in SVG units; the chart scales to its container.
+const PLOT_WIDTH: f32 = 720.0;
+
+/// Cumulative usage of this cycle (solid) against the previous one (faint) on the
+/// same day of cycle.
+#[allow(non_snake_case)]
+#[component]
+pub fn CycleComparison(
+    #[props(default = 160.0)] height: f32,
+    #[props(default = 20.0)] padding: f32,
+) -> Element {
+    let data = use_resource(|| async move { get_cycle_comparison().await });
+    let toasts = use_toasts();
+    use_effect(move || {
+        if let Some(Err(e)) = &*data.read() {
+            toasts.push(format!("Cycle comparison: {e}"));
+        }
+    });
+    let series = match &*data.read_unchecked() {
+        Some(Ok(series)) => series.clone(),
+        _ => Default::default(),
+    };
+    let theme = use_theme();
+
+    let days = series.current.len().max(series.previous.len()).max(2);
+    let max_mb = series
+        .current
+        .iter()
+        .chain(series.previous.iter())
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(1) as f32;
+    let x_of = move |day: usize| padding + day as f32 / (days - 1) as f32 * PLOT_WIDTH;
+    let y_of = move |mb: i32| padding + (1.0 - mb as f32 / max_mb) * height;
+    let line = move |values: &[i32]| -> String {
+        values
+            .iter()
+            .enumerate()
+            .map(|(day, mb)| format!("{:.1},{:.1}", x_of(day), y_of(*mb)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let current_line = line(&series.current);
+    let previous_line = line(&series.previous);
+    // Whole GB on the axis once the figures are large
+    let axis_max = format_megabytes_rounded(
+        max_mb as f64,
+        MB_PER_GB_DECIMAL,
+        GbRounding::WholeFrom(10.0),
+    );
+    let width = PLOT_WIDTH + padding * 2.0;
+    let view_box = format!("0 0 {} {}", width, height + padding * 2.0);
+    let summary = series.current.last().map(|&used| {
+        let day = series.current.len() - 1;
+        match series.previous.get(day) {
+            Some(&prev) => format!(
+                "Day {}: {} used, {} at this point last cycle",
+                day + 1,
+                format_megabytes(used),
+                format_megabytes(prev)
+            ),
+            None => format!("Day {}: {} used", day + 1, format_megabytes(used)),
+        }
+    });
+
+    rsx! {
+        div { class: "rounded-2xl border border-slate-200 dark:border-slate-800 bg-white/60 dark:bg-slate-900/60 backdrop-blur-sm shadow-xl p-6 space-y-3",
+            div { class: "flex items-end justify-between",
+                h2 { class: "text-lg font-medium text-slate-700 dark:text-slate-200", "This cycle vs. last cycle" }
+                div { class: "flex gap-3 text-xs text-slate-500 dark:text-slate-400",
+                    span { class: "text-emerald-600 dark:text-emerald-400", "━ this cycle" }
+                    if !series.previous.is_empty() { span { "━ last cycle" } }
+                }
+            }
+            div { class: "w-full overflow-x-auto",
+                svg { class: "block min-w-full", view_box: "{view_box}", width: "100%", height: "{(height + padding*2.0).to_string()}",
+                    "aria-hidden": "true",
+                    line { x1: "{padding}", y1: "{padding + height}", x2: "{width - padding}", y2: "{padding + height}", stroke: "{theme().chart_axis()}", stroke_width: "1" }
+                    text { x: "{padding}", y: "{padding - 6.0}", class: "text-slate-500 dark:text-slate-400 fill-current text-[10px]", "{axis_max}" }
+                    text { x: "{padding}", y: "{height + padding + 14.0}", class: "text-slate-500 dark:text-slate-400 fill-current text-[10px]", "Day 1" }
+                    text { x: "{width - padding}", y: "{height + padding + 14.0}", text_anchor: "end", class: "text-slate-500 dark:text-slate-400 fill-current text-[10px]", "Day {days}" }
+                    if series.previous.len() > 1 {
+                        polyline { class: "text-slate-400/60 dark:text-slate-500/60", points: "{previous_line}", fill: "none", stroke: "currentColor", stroke_width: "1.5", stroke_linejoin: "round" }
+                    }
+                    if series.current.len() > 1 {
+                        polyline { points: "{current_line}", fill: "none", stroke: "{theme().chart_accent()}", stroke_width: "2", stroke_linejoin: "round", stroke_linecap: "round" }
+                    }
+                }
+            }
+            if let Some(summary) = summary {
+                div { class: "text-xs text-slate-600 dark:text-slate-300", "{summary}" }
+            }
+        }
+    }
+}
@@ -3,37 +3,246 @@ use dioxus::prelude::*;
 #[cfg(feature = "web")]
 use dioxus::logger::tracing::info;
 
-use crate::api::{get_scheduler_status, latest_data_status};
-use crate::components::Gauge;
-use crate::utils::format::{format_local, format_megabytes};
+use crate::api::{
+    get_cycle_usage, get_gauge_mode, get_gauge_style, get_remaining_trend, get_scheduler_status,
+    latest_data_status, request_status_sms, DB_UNAVAILABLE, DEFAULT_APP_TITLE,
+};
+use crate::components::{
+    gauge_geometry, threshold_class, use_admin_token, use_locale, use_toasts, Gauge, Sparkline,
+};
+use crate::shared::types::{DataStatusDto, GaugeMode};
+use crate::utils::format::{
+    age_minutes, format_age_minutes, format_local, format_megabytes, format_relative,
+};
+#[cfg(feature = "web")]
+use crate::FAVICON;
 
+/// Latest reading, gauge and scheduler diagnostics. With `favicon_badge` the tab icon
+/// (and the installed app's badge, where supported) shows the remaining percentage; with
+/// `used_arc` a thin inner arc on the gauge shows the share of the plan used this cycle.
 #[allow(non_snake_case)]
 #[component]
-pub fn DataStatusCard() -> Element {
+pub fn DataStatusCard(
+    #[props(default = true)] favicon_badge: bool,
+    #[props(default = DEFAULT_APP_TITLE.to_string())] title: String,
+    #[props(default = true)] used_arc: bool,
+) -> Element {
     // ssr data (server waits)
     let latest = use_server_future(latest_data_status)?;
     let latest_v = latest.read_unchecked();
+    let toasts = use_toasts();
+    let locale = use_locale();
+    use_effect(move || {
+        if let Some(Err(e)) = &*latest.read() {
+            toasts.push(format!("Data status: {e}"));
+        }
+    });
+
+    // Last 14 days of remaining data for the sparkline; refetched with each new reading
+    let trend = use_resource(move || {
+        let _ = latest.read();
+        async move { get_remaining_trend(14).await.ok().unwrap_or_default() }
+    });
+    // Used so far this cycle, against the plan size when known
+    let cycle_usage = use_resource(move || {
+        let _ = latest.read();
+        async move { get_cycle_usage().await.ok() }
+    });
+    let cycle_usage = cycle_usage.read_unchecked().clone().flatten();
+    let used_line = cycle_usage.as_ref().map(|u| match u.plan_total_mb {
+        Some(total) => format!(
+            "{} of {} used this cycle",
+            format_megabytes(u.used_mb),
+            format_megabytes(total)
+        ),
+        None => format!("{} used this cycle", format_megabytes(u.used_mb)),
+    });
+    let used_pct = cycle_usage
+        .as_ref()
+        .filter(|_| used_arc)
+        .and_then(|u| match u.plan_total_mb {
+            Some(total) if total > 0 => {
+                Some((u.used_mb as f64 / total as f64 * 100.0).round() as i32)
+            }
+            _ => None,
+        });
+    let trend_points: Vec<(f64, f64)> = trend
+        .read_unchecked()
+        .iter()
+        .flatten()
+        .map(|p| (p.timestamp as f64, p.remaining_data_mb as f64))
+        .collect();
+
+    // Gauge center (`DISPLAY_MODE`); percent until the server's setting arrives
+    let gauge_mode = use_resource(|| async move { get_gauge_mode().await.unwrap_or_default() });
+    let gauge_mode = gauge_mode.read_unchecked().unwrap_or_default();
+    // Gauge shape (`GAUGE_STYLE`); three-quarter until the server's setting arrives
+    let gauge_style = use_resource(|| async move { get_gauge_style().await.unwrap_or_default() });
+    let geometry = gauge_geometry(gauge_style.read_unchecked().unwrap_or_default());
 
     // Client-only fetch
     let status = use_resource(|| async move { get_scheduler_status().await.ok() });
     let status_v = status.read_unchecked();
 
+    // Manual "request SMS": send it, then poll until the scheduler (or the SMS webhook)
+    // has stored a newer reading
+    let mut sms_pending = use_signal(|| false);
+    let mut sms_notice = use_signal(|| Option::<String>::None);
+    let admin_token = use_admin_token();
+    let on_request_sms = move |_| {
+        if *sms_pending.read() {
+            return;
+        }
+        sms_pending.set(true);
+        spawn(async move {
+            let notice = match request_status_sms(admin_token()).await {
+                Ok(r) if r.sent => {
+                    sms_notice.set(Some("SMS sent, waiting for reply...".into()));
+                    if wait_for_newer_reading(latest, REPLY_WAIT_SECS).await {
+                        "Reading updated.".to_string()
+                    } else {
+                        "No reply yet; the scheduler will pick it up.".to_string()
+                    }
+                }
+                Ok(r) => r.message,
+                Err(e) => format!("Failed to send SMS: {e}"),
+            };
+            sms_notice.set(Some(notice));
+            sms_pending.set(false);
+            // Clear the confirmation after a few seconds
+            #[cfg(feature = "web")]
+            gloo_timers::callback::Timeout::new(8_000, move || sms_notice.set(None)).forget();
+        });
+    };
+
+    // Remaining percentage on the favicon and via the Badging API; browsers without
+    // canvas or `setAppBadge` keep the plain icon
+    #[cfg(feature = "web")]
+    use_effect(move || {
+        let pct = match &*latest.read() {
+            Some(Ok(Some(ds))) if favicon_badge => ds.remaining_percentage,
+            _ => return,
+        };
+        // Same thresholds as the percentage text (red-600 / amber-600 / emerald-600)
+        let color = match pct {
+            p if p < 20 => "#dc2626",
+            p if p < 50 => "#d97706",
+            _ => "#059669",
+        };
+        let _ = document::eval(&format!(
+            r##"
+            try {{ navigator.setAppBadge && navigator.setAppBadge({pct}).catch(() => {{}}); }} catch (e) {{}}
+            const draw = (img) => {{
+                try {{
+                    const c = document.createElement("canvas");
+                    c.width = c.height = 64;
+                    const g = c.getContext("2d");
+                    if (img) g.drawImage(img, 0, 0, 64, 64);
+                    g.fillStyle = "{color}";
+                    g.beginPath(); g.arc(40, 40, 24, 0, 2 * Math.PI); g.fill();
+                    g.fillStyle = "#ffffff";
+                    g.font = "bold 26px sans-serif";
+                    g.textAlign = "center"; g.textBaseline = "middle";
+                    g.fillText("{pct}", 40, 42);
+                    const link = document.querySelector('link[rel="icon"]');
+                    if (link) link.href = c.toDataURL("image/png");
+                }} catch (e) {{}}
+            }};
+            const img = new Image();
+            img.onload = () => draw(img);
+            img.onerror = () => draw(null);
+            img.src = "{FAVICON}";
+            "##
+        ));
+    });
+    #[cfg(not(feature = "web"))]
+    let _ = favicon_badge;
+
     // Force one rerender after hydration so client formatting can apply
     let hydrated = use_signal(|| false);
     #[cfg(feature = "web")]
     {
         use_effect({
-            let mut hydrated = hydrated.clone();
+            let mut hydrated = hydrated;
             move || {
                 hydrated.set(true); // runs once on the client after hydration
             }
         });
     }
 
+    // Browser clock for the relative "as of" time; each tick also refreshes the age badge
+    let now_ms = use_signal(|| 0.0f64);
+    #[cfg(feature = "web")]
+    use_future(move || {
+        let mut now_ms = now_ms;
+        async move {
+            loop {
+                now_ms.set(js_sys::Date::now());
+                gloo_timers::future::sleep(std::time::Duration::from_secs(15)).await;
+            }
+        }
+    });
+
+    // Live updates over SSE; while connected the timer below stays idle
+    #[cfg(feature = "web")]
+    let sse_connected = use_signal(|| false);
+    #[cfg(feature = "web")]
+    {
+        use wasm_bindgen::{closure::Closure, JsCast};
+        use web_sys::EventSource;
+
+        // EventSource plus its callbacks, kept alive until unmount
+        type Subscription = (EventSource, Vec<Closure<dyn FnMut()>>);
+        let sse: Signal<Option<Subscription>> = use_signal(|| None);
+
+        use_drop({
+            let mut sse = sse;
+            move || {
+                if let Some((es, _callbacks)) = sse.write().take() {
+                    es.close();
+                }
+            }
+        });
+
+        use_effect({
+            let mut sse = sse;
+            let mut sse_connected = sse_connected;
+            let mut status = status;
+            let mut latest = latest;
+            move || {
+                // Under the bundle's base path when served behind a prefix
+                let base = dioxus::cli_config::web_base_path().unwrap_or_default();
+                let url = match base.trim_matches('/') {
+                    "" => "/events".to_string(),
+                    base => format!("/{base}/events"),
+                };
+                let es = match EventSource::new(&url) {
+                    Ok(es) => es,
+                    Err(_) => {
+                        info!("[data_status_card] SSE unavailable; using timer");
+                        return;
+                    }
+                };
+                let on_open = Closure::<dyn FnMut()>::new(move || sse_connected.set(true));
+                // EventSource reconnects on its own; fall back to the timer meanwhile
+                let on_error = Closure::<dyn FnMut()>::new(move || sse_connected.set(false));
+                let on_status = Closure::<dyn FnMut()>::new(move || {
+                    status.restart();
+                    latest.restart();
+                });
+                es.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+                es.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+                let _ = es
+                    .add_event_listener_with_callback("status", on_status.as_ref().unchecked_ref());
+                sse.set(Some((es, vec![on_open, on_error, on_status])));
+            }
+        });
+    }
+
     // ---------- NEW: client-side timer effect ----------
     #[cfg(feature = "web")]
     {
-        use gloo_timers::callback::{Interval, Timeout};
+        use gloo_timers::callback::Timeout;
         use js_sys::Date;
         use wasm_bindgen::JsValue;
 
@@ -42,7 +251,7 @@ pub fn DataStatusCard() -> Element {
 
         // teardown on unmount
         use_drop({
-            let mut timer_handle = timer_handle.clone();
+            let mut timer_handle = timer_handle;
             move || {
                 if let Some(h) = timer_handle.write().take() {
                     h.cancel();
@@ -52,8 +261,8 @@ pub fn DataStatusCard() -> Element {
 
         // refresh at next_iteration_at or poll every second
         use_effect({
-            let mut status = status.clone();
-            let mut timer_handle = timer_handle.clone();
+            let mut status = status;
+            let mut timer_handle = timer_handle;
 
             move || {
                 // Read resource *inside* the effect so it re-runs after restart()
@@ -68,6 +277,11 @@ pub fn DataStatusCard() -> Element {
                     prev.cancel();
                 }
 
+                // Pushed updates make polling unnecessary
+                if sse_connected() {
+                    return;
+                }
+
                 // Decide schedule based on next_iso:
                 // - Some future ts  -> one-shot to exact instant
                 // - Some past ts    -> 1s polling
@@ -102,67 +316,163 @@ pub fn DataStatusCard() -> Element {
 
     rsx! {
         // Card
-        div { class: "w-full rounded-2xl border border-slate-800 bg-slate-900/60 backdrop-blur-sm shadow-xl p-8 space-y-6",
-            h1 { class: "text-2xl font-semibold tracking-tight text-slate-200", "WindTre Data Status" }
+        div { class: "w-full rounded-2xl border border-slate-200 dark:border-slate-800 bg-white/60 dark:bg-slate-900/60 backdrop-blur-sm shadow-xl p-8 space-y-6",
+            div { class: "flex items-center justify-between gap-3",
+                h1 { class: "text-2xl font-semibold tracking-tight text-slate-700 dark:text-slate-200", "{title}" }
+                button {
+                    class: "shrink-0 rounded-lg border border-slate-300 dark:border-slate-700 px-3 py-1 text-xs text-slate-600 dark:text-slate-300 hover:bg-slate-200 dark:hover:bg-slate-800 disabled:opacity-50",
+                    disabled: *sms_pending.read(),
+                    onclick: on_request_sms,
+                    if *sms_pending.read() { "Sending..." } else { "Request SMS" }
+                }
+            }
+            if let Some(notice) = &*sms_notice.read() {
+                div { class: "text-xs text-slate-500 dark:text-slate-400", "{notice}" }
+            }
 
             {
                 match &*latest_v {
                     // Data available
                     Some(Ok(Some(ds))) => {
+                        // The server's rendering until hydrated, so both sides match
                         let shown_time = if *hydrated.read() {
-                            format_local(&ds.date_time)
+                            format_local(&ds.date_time, &locale())
+                        } else {
+                            ds.display_time.clone().unwrap_or_else(|| ds.date_time.clone())
+                        };
+                        let relative_time = (*hydrated.read() && now_ms() > 0.0)
+                            .then(|| format_relative(&ds.date_time, now_ms()));
+                        let thresholds = vec![
+                            (20, "text-red-600 dark:text-red-500".to_string()),
+                            (50, "text-amber-600 dark:text-amber-400".to_string()),
+                        ];
+                        let value_class = threshold_class(ds.remaining_percentage, &thresholds)
+                            .unwrap_or("text-emerald-600 dark:text-emerald-400")
+                            .to_string();
+                        // Age badge: fresh within two scheduler intervals, stale from the server's
+                        // alert threshold (six intervals if unknown), aging in between
+                        let (interval_min, stale_min) = match &*status_v {
+                            Some(Some(st)) => {
+                                let interval = st.interval_minutes as i64;
+                                let stale = match st.stale_threshold_hours {
+                                    0 => 6 * interval,
+                                    h => h as i64 * 60,
+                                };
+                                (interval, stale)
+                            }
+                            _ => (0, 0),
+                        };
+                        let age_badge = if *hydrated.read() && interval_min > 0 {
+                            age_minutes(&ds.date_time).map(|age| {
+                                let cls = if age < 2 * interval_min {
+                                    "bg-emerald-500/15 text-emerald-700 dark:text-emerald-300"
+                                } else if age < stale_min {
+                                    "bg-amber-500/15 text-amber-700 dark:text-amber-300"
+                                } else {
+                                    "bg-red-500/15 text-red-700 dark:text-red-300"
+                                };
+                                (cls, format_age_minutes(age))
+                            })
                         } else {
-                            ds.date_time.clone()
+                            None
                         };
                         rsx! {
                         div { class: "flex flex-col items-center gap-3",
                             Gauge {
                                 value: ds.remaining_percentage,
-                                start_angle: 45.0,
-                                stop_angle: 315.0,
-                                size: 220,
-                                stroke: 14,
-                                track_class: "text-slate-800".to_string(),
-                                progress_class: "text-emerald-400".to_string(),
-                                div { class: "text-5xl font-bold text-emerald-400 tabular-nums", "{ds.remaining_percentage}%" }
+                                start_angle: geometry.start_angle,
+                                stop_angle: geometry.stop_angle,
+                                size: geometry.size,
+                                stroke: geometry.stroke,
+                                track_class: "text-slate-200 dark:text-slate-800".to_string(),
+                                progress_class: "text-emerald-600 dark:text-emerald-400".to_string(),
+                                thresholds,
+                                inner_value: used_pct,
+                                inner_class: "text-slate-400/70 dark:text-slate-500/70".to_string(),
+                                match gauge_mode {
+                                    GaugeMode::Percent => rsx! {
+                                        div { class: "text-5xl font-bold tabular-nums {value_class}", "{ds.remaining_percentage}%" }
+                                    },
+                                    GaugeMode::RemainingGb => rsx! {
+                                        div { class: "text-4xl font-bold tabular-nums {value_class}", "{format_megabytes(ds.remaining_data_mb)}" }
+                                    },
+                                }
+                            }
+                            div { class: "flex items-center gap-3",
+                                Sparkline { points: trend_points.clone(), width: 96, height: 24 }
+                                div { class: "text-lg text-slate-600 dark:text-slate-300",
+                                    match gauge_mode {
+                                        GaugeMode::Percent => format!("{} remaining", format_megabytes(ds.remaining_data_mb)),
+                                        GaugeMode::RemainingGb => format!("{}% remaining", ds.remaining_percentage),
+                                    }
+                                }
+                            }
+                            if let Some(line) = &used_line {
+                                div { class: "text-sm text-slate-500 dark:text-slate-400 tabular-nums", "{line}" }
+                            }
+                            div { class: "flex items-center gap-2",
+                                time { class: "text-xs text-slate-500 dark:text-slate-400", datetime: "{ds.date_time}", "As of {shown_time}",
+                                    if let Some(relative) = relative_time { " ({relative})" }
+                                }
+                                if let Some((cls, age)) = age_badge {
+                                    span { class: "rounded-full px-2 py-0.5 text-[10px] font-medium {cls}", "{age} old" }
+                                }
+                                if interval_min > 0 {
+                                    span { class: "text-xs text-slate-500 dark:text-slate-400",
+                                        if interval_min == 60 { "· updates hourly" } else { "· updates every {interval_min} min" }
+                                    }
+                                }
                             }
-                            div { class: "text-lg text-slate-300", "{format_megabytes(ds.remaining_data_mb)} remaining" }
-                            time { class: "text-xs text-slate-400", datetime: "{ds.date_time}", "As of {shown_time}" }
                         }
                     }},
                     // No data yet
                     Some(Ok(None)) => rsx! {
-                        div { class: "text-center text-slate-300",
+                        div { class: "text-center text-slate-600 dark:text-slate-300",
                             p { class: "text-lg", "No data yet" }
-                            p { class: "text-sm text-slate-400", "Awaiting SMS update from the router..." }
+                            p { class: "text-sm text-slate-500 dark:text-slate-400", "Awaiting SMS update from the router..." }
                         }
                     },
                     // Server fn error
+                    Some(Err(e)) if e.to_string().contains(DB_UNAVAILABLE) => rsx! {
+                        div { class: "text-center text-slate-600 dark:text-slate-300",
+                            p { class: "text-lg", "Backend database not ready" }
+                            p { class: "text-sm text-slate-500 dark:text-slate-400", "The server couldn't open its database; check the server logs." }
+                        }
+                    },
                     Some(Err(_e)) => rsx! {
-                        div { class: "text-center text-slate-300",
+                        div { class: "text-center text-slate-600 dark:text-slate-300",
                             p { class: "text-lg", "Failed to load status." }
                         }
                     },
                     // Only occurs on client-side navigations (not on first SSR render)
                     None => rsx! {
                         div { class: "animate-pulse space-y-3",
-                            div { class: "h-9 w-28 bg-slate-800 rounded" }
-                            div { class: "h-5 w-48 bg-slate-800 rounded" }
-                            div { class: "h-3 w-40 bg-slate-800 rounded" }
+                            div { class: "h-9 w-28 bg-slate-200 dark:bg-slate-800 rounded" }
+                            div { class: "h-5 w-48 bg-slate-200 dark:bg-slate-800 rounded" }
+                            div { class: "h-3 w-40 bg-slate-200 dark:bg-slate-800 rounded" }
                         }
                     },
                 }
             }
 
-            // Diagnostics (only when there's an error)
+            // Diagnostics (only when there's an error or the data went stale)
             {
                 match &*status_v {
-                    Some(Some(st)) if st.last_error.is_some() => rsx!{
-                        div { class: "pt-2 border-t border-slate-800 text-xs text-slate-400 space-y-1",
-                            if let Some(err) = &st.last_error { div { class: "text-red-400 text-sm font-medium", "Error: {err}" } }
+                    Some(Some(st)) if st.last_error.is_some() || st.stale_alert.is_some() || st.unconfigured.is_some() || st.passive => rsx!{
+                        div { class: "pt-2 border-t border-slate-200 dark:border-slate-800 text-xs text-slate-500 dark:text-slate-400 space-y-1",
+                            if let Some(reason) = &st.unconfigured {
+                                div { class: "rounded-lg border border-amber-200/60 dark:border-amber-900/60 bg-amber-500/10 px-3 py-2 text-sm font-medium text-amber-700 dark:text-amber-300", role: "status",
+                                    "Mikrotik not configured: {reason}. Set it and restart to start polling."
+                                }
+                            }
+                            if let Some(alert) = &st.stale_alert {
+                                div { class: "rounded-lg border border-red-200/60 dark:border-red-900/60 bg-red-500/10 px-3 py-2 text-base font-semibold text-red-700 dark:text-red-300", role: "alert", "⚠ {alert}" }
+                            }
+                            if st.passive { div { class: "text-sm font-medium", "Passive: another instance runs the scheduler on this database" } }
+                            if let Some(err) = &st.last_error { div { class: "text-red-600 dark:text-red-400 text-sm font-medium", "Error: {err}" } }
                             if let Some(ev) = &st.last_event { div { "Status: {ev}" } }
-                            if let Some(ts) = &st.last_loop_at { div { "Last loop: {format_local(ts)}" } }
-                            if let Some(next_ts) = &st.next_iteration_at { div { "Next run: {format_local(next_ts)}" } }
+                            if let Some(ts) = &st.last_loop_at { div { "Last loop: {format_local(ts, &locale())}" } }
+                            if let Some(next_ts) = &st.next_iteration_at { div { "Next run: {format_local(next_ts, &locale())}" } }
                         }
                     },
                     _ => rsx!( Fragment {} ),
@@ -171,3 +481,36 @@ pub fn DataStatusCard() -> Element {
         }
     }
 }
+
+/// How long the card watches for the reply after "Request SMS".
+const REPLY_WAIT_SECS: u64 = 60;
+
+/// Poll `latest_data_status` until its `date_time` differs from what `latest` shows,
+/// for up to `wait_secs` (plus a little slack for storing). Restarts `latest` on success.
+async fn wait_for_newer_reading(
+    mut latest: Resource<Result<Option<DataStatusDto>, ServerFnError>>,
+    wait_secs: u64,
+) -> bool {
+    #[cfg(feature = "web")]
+    {
+        let shown = match &*latest.peek() {
+            Some(Ok(Some(ds))) => Some(ds.date_time.clone()),
+            _ => None,
+        };
+        for _ in 0..(wait_secs + 5).div_ceil(3) {
+            gloo_timers::future::TimeoutFuture::new(3_000).await;
+            if let Ok(Some(ds)) = latest_data_status().await {
+                if shown.as_deref() != Some(ds.date_time.as_str()) {
+                    latest.restart();
+                    return true;
+                }
+            }
+        }
+        false
+    }
+    #[cfg(not(feature = "web"))]
+    {
+        let _ = (&mut latest, wait_secs);
+        false
+    }
+}
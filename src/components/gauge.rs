@@ -1,5 +1,43 @@
 use dioxus::prelude::*;
 
+use crate::shared::types::GaugeStyle;
+
+/// Angles (degrees, 0 at the bottom, clockwise, as `Gauge` takes them) and size of a
+/// gauge preset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GaugeGeometry {
+    pub start_angle: f32,
+    pub stop_angle: f32,
+    pub size: i32,
+    pub stroke: i32,
+}
+
+/// `Gauge` props for a preset: `ThreeQuarter` is open at the bottom (45° to 315°),
+/// `Half` spans the top from left to right (90° to 270°), and `Full` is a ring from
+/// the top, a hair short of 360° so the arc's ends don't meet.
+pub fn gauge_geometry(style: GaugeStyle) -> GaugeGeometry {
+    let (start_angle, stop_angle) = match style {
+        GaugeStyle::Full => (180.0, 539.9),
+        GaugeStyle::Half => (90.0, 270.0),
+        GaugeStyle::ThreeQuarter => (45.0, 315.0),
+    };
+    GaugeGeometry {
+        start_angle,
+        stop_angle,
+        size: 220,
+        stroke: 14,
+    }
+}
+
+/// Pick the class of the lowest threshold that `value` falls below, if any.
+pub fn threshold_class(value: i32, thresholds: &[(i32, String)]) -> Option<&str> {
+    thresholds
+        .iter()
+        .filter(|(limit, _)| value < *limit)
+        .min_by_key(|(limit, _)| *limit)
+        .map(|(_, class)| class.as_str())
+}
+
 #[allow(non_snake_case)]
 #[component]
 pub fn Gauge(
@@ -10,8 +48,15 @@ pub fn Gauge(
     stroke: i32,
     track_class: String,
     progress_class: String,
+    // (limit, class): when `value` is below `limit` the class replaces `progress_class`
+    #[props(default)] thresholds: Vec<(i32, String)>,
+    // Optional second value (0-100) drawn as a thin arc inside the main one
+    #[props(default)] inner_value: Option<i32>,
+    #[props(default)] inner_class: String,
     children: Element,
 ) -> Element {
+    let progress_class = threshold_class(value, &thresholds).unwrap_or(&progress_class);
+
     // Normalize & clamp
     let val = value.clamp(0, 100) as f32;
     let span = (stop_angle - start_angle).abs().max(0.0001);
@@ -45,6 +90,11 @@ pub fn Gauge(
 
     let track_d = arc_path(c, c, r, start0, stop0);
     let progress_d = arc_path(c, c, r, start0, end0);
+    let inner_r = r - stroke as f32 / 2.0 - 5.0;
+    let inner_d = inner_value.filter(|v| *v > 0).map(|v| {
+        let end = start0 + span * (v.clamp(0, 100) as f32 / 100.0);
+        arc_path(c, c, inner_r, start0, end)
+    });
 
     let size_attr = size.to_string();
     let view_box = format!("0 0 {size} {size}");
@@ -58,9 +108,24 @@ pub fn Gauge(
                 path { class: "{track_class}", d: "{track_d}", fill: "none", stroke: "currentColor", stroke_width: "{stroke_width}", stroke_linecap: "round" }
                 // Progress
                 path { class: "{progress_class}", d: "{progress_d}", fill: "none", stroke: "currentColor", stroke_width: "{stroke_width}", stroke_linecap: "round" }
+                if let Some(d) = inner_d {
+                    path { class: "{inner_class}", d: "{d}", fill: "none", stroke: "currentColor", stroke_width: "3", stroke_linecap: "round" }
+                }
             }
             // Center content
             div { class: "absolute inset-0 grid place-items-center", {children} }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_gauge_spans_the_top() {
+        let g = gauge_geometry(GaugeStyle::Half);
+        assert_eq!((g.start_angle, g.stop_angle), (90.0, 270.0));
+        assert_eq!((g.size, g.stroke), (220, 14));
+    }
+}
@@ -0,0 +1,154 @@
+use dioxus::prelude::*;
+
+use crate::api::{delete_reading, get_history_page, update_reading};
+use crate::components::{use_admin_token, use_locale, use_toasts};
+use crate::utils::format::{format_local, format_megabytes_rounded, GbRounding, MB_PER_GB_DECIMAL};
+
+const PAGE_SIZE: i64 = 25;
+const ACTION_CLASS: &str = "rounded-md px-1.5 py-0.5 hover:bg-slate-200 dark:hover:bg-slate-800";
+const INPUT_CLASS: &str = "rounded border border-slate-300 dark:border-slate-700 bg-transparent px-1 text-right tabular-nums";
+
+#[allow(non_snake_case)]
+#[component]
+pub fn HistoryTable() -> Element {
+    let mut page = use_signal(|| 0i64);
+    let mut ascending = use_signal(|| false);
+    let locale = use_locale();
+    let admin_token = use_admin_token();
+    let toasts = use_toasts();
+    // Row being edited: (id, percentage, MB) as typed
+    let mut editing = use_signal(|| Option::<(i64, String, String)>::None);
+    // Row whose delete button was clicked once
+    let mut confirm_delete = use_signal(|| Option::<i64>::None);
+    let mut data = use_resource(move || {
+        let (p, asc) = (page(), ascending());
+        async move {
+            get_history_page(PAGE_SIZE, p * PAGE_SIZE, asc)
+                .await
+                .ok()
+                .unwrap_or_default()
+        }
+    });
+    let history = data.read_unchecked().clone().unwrap_or_default();
+    let pages = ((history.total + PAGE_SIZE - 1) / PAGE_SIZE).max(1);
+    let arrow = if ascending() { "↑" } else { "↓" };
+
+    rsx! {
+        div { class: "rounded-2xl border border-slate-200 dark:border-slate-800 bg-white/60 dark:bg-slate-900/60 backdrop-blur-sm shadow-xl p-6 space-y-3",
+            div { class: "flex items-end justify-between",
+                h2 { class: "text-lg font-medium text-slate-700 dark:text-slate-200", "History" }
+                div { class: "text-xs text-slate-500 dark:text-slate-400", "{history.total} readings" }
+            }
+            table { class: "w-full text-xs",
+                thead {
+                    tr { class: "text-left text-slate-500 dark:text-slate-400",
+                        th { class: "py-1 font-normal",
+                            button {
+                                class: "hover:text-slate-700 dark:hover:text-slate-200",
+                                onclick: move |_| {
+                                    ascending.toggle();
+                                    page.set(0);
+                                },
+                                "Date {arrow}"
+                            }
+                        }
+                        th { class: "py-1 font-normal text-right", "Remaining" }
+                        th { class: "py-1 font-normal text-right", "Data" }
+                        if !admin_token().is_empty() {
+                            th { class: "py-1 font-normal text-right", "Edit" }
+                        }
+                    }
+                }
+                tbody {
+                    for r in history.rows.iter().cloned() {
+                        tr { key: "{r.date_time}", class: "border-t border-slate-200 dark:border-slate-800 text-slate-600 dark:text-slate-300",
+                            td { class: "py-1", "{format_local(&r.date_time, &locale())}" }
+                            match editing() {
+                                Some((id, pct, mb)) if id == r.id => rsx! {
+                                    td { class: "py-1 text-right",
+                                        input { class: "{INPUT_CLASS} w-14", r#type: "number", min: "0", max: "100", aria_label: "Remaining percentage", value: "{pct}",
+                                            oninput: move |e| editing.with_mut(|ed| if let Some(ed) = ed { ed.1 = e.value() }),
+                                        }
+                                    }
+                                    td { class: "py-1 text-right",
+                                        input { class: "{INPUT_CLASS} w-24", r#type: "number", min: "0", aria_label: "Remaining MB", value: "{mb}",
+                                            oninput: move |e| editing.with_mut(|ed| if let Some(ed) = ed { ed.2 = e.value() }),
+                                        }
+                                    }
+                                    td { class: "py-1 text-right whitespace-nowrap",
+                                        button { class: ACTION_CLASS,
+                                            onclick: move |_| {
+                                                let Some((id, pct, mb)) = editing() else { return };
+                                                let (Ok(pct), Ok(mb)) = (pct.trim().parse::<i32>(), mb.trim().parse::<i32>()) else {
+                                                    toasts.push("Enter whole numbers for percentage and MB".to_string());
+                                                    return;
+                                                };
+                                                spawn(async move {
+                                                    match update_reading(id, pct, mb, admin_token()).await {
+                                                        Ok(()) => {
+                                                            editing.set(None);
+                                                            data.restart();
+                                                        }
+                                                        Err(e) => toasts.push(format!("Update failed: {e}")),
+                                                    }
+                                                });
+                                            },
+                                            "Save"
+                                        }
+                                        button { class: ACTION_CLASS, onclick: move |_| editing.set(None), "Cancel" }
+                                    }
+                                },
+                                _ => rsx! {
+                                    td { class: "py-1 text-right tabular-nums", "{r.remaining_percentage}%" }
+                                    td { class: "py-1 text-right tabular-nums", "{format_megabytes_rounded(r.remaining_data_mb as f64, MB_PER_GB_DECIMAL, GbRounding::OneDecimal)}" }
+                                    if !admin_token().is_empty() {
+                                        td { class: "py-1 text-right whitespace-nowrap",
+                                            button { class: ACTION_CLASS,
+                                                onclick: move |_| {
+                                                    confirm_delete.set(None);
+                                                    editing.set(Some((r.id, r.remaining_percentage.to_string(), r.remaining_data_mb.to_string())));
+                                                },
+                                                "Edit"
+                                            }
+                                            if confirm_delete() == Some(r.id) {
+                                                button { class: "{ACTION_CLASS} text-red-600 dark:text-red-400",
+                                                    onclick: move |_| {
+                                                        confirm_delete.set(None);
+                                                        spawn(async move {
+                                                            match delete_reading(r.id, admin_token()).await {
+                                                                Ok(()) => data.restart(),
+                                                                Err(e) => toasts.push(format!("Delete failed: {e}")),
+                                                            }
+                                                        });
+                                                    },
+                                                    "Confirm delete"
+                                                }
+                                            } else {
+                                                button { class: ACTION_CLASS, onclick: move |_| confirm_delete.set(Some(r.id)), "Delete" }
+                                            }
+                                        }
+                                    }
+                                },
+                            }
+                        }
+                    }
+                }
+            }
+            div { class: "flex items-center justify-between text-xs text-slate-500 dark:text-slate-400",
+                button {
+                    class: "rounded-md px-2 py-0.5 hover:bg-slate-200 dark:hover:bg-slate-800 disabled:opacity-40",
+                    disabled: page() == 0,
+                    onclick: move |_| page -= 1,
+                    "Prev"
+                }
+                span { "Page {page() + 1} of {pages}" }
+                button {
+                    class: "rounded-md px-2 py-0.5 hover:bg-slate-200 dark:hover:bg-slate-800 disabled:opacity-40",
+                    disabled: page() + 1 >= pages,
+                    onclick: move |_| page += 1,
+                    "Next"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use dioxus::prelude::*;
+
+use crate::api::get_locale;
+use crate::shared::types::LocaleConfig;
+
+/// Provide the server's `LOCALE` to all descendants (called once in `App`).
+/// Starts with the default format and switches once the config is fetched.
+pub fn use_locale_provider() {
+    let mut locale = use_context_provider(|| Signal::new(LocaleConfig::default()));
+    let fetched = use_resource(|| async move { get_locale().await.ok() });
+    use_effect(move || {
+        if let Some(Some(l)) = *fetched.read() {
+            locale.set(l);
+        }
+    });
+}
+
+/// Locale from the surrounding `App`.
+pub fn use_locale() -> Signal<LocaleConfig> {
+    use_context::<Signal<LocaleConfig>>()
+}
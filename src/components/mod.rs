@@ -1,7 +1,35 @@
+pub mod add_reading_form;
+pub mod admin_token;
+pub mod burn_down_chart;
+pub mod cycle_comparison;
 pub mod data_status_card;
 pub mod gauge;
+pub mod history_table;
+pub mod locale;
+pub mod period_usage;
+pub mod raw_sms_sample;
+pub mod recent_readings;
+pub mod sparkline;
+pub mod theme;
+pub mod toasts;
 pub mod usage_chart_view;
+pub mod usage_stats;
+pub mod weekday_breakdown;
 
+pub use add_reading_form::AddReadingForm;
+pub use admin_token::{use_admin_token, use_admin_token_provider, AdminLogin};
+pub use burn_down_chart::BurnDownChart;
+pub use cycle_comparison::CycleComparison;
 pub use data_status_card::DataStatusCard;
-pub use gauge::Gauge;
+pub use gauge::{gauge_geometry, threshold_class, Gauge};
+pub use history_table::HistoryTable;
+pub use locale::{use_locale, use_locale_provider};
+pub use period_usage::PeriodUsage;
+pub use raw_sms_sample::RawSmsSample;
+pub use recent_readings::RecentReadings;
+pub use sparkline::Sparkline;
+pub use theme::{use_theme, use_theme_provider, ThemeToggle};
+pub use toasts::{use_toast_provider, use_toasts, Toasts};
 pub use usage_chart_view::UsageChartView;
+pub use usage_stats::UsageStats;
+pub use weekday_breakdown::WeekdayBreakdown;
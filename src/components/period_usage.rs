@@ -0,0 +1,43 @@
+use dioxus::prelude::*;
+
+use crate::api::get_total_usage;
+use crate::components::use_locale;
+use crate::utils::format::{format_megabytes, format_ymd};
+
+/// Pick two dates and get the data used between them (both days included).
+#[allow(non_snake_case)]
+#[component]
+pub fn PeriodUsage() -> Element {
+    let mut start = use_signal(String::new);
+    let mut end = use_signal(String::new);
+    let locale = use_locale();
+    let total = use_resource(move || {
+        let (s, e) = (start(), end());
+        async move {
+            if s.is_empty() || e.is_empty() {
+                return None;
+            }
+            Some(get_total_usage(s, e).await)
+        }
+    });
+    let input_class = "rounded border border-slate-300 dark:border-slate-700 bg-transparent px-1 text-slate-700 dark:text-slate-200";
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-2 text-xs text-slate-500 dark:text-slate-400",
+            span { "Used between" }
+            input { class: input_class, r#type: "date", aria_label: "First day", value: "{start}", oninput: move |e| start.set(e.value()) }
+            span { "and" }
+            input { class: input_class, r#type: "date", aria_label: "Last day", value: "{end}", oninput: move |e| end.set(e.value()) }
+            match &*total.read_unchecked() {
+                Some(Some(Ok(t))) => rsx! {
+                    span { class: "text-sm text-slate-700 dark:text-slate-200 tabular-nums", "{format_megabytes(t.used_mb)}" }
+                    if t.start != start() || t.end != end() {
+                        span { "(readings cover {format_ymd(&locale(), &t.start)} to {format_ymd(&locale(), &t.end)})" }
+                    }
+                },
+                Some(Some(Err(e))) => rsx! { span { class: "text-red-600 dark:text-red-400", "{e}" } },
+                _ => rsx! {},
+            }
+        }
+    }
+}
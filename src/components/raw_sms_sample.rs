@@ -0,0 +1,65 @@
+use dioxus::prelude::*;
+
+use crate::api::get_raw_sms_sample;
+use crate::components::{use_admin_token, use_toasts};
+use crate::shared::types::RawSmsDto;
+
+/// Admin view of the newest inbox SMS as received, for spotting a change in the
+/// carrier's wording when readings stop coming in although the router answers.
+#[allow(non_snake_case)]
+#[component]
+pub fn RawSmsSample(#[props(default = 10)] limit: usize) -> Element {
+    let admin_token = use_admin_token();
+    let toasts = use_toasts();
+    let mut redact = use_signal(|| true);
+    let mut loading = use_signal(|| false);
+    let mut smss = use_signal(|| Option::<Vec<RawSmsDto>>::None);
+
+    let on_load = move |_| {
+        loading.set(true);
+        spawn(async move {
+            match get_raw_sms_sample(limit, redact(), admin_token()).await {
+                Ok(list) => smss.set(Some(list)),
+                Err(e) => toasts.push(format!("Inbox: {e}")),
+            }
+            loading.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "rounded-2xl border border-slate-200 dark:border-slate-800 bg-white/60 dark:bg-slate-900/60 backdrop-blur-sm shadow-xl p-6 space-y-3",
+            div { class: "flex items-center justify-between gap-2",
+                h2 { class: "text-lg font-medium text-slate-700 dark:text-slate-200", "Inbox (raw)" }
+                div { class: "flex items-center gap-3 text-xs text-slate-600 dark:text-slate-300",
+                    label { class: "flex items-center gap-1",
+                        input { r#type: "checkbox", checked: redact(), onchange: move |e| redact.set(e.checked()) }
+                        "Hide numbers"
+                    }
+                    button {
+                        class: "rounded-lg border border-slate-300 dark:border-slate-700 px-3 py-1 text-xs text-slate-600 dark:text-slate-300 hover:bg-slate-200 dark:hover:bg-slate-800 disabled:opacity-50",
+                        disabled: loading(),
+                        onclick: on_load,
+                        if smss.read().is_some() { "Reload" } else { "Load" }
+                    }
+                }
+            }
+            match &*smss.read() {
+                None => rsx! { p { class: "text-xs text-slate-500 dark:text-slate-400", "Newest {limit} SMS as the router returns them, not parsed." } },
+                Some(list) if list.is_empty() => rsx! { p { class: "text-xs text-slate-500 dark:text-slate-400", "The inbox is empty." } },
+                Some(list) => rsx! {
+                    ul { class: "divide-y divide-slate-200 dark:divide-slate-800",
+                        for (i, sms) in list.iter().enumerate() {
+                            li { key: "{i}", class: "py-2 space-y-1",
+                                div { class: "flex gap-3 text-[11px] text-slate-500 dark:text-slate-400 tabular-nums",
+                                    span { {sms.time.clone().unwrap_or_else(|| "no time".into())} }
+                                    if let Some(from) = &sms.from { span { "{from}" } }
+                                }
+                                pre { class: "whitespace-pre-wrap break-words font-mono text-xs text-slate-700 dark:text-slate-200", "{sms.message}" }
+                            }
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
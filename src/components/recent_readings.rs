@@ -0,0 +1,52 @@
+use dioxus::prelude::*;
+
+use crate::api::get_recent_readings;
+use crate::components::use_locale;
+use crate::utils::format::{format_local, format_megabytes_rounded, GbRounding, MB_PER_GB_DECIMAL};
+
+const RECENT_COUNT: i64 = 10;
+
+/// The last few raw readings, to spot a router that stopped reporting or repeats itself.
+#[allow(non_snake_case)]
+#[component]
+pub fn RecentReadings() -> Element {
+    let locale = use_locale();
+    let data =
+        use_resource(|| async move { get_recent_readings(RECENT_COUNT).await.unwrap_or_default() });
+
+    // Refresh in the background; the list is cheap
+    #[cfg(feature = "web")]
+    use_future(move || {
+        let mut data = data;
+        async move {
+            loop {
+                gloo_timers::future::sleep(std::time::Duration::from_secs(60)).await;
+                data.restart();
+            }
+        }
+    });
+
+    let rows = data.read_unchecked().clone().unwrap_or_default();
+
+    rsx! {
+        div { class: "rounded-2xl border border-slate-200 dark:border-slate-800 bg-white/60 dark:bg-slate-900/60 backdrop-blur-sm shadow-xl p-6 space-y-3",
+            h2 { class: "text-lg font-medium text-slate-700 dark:text-slate-200", "Recent readings" }
+            if rows.is_empty() {
+                p { class: "text-xs text-slate-500 dark:text-slate-400", "No readings yet." }
+            }
+            ul { class: "text-xs divide-y divide-slate-200 dark:divide-slate-800",
+                for (i, r) in rows.iter().enumerate() {
+                    li { key: "{r.date_time}", class: "flex items-center gap-3 py-1 text-slate-600 dark:text-slate-300",
+                        time { class: "flex-1", datetime: "{r.date_time}", "{format_local(&r.date_time, &locale())}" }
+                        // Same values as the reading before it (rows are newest first)
+                        if rows.get(i + 1).is_some_and(|prev| prev.remaining_percentage == r.remaining_percentage && prev.remaining_data_mb == r.remaining_data_mb) {
+                            span { class: "rounded px-1.5 bg-amber-500/15 text-amber-700 dark:text-amber-300", "repeat" }
+                        }
+                        span { class: "w-10 text-right tabular-nums", "{r.remaining_percentage}%" }
+                        span { class: "w-20 text-right tabular-nums", "{format_megabytes_rounded(r.remaining_data_mb as f64, MB_PER_GB_DECIMAL, GbRounding::OneDecimal)}" }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use dioxus::prelude::*;
+
+/// Minimal line chart of `(x, y)` points scaled to fill the box. The box always
+/// occupies `width`×`height`, so it doesn't shift layout while data loads; with
+/// fewer than two points it stays empty.
+#[allow(non_snake_case)]
+#[component]
+pub fn Sparkline(
+    points: Vec<(f64, f64)>,
+    width: i32,
+    height: i32,
+    #[props(default = "text-emerald-600 dark:text-emerald-400".to_string())] class: String,
+) -> Element {
+    let path = if points.len() < 2 {
+        None
+    } else {
+        let (min_x, max_x) = min_max(points.iter().map(|p| p.0));
+        let (min_y, max_y) = min_max(points.iter().map(|p| p.1));
+        // Keep the stroke inside the box
+        let pad = 1.5;
+        let (w, h) = (width as f64 - 2.0 * pad, height as f64 - 2.0 * pad);
+        let span_x = (max_x - min_x).max(f64::EPSILON);
+        // A flat series is drawn through the middle
+        let span_y = max_y - min_y;
+        Some(
+            points
+                .iter()
+                .map(|(x, y)| {
+                    let px = pad + (x - min_x) / span_x * w;
+                    let py = if span_y > 0.0 {
+                        pad + (max_y - y) / span_y * h
+                    } else {
+                        pad + h / 2.0
+                    };
+                    format!("{px:.1},{py:.1}")
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    };
+
+    rsx! {
+        svg {
+            class: "{class} shrink-0",
+            width: "{width}",
+            height: "{height}",
+            view_box: "0 0 {width} {height}",
+            "aria-hidden": "true",
+            if let Some(path) = path {
+                polyline { points: "{path}", fill: "none", stroke: "currentColor", stroke_width: "1.5", stroke_linejoin: "round", stroke_linecap: "round" }
+            }
+        }
+    }
+}
+
+fn min_max(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+        (lo.min(v), hi.max(v))
+    })
+}
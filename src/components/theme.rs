@@ -0,0 +1,106 @@
+use dioxus::prelude::*;
+
+/// `localStorage` key holding the user's choice.
+const STORAGE_KEY: &str = "trullo-theme";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            Theme::Dark => Theme::Light,
+            Theme::Light => Theme::Dark,
+        }
+    }
+
+    /// Page background, for the browser's `theme-color`.
+    pub fn page_color(self) -> &'static str {
+        match self {
+            Theme::Dark => "#020618",  // slate-950
+            Theme::Light => "#f8fafc", // slate-50
+        }
+    }
+
+    /// Colors for SVG attributes, which can't use `dark:` classes.
+    pub fn chart_axis(self) -> &'static str {
+        match self {
+            Theme::Dark => "#1f2937",
+            Theme::Light => "#e2e8f0",
+        }
+    }
+
+    pub fn chart_accent(self) -> &'static str {
+        match self {
+            Theme::Dark => "#10b981",
+            Theme::Light => "#059669",
+        }
+    }
+
+    /// (fill, stroke) of the chart tooltip
+    pub fn tooltip_colors(self) -> (&'static str, &'static str) {
+        match self {
+            Theme::Dark => ("#0f172a", "#334155"),
+            Theme::Light => ("#ffffff", "#cbd5e1"),
+        }
+    }
+}
+
+/// Provide the current theme to all descendants (called once in `App`).
+/// Renders dark on the server; in the browser it switches to the stored choice,
+/// or to `prefers-color-scheme` when nothing is stored yet.
+pub fn use_theme_provider() -> Signal<Theme> {
+    let mut theme = use_context_provider(|| Signal::new(Theme::default()));
+    use_effect(move || {
+        spawn(async move {
+            let js = format!(
+                r#"return localStorage.getItem("{STORAGE_KEY}")
+                    || (matchMedia("(prefers-color-scheme: light)").matches ? "light" : "dark");"#
+            );
+            if let Ok(v) = document::eval(&js).await {
+                if v.as_str() == Some("light") {
+                    theme.set(Theme::Light);
+                }
+            }
+        });
+    });
+    theme
+}
+
+/// Theme from the surrounding `App`.
+pub fn use_theme() -> Signal<Theme> {
+    use_context::<Signal<Theme>>()
+}
+
+/// Switches between the dark and light palette and remembers the choice.
+#[allow(non_snake_case)]
+#[component]
+pub fn ThemeToggle() -> Element {
+    let mut theme = use_theme();
+    let next = theme().toggled();
+    rsx! {
+        button {
+            class: "rounded-lg border border-slate-300 dark:border-slate-700 px-3 py-1 text-xs text-slate-600 dark:text-slate-300 hover:bg-slate-200 dark:hover:bg-slate-800",
+            aria_label: "Switch to {next.name()} theme",
+            onclick: move |_| {
+                theme.set(next);
+                let _ = document::eval(&format!(
+                    r#"localStorage.setItem("{STORAGE_KEY}", "{}");"#,
+                    next.name()
+                ));
+            },
+            if next == Theme::Light { "☀ Light" } else { "☾ Dark" }
+        }
+    }
+}
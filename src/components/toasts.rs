@@ -0,0 +1,75 @@
+use dioxus::prelude::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toast {
+    pub id: u64,
+    pub message: String,
+}
+
+/// Shared error queue, provided by `App` as context and rendered by `Toasts`.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ToastQueue {
+    toasts: Signal<Vec<Toast>>,
+    next_id: Signal<u64>,
+}
+
+impl ToastQueue {
+    /// Show `message`, unless the same message is already on screen.
+    pub fn push(mut self, message: impl Into<String>) {
+        let message = message.into();
+        if self.toasts.peek().iter().any(|t| t.message == message) {
+            return;
+        }
+        let id = *self.next_id.peek();
+        self.next_id.set(id + 1);
+        self.toasts.write().push(Toast { id, message });
+    }
+
+    pub fn dismiss(mut self, id: u64) {
+        self.toasts.write().retain(|t| t.id != id);
+    }
+}
+
+/// Create the queue and provide it to all descendants (called once in `App`).
+pub fn use_toast_provider() -> ToastQueue {
+    use_context_provider(|| ToastQueue {
+        toasts: Signal::new(Vec::new()),
+        next_id: Signal::new(0),
+    })
+}
+
+/// Toast queue from the surrounding `App`.
+pub fn use_toasts() -> ToastQueue {
+    use_context::<ToastQueue>()
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn Toasts() -> Element {
+    let queue = use_toasts();
+    // Effects never run during SSR: render nothing until mounted on the client
+    // so server and hydration markup match
+    let mut mounted = use_signal(|| false);
+    use_effect(move || mounted.set(true));
+    if !mounted() {
+        return rsx!(Fragment {});
+    }
+
+    rsx! {
+        div { class: "fixed bottom-4 right-4 z-50 flex w-full max-w-sm flex-col gap-2", role: "alert", aria_live: "assertive",
+            for toast in queue.toasts.read().iter().cloned() {
+                div {
+                    key: "{toast.id}",
+                    class: "flex items-start gap-3 rounded-lg border border-red-200/60 dark:border-red-900/60 bg-white/95 dark:bg-slate-900/95 px-4 py-3 text-sm text-red-700 dark:text-red-300 shadow-xl",
+                    span { class: "flex-1 break-words", "{toast.message}" }
+                    button {
+                        class: "shrink-0 text-slate-500 dark:text-slate-400 hover:text-slate-900 dark:hover:text-slate-100",
+                        aria_label: "Dismiss",
+                        onclick: move |_| queue.dismiss(toast.id),
+                        "✕"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,31 +1,220 @@
 use dioxus::prelude::*;
 
-use crate::api::get_daily_usage;
-use crate::utils::format::{format_megabytes, format_megabytes_f32};
+use crate::api::{
+    get_coverage, get_daily_usage, get_monthly_usage, get_weekly_usage, DEFAULT_USAGE_DAYS,
+    MAX_USAGE_DAYS,
+};
+use crate::components::{use_locale, use_theme, use_toasts, PeriodUsage, UsageStats};
+use crate::shared::types::{DailyUsagePointDto, Granularity};
+use crate::utils::format::{format_megabytes, format_megabytes_f32, format_ymd, format_ymd_month};
 
+/// Tooltip box `(x, y, w, h)` for the bar whose top center is at (`x`, `y`): above the
+/// bar when there is room, always inside the plot horizontally and the viewBox
+/// vertically.
+fn tooltip_rect(
+    x: f32,
+    y: f32,
+    content_w: f32,
+    width: f32,
+    height: f32,
+    padding: f32,
+) -> (f32, f32, f32, f32) {
+    let tip_w = content_w.max(12.0).min(width - padding * 2.0);
+    let tip_h = 36.0f32; // two lines
+    let tip_x = (x - tip_w / 2.0).clamp(padding, (width - padding) - tip_w);
+    let tip_y = (y - 10.0 - tip_h)
+        .max(6.0)
+        .min((height + padding * 2.0 - tip_h).max(0.0));
+    (tip_x, tip_y, tip_w, tip_h)
+}
+
+/// Bar and tooltip colors of `UsageChartView`. Bars take Tailwind classes (filled with
+/// `currentColor`); `None` colors follow the page theme.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartPalette {
+    pub bar_class: String,
+    /// Days without measurable usage
+    pub zero_bar_class: String,
+    /// Tooltip pointer line
+    pub accent: Option<String>,
+    /// Tooltip (fill, stroke)
+    pub tooltip: Option<(String, String)>,
+}
+
+impl Default for ChartPalette {
+    fn default() -> Self {
+        Self {
+            bar_class: "text-emerald-600/80 dark:text-emerald-400/80".into(),
+            zero_bar_class: "text-slate-200 dark:text-slate-800".into(),
+            accent: None,
+            tooltip: None,
+        }
+    }
+}
+
+/// Horizontal layout of the bars in SVG units; bar `i` starts at `offset + i * pitch`
+/// (plus the chart padding).
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BarGeometry {
+    pitch: f32,
+    gap: f32,
+    bar_w: f32,
+    /// Left margin centering bars narrower than `min_width`
+    offset: f32,
+    plot_width: f32,
+}
+
+/// Longest plot before bars shrink, in SVG units.
+const MAX_PLOT_WIDTH: f32 = 720.0;
+
+/// Nominal pitch until the plot reaches `MAX_PLOT_WIDTH`, then shrunk so long ranges
+/// keep the same overall width; the gap keeps its share of the pitch. Below
+/// `min_width` bars widen up to twice their pitch and the rest is split on both sides,
+/// so a single bar (n = 1) sits centered.
+fn bar_geometry(
+    n: usize,
+    bar_width: f32,
+    bar_gap: f32,
+    bucket_days: u32,
+    min_width: f32,
+) -> BarGeometry {
+    let n = n.max(1) as f32;
+    let nominal_pitch = (bar_width + bar_gap).max(1.0);
+    let widest = match bucket_days {
+        1 => nominal_pitch,
+        7 => nominal_pitch * 1.75,
+        _ => nominal_pitch * 3.0,
+    };
+    let min_width = min_width.clamp(0.0, MAX_PLOT_WIDTH);
+    let pitch = (MAX_PLOT_WIDTH / n)
+        .min(widest)
+        .max((min_width / n).min(widest * 2.0));
+    let gap = (pitch * bar_gap / nominal_pitch).min(bar_gap);
+    let plot_width = (n * pitch).max(min_width);
+    BarGeometry {
+        pitch,
+        gap,
+        bar_w: (pitch - gap).max(1.0),
+        offset: (plot_width - n * pitch) / 2.0,
+        plot_width,
+    }
+}
+
+/// Usage bar chart with range and day/week/month buttons; `granularity` is the initial
+/// bar size. Geometry is in SVG units: bars are `bar_width` wide with `bar_gap` between
+/// them (weekly bars 1.75×, monthly 3× that pitch), shrinking to fit long ranges in
+/// about 720 units; short ranges fill at least `min_width` (see `bar_geometry`).
+/// Without `show_zero_days`, days without usage leave an empty slot instead of a faint
+/// stub.
 #[allow(non_snake_case)]
 #[component]
-pub fn UsageChartView() -> Element {
+pub fn UsageChartView(
+    #[props(default = 6.0)] bar_width: f32,
+    #[props(default = 2.0)] bar_gap: f32,
+    #[props(default = 180.0)] height: f32,
+    #[props(default = 20.0)] padding: f32,
+    #[props(default)] palette: ChartPalette,
+    #[props(default = true)] show_zero_days: bool,
+    #[props(default)] granularity: Granularity,
+    #[props(default = 360.0)] min_width: f32,
+) -> Element {
+    // Selected range (days) and bar size; changing either re-runs the resource
+    let mut days = use_signal(|| DEFAULT_USAGE_DAYS);
+    let mut selected = use_signal(|| granularity);
     // Fetch data
-    let data = use_resource(|| async move { get_daily_usage().await.ok().unwrap_or_default() });
-    let points = data.read_unchecked().clone().unwrap_or_default();
+    let data = use_resource(move || {
+        let d = days();
+        let g = selected();
+        async move {
+            match g {
+                Granularity::Day => get_daily_usage(d, false).await,
+                Granularity::Week => get_weekly_usage(d, false).await,
+                Granularity::Month => get_monthly_usage(d, false).await,
+            }
+        }
+    });
+    // Days with readings in the range, so inferred usage comes with its gaps
+    let coverage = use_resource(move || {
+        let d = days();
+        async move { get_coverage(d).await.ok() }
+    });
+    let coverage = coverage.read_unchecked().clone().flatten();
+    let toasts = use_toasts();
+    use_effect(move || {
+        if let Some(Err(e)) = &*data.read() {
+            toasts.push(format!("Usage chart: {e}"));
+        }
+    });
+    let points = match &*data.read_unchecked() {
+        Some(Ok(points)) => points.clone(),
+        _ => vec![],
+    };
+    // Loaded, but no day in the range had usage measured: not the same as zero use
+    let no_data =
+        matches!(&*data.read_unchecked(), Some(Ok(_))) && !points.iter().any(|p| p.has_data);
+    // Long daily ranges come back in weekly buckets too
+    let bucket_days = points.first().map_or(1, |p| p.bucket_days);
+    let title = format!(
+        "{} usage ({})",
+        match bucket_days {
+            1 => "Daily",
+            7 => "Weekly",
+            _ => "Monthly",
+        },
+        if days() >= MAX_USAGE_DAYS {
+            "all time".to_string()
+        } else {
+            format!("last {} days", days())
+        }
+    );
     // Hovered bar index (for tooltip)
     let mut hovered = use_signal(|| Option::<usize>::None);
     // Visual params
-    let height = 180.0f32;
-    let padding = 20.0f32;
-    let bar_gap = 2.0f32;
-    let n = points.len().max(1) as f32;
-    let width = (n * (6.0 + bar_gap) + padding * 2.0).ceil();
+    let BarGeometry {
+        pitch,
+        gap,
+        bar_w,
+        offset,
+        plot_width,
+    } = bar_geometry(points.len(), bar_width, bar_gap, bucket_days, min_width);
+    let x0 = padding + offset;
+    let width = (plot_width + padding * 2.0).ceil();
     let max_used = points.iter().map(|p| p.used_mb).max().unwrap_or(1) as f32;
+    // Gridline in the gap before the first bar of each new month (not before the
+    // first bar; monthly bars need none)
+    let month_starts: Vec<f32> = if bucket_days < 30 {
+        points
+            .windows(2)
+            .enumerate()
+            .filter(|(_, w)| w[0].date.get(..7) != w[1].date.get(..7))
+            .map(|(i, _)| x0 + (i + 1) as f32 * pitch - gap / 2.0)
+            .collect()
+    } else {
+        vec![]
+    };
     let view_box = format!("0 0 {} {}", width, height + padding * 2.0);
 
-    // Date formatter for tooltip (yyyy-mm-dd -> dd.mm.yyyy)
-    let fmt_date = |s: &str| -> String {
-        if s.len() >= 10 {
-            format!("{}.{}.{}", &s[8..10], &s[5..7], &s[0..4])
-        } else {
-            s.to_string()
+    // Dates arrive as yyyy-mm-dd and are shown in the configured order
+    let locale = use_locale();
+    let theme = use_theme();
+    let (tip_fill, tip_stroke) = match &palette.tooltip {
+        Some((fill, stroke)) => (fill.clone(), stroke.clone()),
+        None => {
+            let (fill, stroke) = theme().tooltip_colors();
+            (fill.to_string(), stroke.to_string())
+        }
+    };
+    let accent = palette
+        .accent
+        .clone()
+        .unwrap_or_else(|| theme().chart_accent().to_string());
+    // Weeks show their ISO label and first day, months their name
+    let fmt_date = move |p: &DailyUsagePointDto| -> String {
+        match (p.bucket_days, &p.label) {
+            (1, _) => format_ymd(&locale(), &p.date),
+            (7, Some(label)) => format!("{label} ({})", format_ymd(&locale(), &p.date)),
+            (7, None) => format!("Week of {}", format_ymd(&locale(), &p.date)),
+            _ => format_ymd_month(&locale(), &p.date),
         }
     };
 
@@ -33,69 +222,181 @@ pub fn UsageChartView() -> Element {
     use std::collections::HashSet;
 
     rsx! {
-        div { class: "rounded-2xl border border-slate-800 bg-slate-900/60 backdrop-blur-sm shadow-xl p-6 space-y-3",
+        div { class: "rounded-2xl border border-slate-200 dark:border-slate-800 bg-white/60 dark:bg-slate-900/60 backdrop-blur-sm shadow-xl p-6 space-y-3",
             div { class: "flex items-end justify-between",
-                h2 { class: "text-lg font-medium text-slate-200", "Daily usage (last 90 days)" }
-                if max_used > 0.0 { div { class: "text-xs text-slate-400", "Peak: {format_megabytes_f32(max_used)}" } }
+                h2 { class: "text-lg font-medium text-slate-700 dark:text-slate-200", "{title}" }
+                div { class: "flex gap-3 text-xs text-slate-500 dark:text-slate-400",
+                    if let Some(c) = coverage.as_ref().filter(|c| c.days_total > 0) {
+                        span { title: "Longest gap between readings: {c.longest_gap_hours} h",
+                            "Coverage: {c.days_with_data}/{c.days_total} days"
+                        }
+                    }
+                    if max_used > 0.0 { span { "Peak: {format_megabytes_f32(max_used)}" } }
+                }
             }
-            div { class: "w-full overflow-x-auto",
-                svg { class: "block min-w-full", view_box: "{view_box}", width: "100%", height: "{(height + padding*2.0).to_string()}",
-                    line { x1: "{padding}", y1: "{padding + height}", x2: "{width - padding}", y2: "{padding + height}", stroke: "#1f2937", stroke_width: "1" }
-                    {
-                        points.iter().enumerate().map(|(i, p)| {
-                            let x = padding + (i as f32) * (6.0 + bar_gap);
-                            let h = if max_used <= 0.0 { 0.0 } else { (p.used_mb as f32) / max_used * height };
-                            let y = padding + (height - h);
-                            let cls = if p.used_mb == 0 { "text-slate-800" } else { "text-emerald-400/80" };
-                            rsx!{ rect {
-                                key: "{i}", class: "{cls}", x: "{x}", y: "{y}", width: "6", height: "{h}", fill: "currentColor", rx: "2",
-                                onmouseenter: move |_| *hovered.write() = Some(i),
-                                onmouseleave: move |_| *hovered.write() = None,
-                                ontouchstart: move |_| *hovered.write() = Some(i),
-                                ontouchend: move |_| *hovered.write() = None,
-                            }}
-                        })
+            div { class: "flex gap-1",
+                for d in [7i64, 30, 90, 365, MAX_USAGE_DAYS] {
+                    button {
+                        key: "{d}",
+                        class: if days() == d { "rounded-md px-2 py-0.5 text-xs bg-slate-300 dark:bg-slate-700 text-slate-900 dark:text-slate-100" } else { "rounded-md px-2 py-0.5 text-xs text-slate-500 dark:text-slate-400 hover:bg-slate-200 dark:hover:bg-slate-800" },
+                        onclick: move |_| {
+                            hovered.set(None);
+                            days.set(d);
+                        },
+                        if d == MAX_USAGE_DAYS { "All" } else { "{d}d" }
                     }
-                    {
-                        match *hovered.read() {
-                            Some(i) => {
-                                let p = &points[i];
-                                let x = padding + (i as f32) * (6.0 + bar_gap) + 3.0; // center of bar
+                }
+                span { class: "mx-1 border-l border-slate-300 dark:border-slate-700" }
+                for (g, label) in [(Granularity::Day, "Day"), (Granularity::Week, "Week"), (Granularity::Month, "Month")] {
+                    button {
+                        key: "{label}",
+                        class: if selected() == g { "rounded-md px-2 py-0.5 text-xs bg-slate-300 dark:bg-slate-700 text-slate-900 dark:text-slate-100" } else { "rounded-md px-2 py-0.5 text-xs text-slate-500 dark:text-slate-400 hover:bg-slate-200 dark:hover:bg-slate-800" },
+                        onclick: move |_| {
+                            hovered.set(None);
+                            selected.set(g);
+                        },
+                        "{label}"
+                    }
+                }
+            }
+            if no_data {
+                div { class: "flex items-center justify-center text-sm text-slate-500 dark:text-slate-400", style: "height: {height + padding * 2.0}px",
+                    "No usage data yet"
+                }
+            } else {
+                div { class: "w-full overflow-x-auto",
+                    svg { class: "block min-w-full", view_box: "{view_box}", width: "100%", height: "{(height + padding*2.0).to_string()}",
+                        for x in month_starts.iter() {
+                            line { key: "{x}", class: "text-slate-300/60 dark:text-slate-700/60", x1: "{x}", y1: "{padding}", x2: "{x}", y2: "{padding + height}", stroke: "currentColor", stroke_width: "1" }
+                        }
+                        line { x1: "{padding}", y1: "{padding + height}", x2: "{width - padding}", y2: "{padding + height}", stroke: "{theme().chart_axis()}", stroke_width: "1" }
+                        {
+                            points.iter().enumerate().filter(|(_, p)| show_zero_days || p.used_mb > 0).map(|(i, p)| {
+                                let x = x0 + (i as f32) * pitch;
                                 let h = if max_used <= 0.0 { 0.0 } else { (p.used_mb as f32) / max_used * height };
                                 let y = padding + (height - h);
-                                let date_label = fmt_date(&p.date);
-                                let value_label = format!("{}", format_megabytes(p.used_mb));
-                                let cw = 7.0f32; // approx char width at 11px
-                                let content_w = (date_label.len().max(value_label.len()) as f32) * cw + 12.0; // padding
-                                let tip_w = content_w.max(12.0).min(width - padding * 2.0);
-                                let tip_h = 36.0f32; // two lines
-                                let tip_x = (x - tip_w / 2.0).clamp(padding, (width - padding) - tip_w);
-                                let tip_y = (y - 10.0 - tip_h).max(6.0);
-                                rsx!{ g { key: "tooltip",
-                                    line { x1: "{x}", y1: "{y}", x2: "{x}", y2: "{tip_y + tip_h}", stroke: "#10b981", stroke_width: "1" }
-                                    rect { x: "{tip_x}", y: "{tip_y}", width: "{tip_w}", height: "{tip_h}", rx: "6", fill: "#0f172a", stroke: "#334155", stroke_width: "1" }
-                                    text { x: "{tip_x + 8.0}", y: "{tip_y + 16.0}", class: "fill-current text-[11px] text-slate-300", "{date_label}" }
-                                    text { x: "{tip_x + 8.0}", y: "{tip_y + 30.0}", class: "fill-current text-[11px] text-slate-200", "{value_label}" }
+                                let cls = if p.used_mb == 0 { &palette.zero_bar_class } else { &palette.bar_class };
+                                let label = format!("{}: {} used", fmt_date(p), format_megabytes(p.used_mb));
+                                rsx!{ rect {
+                                    key: "{i}", class: "{cls} focus:outline-none focus-visible:stroke-slate-900 dark:focus-visible:stroke-slate-100 focus-visible:stroke-2", x: "{x}", y: "{y}", width: "{bar_w}", height: "{h}", fill: "currentColor", rx: "{(bar_w / 3.0).min(2.0)}",
+                                    tabindex: "0",
+                                    role: "img",
+                                    "aria-label": "{label}",
+                                    onfocus: move |_| *hovered.write() = Some(i),
+                                    onblur: move |_| *hovered.write() = None,
+                                    onmouseenter: move |_| *hovered.write() = Some(i),
+                                    onmouseleave: move |_| *hovered.write() = None,
+                                    ontouchstart: move |_| *hovered.write() = Some(i),
+                                    ontouchend: move |_| *hovered.write() = None,
                                 }}
-                            }
-                            None => rsx!{ Fragment {} }
+                            })
                         }
-                    }
-                    {
-                        points.iter().enumerate().scan(HashSet::<String>::new(), |printed, (i, p)| {
-                            if p.date.len() >= 7 {
-                                let m = &p.date[..7];
-                                if printed.insert(m.to_string()) {
-                                    let x = padding + (i as f32) * (6.0 + bar_gap);
-                                    let node = rsx!{ text { x: "{x}", y: "{height + padding + 14.0}", class: "text-slate-400 fill-current text-[10px]", "{m}" } };
-                                    return Some(Some(node));
+                        {
+                            match *hovered.read() {
+                                Some(i) if i < points.len() => {
+                                    let p = &points[i];
+                                    let x = x0 + (i as f32) * pitch + bar_w / 2.0; // center of bar
+                                    let h = if max_used <= 0.0 { 0.0 } else { (p.used_mb as f32) / max_used * height };
+                                    let y = padding + (height - h);
+                                    let date_label = fmt_date(p);
+                                    let value_label = format_megabytes(p.used_mb);
+                                    let cw = 7.0f32; // approx char width at 11px
+                                    let content_w = (date_label.len().max(value_label.len()) as f32) * cw + 12.0; // padding
+                                    let (tip_x, tip_y, tip_w, tip_h) = tooltip_rect(x, y, content_w, width, height, padding);
+                                    rsx!{ g { key: "tooltip",
+                                        line { x1: "{x}", y1: "{y}", x2: "{x}", y2: "{tip_y + tip_h}", stroke: "{accent}", stroke_width: "1" }
+                                        rect { x: "{tip_x}", y: "{tip_y}", width: "{tip_w}", height: "{tip_h}", rx: "6", fill: "{tip_fill}", stroke: "{tip_stroke}", stroke_width: "1" }
+                                        text { x: "{tip_x + 8.0}", y: "{tip_y + 16.0}", class: "fill-current text-[11px] text-slate-600 dark:text-slate-300", "{date_label}" }
+                                        text { x: "{tip_x + 8.0}", y: "{tip_y + 30.0}", class: "fill-current text-[11px] text-slate-700 dark:text-slate-200", "{value_label}" }
+                                    }}
                                 }
+                                _ => rsx!{ Fragment {} }
                             }
-                            Some(None)
-                        }).filter_map(|x| x)
+                        }
+                        {
+                            // Skip months whose label would overlap the previous one
+                            points.iter().enumerate().scan((HashSet::<String>::new(), f32::NEG_INFINITY), |(printed, last_x), (i, p)| {
+                                if p.date.len() >= 7 {
+                                    let m = &p.date[..7];
+                                    let x = x0 + (i as f32) * pitch;
+                                    if x - *last_x >= 40.0 && printed.insert(m.to_string()) {
+                                        *last_x = x;
+                                        let label = format_ymd_month(&locale(), &p.date);
+                                        let node = rsx!{ text { x: "{x}", y: "{height + padding + 14.0}", class: "text-slate-500 dark:text-slate-400 fill-current text-[10px]", "{label}" } };
+                                        return Some(Some(node));
+                                    }
+                                }
+                                Some(None)
+                            }).flatten()
+                        }
+                    }
+                }
+            }
+            // Same series as a table for screen readers
+            table { class: "sr-only",
+                caption { "{title}" }
+                thead { tr { th { scope: "col", "Date" } th { scope: "col", "Used" } } }
+                tbody {
+                    for p in points.iter() {
+                        tr { key: "{p.date}",
+                            td { "{fmt_date(p)}" }
+                            td { "{format_megabytes(p.used_mb)}" }
+                        }
                     }
                 }
             }
+            UsageStats {}
+            PeriodUsage {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geometry(n: usize) -> BarGeometry {
+        // Default props: 6 wide, 2 apart, daily bars, 360 minimum width
+        bar_geometry(n, 6.0, 2.0, 1, 360.0)
+    }
+
+    #[test]
+    fn a_single_bar_is_centered() {
+        let g = geometry(1);
+        assert_eq!((g.pitch, g.gap, g.bar_w), (16.0, 2.0, 14.0));
+        assert_eq!(g.plot_width, 360.0);
+        assert_eq!(g.offset, 172.0);
+    }
+
+    #[test]
+    fn a_week_fills_the_minimum_width() {
+        let g = geometry(7);
+        assert_eq!((g.pitch, g.bar_w), (16.0, 14.0));
+        assert_eq!(g.plot_width, 360.0);
+        assert_eq!(g.offset * 2.0 + 7.0 * g.pitch, g.plot_width);
+    }
+
+    #[test]
+    fn ninety_days_keep_the_nominal_pitch() {
+        let g = geometry(90);
+        assert_eq!((g.pitch, g.gap, g.bar_w), (8.0, 2.0, 6.0));
+        assert_eq!(g.plot_width, MAX_PLOT_WIDTH);
+        assert_eq!(g.offset, 0.0);
+    }
+
+    #[test]
+    fn tooltip_stays_inside_a_small_chart() {
+        // A sparkline: 3 px bars, no minimum width, 40 high with 4 padding
+        let (height, padding) = (40.0, 4.0);
+        let g = bar_geometry(5, 2.0, 1.0, 1, 0.0);
+        let width = (g.plot_width + padding * 2.0).ceil();
+        for i in 0..5 {
+            let x = padding + g.offset + i as f32 * g.pitch + g.bar_w / 2.0;
+            // Full-height bar, and a label far wider than the chart
+            let (tip_x, tip_y, tip_w, tip_h) =
+                tooltip_rect(x, padding, 110.0, width, height, padding);
+            assert!(tip_x >= padding && tip_x + tip_w <= width - padding);
+            assert!(tip_y >= 0.0 && tip_y + tip_h <= height + padding * 2.0);
         }
     }
 }
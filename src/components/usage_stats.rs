@@ -0,0 +1,34 @@
+use dioxus::prelude::*;
+
+use crate::api::{get_cycle_start, get_usage_stats};
+use crate::components::use_locale;
+use crate::utils::format::{format_local, format_megabytes};
+
+#[allow(non_snake_case)]
+#[component]
+pub fn UsageStats() -> Element {
+    let stats = use_resource(|| async move { get_usage_stats().await.ok().unwrap_or_default() });
+    let s = stats.read_unchecked().clone().unwrap_or_default();
+    let locale = use_locale();
+    let cycle_start = use_resource(|| async move { get_cycle_start().await.ok().flatten() });
+
+    rsx! {
+        div { class: "grid grid-cols-2 sm:grid-cols-4 gap-3 pt-3 border-t border-slate-200 dark:border-slate-800 text-xs text-slate-500 dark:text-slate-400",
+            div { "Used this cycle"
+                div { class: "text-sm text-slate-700 dark:text-slate-200 tabular-nums", "{format_megabytes(s.total_used_this_cycle_mb)}" }
+            }
+            div { "Avg per day"
+                div { class: "text-sm text-slate-700 dark:text-slate-200 tabular-nums", "{format_megabytes(s.avg_daily_mb)}" }
+            }
+            div { "Peak day"
+                div { class: "text-sm text-slate-700 dark:text-slate-200 tabular-nums", "{format_megabytes(s.peak_day_mb)}" }
+            }
+            div { "Day of cycle"
+                div { class: "text-sm text-slate-700 dark:text-slate-200 tabular-nums", "{s.days_into_cycle}" }
+            }
+            if let Some(Some(start)) = &*cycle_start.read_unchecked() {
+                div { class: "col-span-full", "Cycle started on {format_local(start, &locale())}" }
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use dioxus::prelude::*;
+
+use crate::api::{get_weekday_usage, DEFAULT_USAGE_DAYS};
+use crate::utils::format::format_megabytes;
+
+#[allow(non_snake_case)]
+#[component]
+pub fn WeekdayBreakdown() -> Element {
+    let data = use_resource(|| async move {
+        get_weekday_usage(DEFAULT_USAGE_DAYS)
+            .await
+            .ok()
+            .unwrap_or_default()
+    });
+    let points = data.read_unchecked().clone().unwrap_or_default();
+    let max_avg = points.iter().map(|p| p.avg_mb).max().unwrap_or(0).max(1) as f32;
+
+    rsx! {
+        div { class: "rounded-2xl border border-slate-200 dark:border-slate-800 bg-white/60 dark:bg-slate-900/60 backdrop-blur-sm shadow-xl p-6 space-y-3",
+            h2 { class: "text-lg font-medium text-slate-700 dark:text-slate-200", "Average usage by weekday" }
+            div { class: "space-y-1",
+                for p in points.iter() {
+                    div { key: "{p.weekday}", class: "flex items-center gap-3 text-xs",
+                        span { class: "w-8 text-slate-500 dark:text-slate-400", "{p.weekday}" }
+                        div { class: "flex-1 h-3 rounded bg-slate-200 dark:bg-slate-800",
+                            div {
+                                class: "h-3 rounded bg-emerald-500/80 dark:bg-emerald-400/80",
+                                style: "width:{(p.avg_mb as f32) / max_avg * 100.0}%",
+                            }
+                        }
+                        span {
+                            class: "w-16 text-right text-slate-600 dark:text-slate-300 tabular-nums",
+                            title: "{p.samples} day(s) measured",
+                            "{format_megabytes(p.avg_mb)}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
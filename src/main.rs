@@ -20,20 +20,36 @@ fn main() {
     {
         backend::init_tracing();
 
+        // Load .env first so it can provide DATABASE_URL / DATA_DIR
+        dotenvy::dotenv().ok();
+
         // Initialize the global DB once at boot
         let db_url = backend::db::resolve_db_url();
         {
-            use dotenvy::dotenv;
-            dotenv().ok();
             let rt = tokio::runtime::Runtime::new().expect("rt");
             rt.block_on(async {
-                match backend::Db::connect(&db_url).await {
-                    Ok(db) => {
-                        let _ = backend::GLOBAL_DB.set(Arc::new(db));
-                        eprintln!("[db] initialized");
-                    }
-                    Err(e) => {
-                        eprintln!("[db] failed to init: {e}");
+                // Retry with backoff, e.g. while a network volume is still being mounted
+                const ATTEMPTS: u32 = 5;
+                for attempt in 1..=ATTEMPTS {
+                    match backend::Db::connect(&db_url).await {
+                        Ok(db) => {
+                            let _ = backend::GLOBAL_DB.set(Arc::new(db));
+                            eprintln!("[db] initialized");
+                            break;
+                        }
+                        // A damaged file stays damaged; don't wait through the retries
+                        Err(e) if attempt < ATTEMPTS && !backend::db::is_corruption(&e) => {
+                            let wait = std::time::Duration::from_secs(1 << attempt);
+                            eprintln!(
+                                "[db] failed to init (attempt {attempt}/{ATTEMPTS}): {e}; retrying in {}s",
+                                wait.as_secs()
+                            );
+                            tokio::time::sleep(wait).await;
+                        }
+                        Err(e) => {
+                            eprintln!("[db] failed to init after {attempt} attempt(s): {e:#}");
+                            break;
+                        }
                     }
                 }
             });
@@ -48,6 +64,15 @@ fn main() {
                     .next()
                     .and_then(|s| s.parse::<i32>().ok())
                     .unwrap_or(102_400);
+                // optional: days of history and RNG seed
+                let days = args
+                    .next()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .unwrap_or(backend::scheduler::TEST_DATA_DAYS);
+                let seed = args
+                    .next()
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .unwrap_or(backend::scheduler::TEST_DATA_SEED);
                 // block_on small runtime
                 let rt = tokio::runtime::Runtime::new().expect("rt");
                 rt.block_on(async move {
@@ -55,8 +80,13 @@ fn main() {
                         eprintln!("[gen-test-data] GLOBAL_DB not initialized");
                         std::process::exit(1);
                     };
-                    if let Err(e) =
-                        backend::scheduler::generate_test_data(db.clone(), plan_total_mb).await
+                    if let Err(e) = backend::scheduler::generate_test_data(
+                        db.clone(),
+                        plan_total_mb,
+                        days,
+                        seed,
+                    )
+                    .await
                     {
                         eprintln!("error generating test data: {e}");
                         std::process::exit(1);
@@ -64,8 +94,84 @@ fn main() {
                 });
                 return;
             }
+            if cmd == "import-csv" {
+                let Some(path) = args.next() else {
+                    eprintln!("usage: import-csv <path>");
+                    std::process::exit(2);
+                };
+                let rt = tokio::runtime::Runtime::new().expect("rt");
+                rt.block_on(async move {
+                    let Some(db) = backend::GLOBAL_DB.get() else {
+                        eprintln!("[import-csv] GLOBAL_DB not initialized");
+                        std::process::exit(1);
+                    };
+                    match backend::import::import_csv(db, std::path::Path::new(&path)).await {
+                        Ok(r) => eprintln!(
+                            "import-csv: inserted {}, skipped {} (duplicates), malformed {}",
+                            r.inserted, r.skipped, r.malformed
+                        ),
+                        Err(e) => {
+                            eprintln!("import-csv: {e}");
+                            std::process::exit(1);
+                        }
+                    }
+                });
+                return;
+            }
+            if cmd == "reparse" {
+                // Re-run the parser over the inbox after a pattern change
+                let fix = match args.next().as_deref() {
+                    None => false,
+                    Some("--fix") => true,
+                    Some(_) => {
+                        eprintln!("usage: reparse [--fix]");
+                        std::process::exit(2);
+                    }
+                };
+                let rt = tokio::runtime::Runtime::new().expect("rt");
+                rt.block_on(async move {
+                    let Some(db) = backend::GLOBAL_DB.get() else {
+                        eprintln!("[reparse] GLOBAL_DB not initialized");
+                        std::process::exit(1);
+                    };
+                    match backend::import::reparse_inbox(db, fix).await {
+                        Ok(r) => eprintln!(
+                            "reparse: {} SMS, {} not parseable, {} newly matched, {} differing from the DB ({} corrected)",
+                            r.inbox, r.unparsed, r.inserted, r.mismatched, r.corrected
+                        ),
+                        Err(e) => {
+                            eprintln!("reparse: {e:#}");
+                            std::process::exit(1);
+                        }
+                    }
+                });
+                return;
+            }
             if cmd == "import-sms" {
                 // Import all Mikrotik SMS that look like WindTre data status into the DB
+                let mut dry_run = false;
+                let mut since = None;
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--dry-run" => dry_run = true,
+                        "--since" => {
+                            let bound = args.next();
+                            since = bound
+                                .as_deref()
+                                .and_then(|s| api::parse_range_bound(s, false));
+                            if since.is_none() {
+                                eprintln!(
+                                    "import-sms: --since needs YYYY-MM-DD or an RFC3339 timestamp"
+                                );
+                                std::process::exit(2);
+                            }
+                        }
+                        _ => {
+                            eprintln!("usage: import-sms [--dry-run] [--since <date>]");
+                            std::process::exit(2);
+                        }
+                    }
+                }
                 let rt = tokio::runtime::Runtime::new().expect("rt");
                 rt.block_on(async move {
                     let Some(db) = backend::GLOBAL_DB.get() else {
@@ -78,31 +184,51 @@ fn main() {
                             let mut inserted = 0usize;
                             for sms in smss.iter() {
                                 total += 1;
-                                if let Some(ds) = backend::windtre::parse_data_status_from_sms(sms)
+                                let Some(ds) = backend::windtre::parse_data_status_from_sms(sms)
+                                else {
+                                    continue;
+                                };
+                                if since.is_some_and(|s| ds.date_time < s) {
+                                    continue;
+                                }
+                                if dry_run {
+                                    eprintln!(
+                                        "import-sms: would insert {}: {}%, {} MB",
+                                        ds.date_time, ds.remaining_percentage, ds.remaining_data_mb
+                                    );
+                                    inserted += 1;
+                                    continue;
+                                }
+                                match db
+                                    .insert_data_status(
+                                        ds.remaining_percentage,
+                                        ds.remaining_data_mb,
+                                        ds.date_time,
+                                    )
+                                    .await
                                 {
-                                    match db
-                                        .insert_data_status(
-                                            ds.remaining_percentage,
-                                            ds.remaining_data_mb,
-                                            ds.date_time,
-                                        )
-                                        .await
-                                    {
-                                        Ok(rowid) => {
-                                            if rowid != 0 {
-                                                inserted += 1;
-                                            }
-                                        }
-                                        Err(e) => {
-                                            eprintln!(
-                                                "import-sms: db insert error for {}: {}",
-                                                ds.date_time, e
-                                            );
+                                    Ok(rowid) => {
+                                        if rowid != 0 {
+                                            inserted += 1;
                                         }
                                     }
+                                    Err(e) => {
+                                        eprintln!(
+                                            "import-sms: db insert error for {}: {}",
+                                            ds.date_time, e
+                                        );
+                                    }
                                 }
                             }
-                            eprintln!("import-sms: processed {}, inserted {}", total, inserted);
+                            if dry_run {
+                                // Duplicates aren't checked without writing
+                                eprintln!(
+                                    "import-sms: dry run, processed {}, {} parseable (nothing written)",
+                                    total, inserted
+                                );
+                            } else {
+                                eprintln!("import-sms: processed {}, inserted {}", total, inserted);
+                            }
                         }
                         Err(e) => {
                             eprintln!("import-sms: failed to fetch SMS: {e}");
@@ -114,6 +240,22 @@ fn main() {
             }
         }
 
+        match backend::windtre::carrier_config() {
+            Ok(c) => eprintln!(
+                "[windtre] status requests: {:?} to {}",
+                c.request_body, c.shortcode
+            ),
+            Err(e) => {
+                eprintln!("[windtre] invalid carrier config: {e}");
+                std::process::exit(1);
+            }
+        }
+
+        if let Err(e) = backend::base_path_config() {
+            eprintln!("[http] invalid BASE_PATH: {e}");
+            std::process::exit(1);
+        }
+
         let _ = std::thread::Builder::new()
             .name("scheduler-rt".into())
             .spawn(|| {
@@ -136,6 +278,20 @@ fn main() {
                 });
             })
             .expect("spawn scheduler thread");
+
+        backend::http::launch(app::App);
+    }
+    #[cfg(not(feature = "server"))]
+    {
+        // Behind a base path the server fns are mounted under it too
+        #[cfg(feature = "web")]
+        if let Some(base) = dioxus::cli_config::web_base_path() {
+            let base = base.trim_matches('/');
+            if !base.is_empty() {
+                let url: &'static str = Box::leak(format!("/{base}").into_boxed_str());
+                server_fn::client::set_server_url(url);
+            }
+        }
+        dioxus::launch(app::App);
     }
-    dioxus::launch(app::App);
 }
@@ -8,6 +8,13 @@ pub struct DataStatusDto {
     pub remaining_data_mb: i32,
     #[serde(rename = "dateTime")]
     pub date_time: String,
+    /// Row id, for editing/deleting; 0 when the reading isn't from the database
+    #[serde(default)]
+    pub id: i64,
+    /// `date_time` as the server renders it (`DISPLAY_TZ`), shown until the client
+    /// can format it in the browser's timezone
+    #[serde(default)]
+    pub display_time: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -19,10 +26,260 @@ pub struct SchedulerStatusDto {
     pub last_event: Option<String>,
     pub last_error: Option<String>,
     pub next_iteration_at: Option<String>,
+    /// Scheduler cadence; 0 when unknown
+    #[serde(default)]
+    pub interval_minutes: u64,
+    /// Set while the newest reading is older than `STALE_ALERT_HOURS`
+    #[serde(default)]
+    pub stale_alert: Option<String>,
+    /// `STALE_ALERT_HOURS`; 0 when unknown
+    #[serde(default)]
+    pub stale_threshold_hours: u64,
+    /// Why the poll loop wasn't started (Mikrotik env missing); `None` when configured
+    #[serde(default)]
+    pub unconfigured: Option<String>,
+    /// Another process holds the scheduler lease on this DB, so this one doesn't poll
+    #[serde(default)]
+    pub passive: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DailyUsagePointDto {
-    pub date: String, // yyyy-mm-dd
-    pub used_mb: i32, // usage within that day
+    pub date: String, // yyyy-mm-dd, first day of the bucket
+    pub used_mb: i32, // usage within the bucket
+    /// Days per bucket: 1 for daily bars, 7 for weekly ones on long ranges
+    #[serde(default = "one_day")]
+    pub bucket_days: u32,
+    /// Bucket name for weeks and months, e.g. `2024-W20` or `2024-05`
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Usage was measured on the day (any day of a bucket); without it `used_mb` is
+    /// a gap in the readings, not a day without use
+    #[serde(default)]
+    pub has_data: bool,
+}
+
+fn one_day() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmsRequestDto {
+    pub sent: bool,
+    pub message: String,
+}
+
+/// How complete the readings behind a chart range are.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CoverageDto {
+    pub days_total: i64,
+    /// Days with at least one reading
+    pub days_with_data: i64,
+    pub longest_gap_hours: i64,
+    pub last_reading_age_hours: Option<i64>,
+}
+
+/// Data used since the current cycle started, against the plan size when known.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CycleUsageDto {
+    pub used_mb: i32,
+    pub plan_total_mb: Option<i32>,
+    pub cycle_start: String, // RFC 3339
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageStatsDto {
+    pub total_used_this_cycle_mb: i32,
+    pub avg_daily_mb: i32,
+    pub days_into_cycle: i32,
+    pub peak_day_mb: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeekdayUsageDto {
+    pub weekday: String, // Mon..Sun
+    pub avg_mb: i32,     // average over days that could be measured
+    pub samples: i32,    // number of measured days
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthDto {
+    pub db_ok: bool,
+    /// `None` when the Mikrotik probe wasn't requested
+    pub mikrotik_ok: Option<bool>,
+    pub scheduler_running: bool,
+    pub latest_reading_age_seconds: Option<i64>,
+}
+
+/// Database size in bytes around an `OptimizeDb` run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OptimizeDbDto {
+    pub size_before_bytes: i64,
+    pub size_after_bytes: i64,
+}
+
+/// An inbox SMS as received, for checking the parser against the carrier's wording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RawSmsDto {
+    pub message: String,
+    pub from: Option<String>,
+    /// RFC 3339 when the router's time could be read, else the raw value
+    pub time: Option<String>,
+}
+
+/// One reading for the card's sparkline; `timestamp` is Unix seconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrendPointDto {
+    pub timestamp: i64,
+    pub remaining_data_mb: i32,
+}
+
+/// Last reading of one day for the burn-down chart; `timestamp` is Unix seconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemainingPointDto {
+    pub date_time: String, // RFC 3339
+    pub timestamp: i64,
+    pub remaining_percentage: i32,
+}
+
+/// Remaining percentage over time plus the current cycle's bounds (Unix seconds),
+/// between which the ideal line falls from 100% to 0%.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RemainingSeriesDto {
+    pub points: Vec<RemainingPointDto>,
+    pub cycle_start: i64,
+    pub cycle_end: i64,
+    /// `LOW_DATA_THRESHOLD` in MB and as a share of the plan, when both are known
+    #[serde(default)]
+    pub low_threshold: Option<(i32, f32)>,
+    /// Where this cycle's average daily usage leads by `cycle_end`
+    #[serde(default)]
+    pub projection: Option<CycleProjectionDto>,
+}
+
+/// Remaining data at the end of the cycle if usage keeps its average pace so far.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CycleProjectionDto {
+    /// Negative when the plan runs out first (the shortfall)
+    pub projected_remaining_at_cycle_end_mb: i32,
+    pub will_run_out: bool,
+    /// Days between running out and the reset; 0 unless `will_run_out`
+    pub days_early: i64,
+}
+
+/// Cumulative usage (MB) by day of cycle; index 0 is the cycle's first day.
+/// `previous` is empty until a whole previous cycle is stored.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CycleComparisonDto {
+    pub current: Vec<i32>,
+    pub previous: Vec<i32>,
+}
+
+/// Bar size of the usage chart.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Granularity {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+/// Usage within a window of days, after clamping it to the stored readings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TotalUsageDto {
+    pub start: String, // yyyy-mm-dd
+    pub end: String,   // yyyy-mm-dd, inclusive
+    pub used_mb: i32,
+}
+
+// `ForceRefresh` is for API clients only (the UI goes through `RequestStatusSms`), so
+// the client build never uses these types.
+/// How `ForceRefresh` waits for the carrier's reply SMS.
+#[cfg_attr(not(feature = "server"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum RefreshMode {
+    /// Hold the request open until the reply is stored or the poll timeout passes.
+    /// Long timeouts can outlast proxy/server-fn request timeouts.
+    #[default]
+    Blocking,
+    /// Return right after sending; the server stores the reply in the background and
+    /// the client polls `latest_data_status` for a newer `date_time`. Avoids
+    /// long-running server-fn requests.
+    FireAndPoll,
+}
+
+#[cfg_attr(not(feature = "server"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ForceRefreshStatus {
+    Fresh,
+    AwaitingReply,
+    Error,
+}
+
+#[cfg_attr(not(feature = "server"), allow(dead_code))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForceRefreshDto {
+    pub status: ForceRefreshStatus,
+    /// The new reading (`Fresh` only)
+    pub data_status: Option<DataStatusDto>,
+    pub message: String,
+    /// How long the server keeps waiting for the reply (`AwaitingReply` only)
+    pub wait_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum DateOrder {
+    /// `dd.mm.yyyy`
+    #[default]
+    DayMonthYear,
+    /// `yyyy-mm-dd`
+    YearMonthDay,
+    /// `mm/dd/yyyy`
+    MonthDayYear,
+}
+
+/// Current cycle's bounds (Unix seconds) and average pace, with where that pace
+/// leads by the reset. `projection` is `None` before the cycle's first reading.
+/// Served to API clients only; the UI reads the projection from `RemainingSeriesDto`.
+#[cfg_attr(not(feature = "server"), allow(dead_code))]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ForecastDto {
+    pub cycle_start: i64,
+    pub cycle_end: i64,
+    pub reset_day: u32,
+    pub remaining_mb: i32,
+    pub avg_daily_mb: i32,
+    pub projection: Option<CycleProjectionDto>,
+}
+
+/// Date/time display settings (`LOCALE`), shared with the client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LocaleConfig {
+    pub date_order: DateOrder,
+    pub hour12: bool,
+}
+
+/// What the card's gauge shows in its center (`DISPLAY_MODE`); the arc is always the percentage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum GaugeMode {
+    #[default]
+    Percent,
+    RemainingGb,
+}
+
+/// Shape of the card's gauge (`GAUGE_STYLE`); `components::gauge_geometry` turns it
+/// into `Gauge` props.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum GaugeStyle {
+    Full,
+    Half,
+    #[default]
+    ThreeQuarter,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HistoryPageDto {
+    pub rows: Vec<DataStatusDto>,
+    /// Number of readings in the whole table
+    pub total: i64,
 }
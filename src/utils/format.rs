@@ -1,4 +1,5 @@
-#[cfg(all(feature = "web"))]
+use crate::shared::types::{DateOrder, LocaleConfig};
+
 pub fn pad2(n: i32) -> String {
     if n < 10 {
         format!("0{}", n)
@@ -7,8 +8,78 @@ pub fn pad2(n: i32) -> String {
     }
 }
 
-#[cfg(all(feature = "web"))]
-pub fn format_local(rfc3339: &str) -> String {
+/// Parse a `LOCALE` value: a date pattern (`dd.mm.yyyy`, `yyyy-mm-dd`, `mm/dd/yyyy`)
+/// and/or `12h`/`24h`, separated by spaces, e.g. `"yyyy-mm-dd 12h"`.
+#[cfg(feature = "server")]
+pub fn parse_locale(s: &str) -> Option<LocaleConfig> {
+    let mut locale = LocaleConfig::default();
+    for token in s.split_whitespace() {
+        match token.to_ascii_lowercase().as_str() {
+            "dd.mm.yyyy" => locale.date_order = DateOrder::DayMonthYear,
+            "yyyy-mm-dd" => locale.date_order = DateOrder::YearMonthDay,
+            "mm/dd/yyyy" => locale.date_order = DateOrder::MonthDayYear,
+            "12h" => locale.hour12 = true,
+            "24h" => locale.hour12 = false,
+            _ => return None,
+        }
+    }
+    Some(locale)
+}
+
+pub fn format_date(locale: &LocaleConfig, year: i32, month: i32, day: i32) -> String {
+    match locale.date_order {
+        DateOrder::DayMonthYear => format!("{}.{}.{}", pad2(day), pad2(month), year),
+        DateOrder::YearMonthDay => format!("{}-{}-{}", year, pad2(month), pad2(day)),
+        DateOrder::MonthDayYear => format!("{}/{}/{}", pad2(month), pad2(day), year),
+    }
+}
+
+/// Month label for chart axes, e.g. `05.2024` / `2024-05` / `05/2024`.
+pub fn format_month(locale: &LocaleConfig, year: i32, month: i32) -> String {
+    match locale.date_order {
+        DateOrder::DayMonthYear => format!("{}.{}", pad2(month), year),
+        DateOrder::YearMonthDay => format!("{}-{}", year, pad2(month)),
+        DateOrder::MonthDayYear => format!("{}/{}", pad2(month), year),
+    }
+}
+
+#[cfg(any(feature = "web", feature = "server"))]
+pub fn format_time(locale: &LocaleConfig, hour: i32, minute: i32) -> String {
+    if locale.hour12 {
+        let h = if hour % 12 == 0 { 12 } else { hour % 12 };
+        let suffix = if hour < 12 { "AM" } else { "PM" };
+        format!("{}:{} {}", h, pad2(minute), suffix)
+    } else {
+        format!("{}:{}", pad2(hour), pad2(minute))
+    }
+}
+
+/// Split a `yyyy-mm-dd` date string into numbers.
+fn split_ymd(s: &str) -> Option<(i32, i32, i32)> {
+    let y = s.get(0..4)?.parse().ok()?;
+    let m = s.get(5..7)?.parse().ok()?;
+    let d = s.get(8..10)?.parse().ok()?;
+    Some((y, m, d))
+}
+
+/// Reformat a `yyyy-mm-dd` string; anything else is returned unchanged.
+pub fn format_ymd(locale: &LocaleConfig, s: &str) -> String {
+    match split_ymd(s) {
+        Some((y, m, d)) => format_date(locale, y, m, d),
+        None => s.to_string(),
+    }
+}
+
+/// Month label for a `yyyy-mm-dd` string.
+pub fn format_ymd_month(locale: &LocaleConfig, s: &str) -> String {
+    match split_ymd(s) {
+        Some((y, m, _)) => format_month(locale, y, m),
+        None => s.to_string(),
+    }
+}
+
+#[cfg(feature = "web")]
+pub fn format_local(rfc3339: &str, locale: &LocaleConfig) -> String {
     use js_sys::Date;
     let d = Date::new(&wasm_bindgen::JsValue::from_str(rfc3339));
     if d.get_time().is_nan() {
@@ -20,42 +91,296 @@ pub fn format_local(rfc3339: &str) -> String {
     let hour = d.get_hours() as i32;
     let minute = d.get_minutes() as i32;
     format!(
-        "{}.{}.{} {}:{}",
-        pad2(day),
-        pad2(month),
-        year,
-        pad2(hour),
-        pad2(minute)
+        "{} {}",
+        format_date(locale, year, month, day),
+        format_time(locale, hour, minute)
+    )
+}
+
+/// On the server: in `DISPLAY_TZ` when set, so server-rendered times match what the
+/// browser shows for users in that zone; otherwise the timestamp as is.
+#[cfg(all(feature = "server", not(feature = "web")))]
+pub fn format_local(rfc3339: &str, locale: &LocaleConfig) -> String {
+    format_in_tz(rfc3339, locale, *crate::backend::DISPLAY_TZ)
+}
+
+#[cfg(all(feature = "server", not(feature = "web")))]
+fn format_in_tz(rfc3339: &str, locale: &LocaleConfig, tz: Option<chrono_tz::Tz>) -> String {
+    use chrono::{Datelike, Timelike};
+    let (Some(tz), Ok(dt)) = (tz, chrono::DateTime::parse_from_rfc3339(rfc3339)) else {
+        return rfc3339.to_string();
+    };
+    let d = dt.with_timezone(&tz);
+    format!(
+        "{} {}",
+        format_date(locale, d.year(), d.month() as i32, d.day() as i32),
+        format_time(locale, d.hour() as i32, d.minute() as i32)
     )
 }
 
-#[cfg(not(all(feature = "web")))]
-pub fn format_local(rfc3339: &str) -> String {
+#[cfg(not(any(feature = "web", feature = "server")))]
+pub fn format_local(rfc3339: &str, _locale: &LocaleConfig) -> String {
     rfc3339.to_string()
 }
 
-pub fn format_megabytes(mb: i32) -> String {
-    if mb.abs() >= 1_000 {
-        let gb = mb as f64 / 1_000.0;
-        if (gb.fract()).abs() < f64::EPSILON || (gb * 10.0).round() % 10.0 == 0.0 {
-            format!("{} GB", gb.round() as i32)
-        } else {
-            format!("{:.1} GB", gb)
-        }
+/// Minutes between `rfc3339` and now, using the browser clock.
+#[cfg(feature = "web")]
+pub fn age_minutes(rfc3339: &str) -> Option<i64> {
+    use js_sys::Date;
+    let t = Date::new(&wasm_bindgen::JsValue::from_str(rfc3339)).get_time();
+    if t.is_nan() {
+        return None;
+    }
+    Some(((Date::now() - t) / 60_000.0).floor() as i64)
+}
+
+/// Not available without a client clock; callers render nothing until hydrated.
+#[cfg(not(feature = "web"))]
+pub fn age_minutes(_rfc3339: &str) -> Option<i64> {
+    None
+}
+
+/// "3 minutes ago" for `rfc3339` at `now_ms` (ms since the epoch, browser clock).
+#[cfg(feature = "web")]
+pub fn format_relative(rfc3339: &str, now_ms: f64) -> String {
+    use js_sys::Date;
+    let t = Date::new(&wasm_bindgen::JsValue::from_str(rfc3339)).get_time();
+    if t.is_nan() {
+        return rfc3339.to_string();
+    }
+    format_relative_minutes(((now_ms - t) / 60_000.0).floor() as i64)
+}
+
+/// Without a client clock the timestamp is returned as is.
+#[cfg(not(feature = "web"))]
+pub fn format_relative(rfc3339: &str, _now_ms: f64) -> String {
+    rfc3339.to_string()
+}
+
+/// "just now", "3 minutes ago", "2 hours ago", "5 days ago", rounded down. Clock
+/// skew (a reading "in the future") counts as just now.
+#[cfg(any(feature = "web", test))]
+pub fn format_relative_minutes(minutes: i64) -> String {
+    let (n, unit) = match minutes {
+        m if m < 1 => return "just now".to_string(),
+        m if m < 60 => (m, "minute"),
+        m if m < 24 * 60 => (m / 60, "hour"),
+        m => (m / (24 * 60), "day"),
+    };
+    format!("{} {}{} ago", n, unit, if n == 1 { "" } else { "s" })
+}
+
+/// "45 min", "3 h", "2 d"
+pub fn format_age_minutes(minutes: i64) -> String {
+    let m = minutes.max(0);
+    if m < 60 {
+        format!("{} min", m)
+    } else if m < 48 * 60 {
+        format!("{} h", m / 60)
     } else {
-        format!("{} MB", mb)
+        format!("{} d", m / (24 * 60))
+    }
+}
+
+/// MB per GB for decimal figures, as the carrier counts: the SMS total in GB is stored
+/// with 1000 MB per GB, so the whole UI formats with this.
+pub const MB_PER_GB_DECIMAL: f64 = 1000.0;
+
+/// How figures of one GB and more are rounded; below one GB it's always whole MB.
+/// With decimal units:
+///
+/// | MB     | `TrimZero` | `OneDecimal` | `WholeFrom(10.0)` |
+/// |--------|------------|--------------|-------------------|
+/// | 999    | 999 MB     | 999 MB       | 999 MB            |
+/// | 1000   | 1 GB       | 1.0 GB       | 1 GB              |
+/// | 1049   | 1 GB       | 1.0 GB       | 1 GB              |
+/// | 1500   | 1.5 GB     | 1.5 GB       | 1.5 GB            |
+/// | 51200  | 51.2 GB    | 51.2 GB      | 51 GB             |
+/// | 102400 | 102.4 GB   | 102.4 GB     | 102 GB            |
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum GbRounding {
+    /// One decimal, dropped when it rounds to `.0` (so 1049 MB reads "1 GB")
+    #[default]
+    TrimZero,
+    /// Always one decimal, for columns that should line up
+    OneDecimal,
+    /// Nearest whole GB from this many GB up, `TrimZero` below
+    WholeFrom(f64),
+}
+
+/// Format MB as "N MB" below one GB, otherwise as GB rounded per `rounding`.
+pub fn format_megabytes_rounded(mb: f64, mb_per_gb: f64, rounding: GbRounding) -> String {
+    if mb.abs() < mb_per_gb {
+        return format!("{} MB", mb.round() as i32);
+    }
+    let gb = mb / mb_per_gb;
+    let tenths = (gb * 10.0).round() as i64;
+    match rounding {
+        GbRounding::OneDecimal => format!("{:.1} GB", gb),
+        GbRounding::WholeFrom(from) if gb.abs() >= from => format!("{} GB", gb.round() as i64),
+        _ if tenths % 10 == 0 => format!("{} GB", tenths / 10),
+        _ => format!("{:.1} GB", gb),
     }
 }
 
+/// Format MB as "N MB" below one GB, otherwise as GB with at most one decimal;
+/// `mb_per_gb` is `MB_PER_GB_DECIMAL` or 1024 for binary GB.
+pub fn format_megabytes_in(mb: f64, mb_per_gb: f64) -> String {
+    format_megabytes_rounded(mb, mb_per_gb, GbRounding::default())
+}
+
+/// Decimal units (1 GB = 1000 MB).
+pub fn format_megabytes(mb: i32) -> String {
+    format_megabytes_in(mb as f64, MB_PER_GB_DECIMAL)
+}
+
 pub fn format_megabytes_f32(mb: f32) -> String {
-    if mb.abs() >= 1_000.0 {
-        let gb = mb as f64 / 1_000.0;
-        if (gb.fract()).abs() < f64::EPSILON || (gb * 10.0).round() % 10.0 == 0.0 {
-            format!("{} GB", gb.round() as i32)
-        } else {
-            format!("{:.1} GB", gb)
+    format_megabytes_in(mb as f64, MB_PER_GB_DECIMAL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn megabytes_in_binary_and_decimal_units() {
+        assert_eq!(format_megabytes_in(51200.0, 1024.0), "50 GB");
+        assert_eq!(format_megabytes(51200), "51.2 GB");
+        assert_eq!(format_megabytes(50000), "50 GB");
+        assert_eq!(format_megabytes(999), "999 MB");
+    }
+
+    #[test]
+    fn gb_rounding_near_boundaries() {
+        let cases = [
+            (999.0, ["999 MB", "999 MB", "999 MB"]),
+            (1000.0, ["1 GB", "1.0 GB", "1 GB"]),
+            (1049.0, ["1 GB", "1.0 GB", "1 GB"]),
+            (1500.0, ["1.5 GB", "1.5 GB", "1.5 GB"]),
+            (51200.0, ["51.2 GB", "51.2 GB", "51 GB"]),
+            (102400.0, ["102.4 GB", "102.4 GB", "102 GB"]),
+        ];
+        let modes = [
+            GbRounding::TrimZero,
+            GbRounding::OneDecimal,
+            GbRounding::WholeFrom(10.0),
+        ];
+        for (mb, expected) in cases {
+            for (rounding, want) in modes.into_iter().zip(expected) {
+                assert_eq!(
+                    format_megabytes_rounded(mb, MB_PER_GB_DECIMAL, rounding),
+                    want,
+                    "{mb} MB with {rounding:?}"
+                );
+            }
         }
-    } else {
-        format!("{} MB", mb.round() as i32)
+        assert_eq!(format_megabytes(1049), "1 GB");
+    }
+
+    #[test]
+    fn relative_minutes_buckets() {
+        assert_eq!(format_relative_minutes(-5), "just now");
+        assert_eq!(format_relative_minutes(0), "just now");
+        assert_eq!(format_relative_minutes(1), "1 minute ago");
+        assert_eq!(format_relative_minutes(59), "59 minutes ago");
+        assert_eq!(format_relative_minutes(60), "1 hour ago");
+        assert_eq!(format_relative_minutes(24 * 60 - 1), "23 hours ago");
+        assert_eq!(format_relative_minutes(24 * 60), "1 day ago");
+        assert_eq!(format_relative_minutes(5 * 24 * 60 + 90), "5 days ago");
+    }
+
+    #[cfg(not(feature = "web"))]
+    #[test]
+    fn server_relative_is_absolute() {
+        assert_eq!(
+            format_relative("2024-07-01T21:30:00Z", 0.0),
+            "2024-07-01T21:30:00Z"
+        );
+    }
+
+    #[test]
+    fn dates_in_each_locale() {
+        let european = LocaleConfig::default();
+        let iso = LocaleConfig {
+            date_order: DateOrder::YearMonthDay,
+            hour12: false,
+        };
+        let us = LocaleConfig {
+            date_order: DateOrder::MonthDayYear,
+            hour12: true,
+        };
+        assert_eq!(format_date(&european, 2024, 5, 7), "07.05.2024");
+        assert_eq!(format_date(&iso, 2024, 5, 7), "2024-05-07");
+        assert_eq!(format_date(&us, 2024, 5, 7), "05/07/2024");
+        assert_eq!(format_month(&european, 2024, 5), "05.2024");
+        assert_eq!(format_month(&iso, 2024, 5), "2024-05");
+        assert_eq!(format_ymd(&us, "2024-12-31"), "12/31/2024");
+        assert_eq!(format_ymd_month(&us, "2024-12-31"), "12/2024");
+        assert_eq!(format_ymd(&iso, "last week"), "last week");
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn locale_config() {
+        let us = parse_locale("mm/dd/yyyy 12h").unwrap();
+        assert_eq!(us.date_order, DateOrder::MonthDayYear);
+        assert!(us.hour12);
+        assert_eq!(parse_locale("24h").unwrap(), LocaleConfig::default());
+        assert!(parse_locale("dd/mm/yyyy").is_none());
+    }
+
+    #[cfg(any(feature = "web", feature = "server"))]
+    #[test]
+    fn times_in_12h_and_24h() {
+        let european = LocaleConfig::default();
+        let us = LocaleConfig {
+            date_order: DateOrder::MonthDayYear,
+            hour12: true,
+        };
+        assert_eq!(format_time(&european, 0, 5), "00:05");
+        assert_eq!(format_time(&european, 23, 30), "23:30");
+        assert_eq!(format_time(&us, 0, 5), "12:05 AM");
+        assert_eq!(format_time(&us, 12, 0), "12:00 PM");
+        assert_eq!(format_time(&us, 23, 30), "11:30 PM");
+    }
+
+    #[cfg(not(any(feature = "web", feature = "server")))]
+    #[test]
+    fn local_time_without_a_clock_is_unchanged() {
+        let locale = LocaleConfig::default();
+        assert_eq!(
+            format_local("2024-07-01T21:30:00Z", &locale),
+            "2024-07-01T21:30:00Z"
+        );
+    }
+
+    #[cfg(all(feature = "server", not(feature = "web")))]
+    #[test]
+    fn server_times_in_display_tz() {
+        let locale = LocaleConfig::default();
+        let us = LocaleConfig {
+            date_order: DateOrder::MonthDayYear,
+            hour12: true,
+        };
+        // CEST (UTC+2) in summer, CET (UTC+1) in winter
+        let rome = Some(chrono_tz::Europe::Rome);
+        assert_eq!(
+            format_in_tz("2024-07-01T21:30:00Z", &locale, rome),
+            "01.07.2024 23:30"
+        );
+        assert_eq!(
+            format_in_tz("2024-01-15T23:30:00Z", &locale, rome),
+            "16.01.2024 00:30"
+        );
+        // EDT (UTC-4) crosses back to the previous day
+        let new_york = Some(chrono_tz::America::New_York);
+        assert_eq!(
+            format_in_tz("2024-07-02T01:05:00Z", &us, new_york),
+            "07/01/2024 9:05 PM"
+        );
+        assert_eq!(
+            format_in_tz("2024-07-02T01:05:00Z", &locale, None),
+            "2024-07-02T01:05:00Z"
+        );
     }
 }